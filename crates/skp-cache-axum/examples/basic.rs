@@ -1,3 +1,6 @@
+// The router wiring below is commented out, so most items are intentionally unused.
+#![allow(unused)]
+
 use axum::{
     routing::get,
     Router,
@@ -20,7 +20,7 @@ async fn main() {
     // 2. Call `with_state` to transition to `Router<MyCache>`
     // 3. Add routes (handlers are checked against `MyCache`)
     // 4. Add CacheLayer
-    let app = Router::new()
+    let _app = Router::new()
         .with_state(cache.clone())
         .route("/cached", get(cached_handler))
         .route("/manual", get(manual_handler))
@@ -30,7 +30,7 @@ async fn main() {
     println!("Listening on {}", listener.local_addr().unwrap());
     
     // Uncomment to run server:
-    // axum::serve(listener, app).await.unwrap();
+    // axum::serve(listener, _app).await.unwrap();
 }
 
 /// Automatically cached by middleware if headers allow
@@ -11,11 +11,24 @@ use skp_cache_core::MsgPackSerializer;
 #[cfg(feature = "bincode")]
 use skp_cache_core::BincodeSerializer;
 
+#[cfg(feature = "postcard")]
+use skp_cache_core::PostcardSerializer;
+
+#[cfg(feature = "cbor")]
+use skp_cache_core::CborSerializer;
+
+#[cfg(feature = "rkyv")]
+use skp_cache_core::RkyvSerializer;
+
 #[cfg(feature = "compression")]
 use skp_cache_core::ZstdCompressor;
 
 /// Test data structure for benchmarking
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 struct TestData {
     id: u64,
     name: String,
@@ -98,6 +111,36 @@ fn bench_serializers(c: &mut Criterion) {
                 black_box(bytes);
             });
         });
+
+        // Postcard
+        #[cfg(feature = "postcard")]
+        group.bench_with_input(BenchmarkId::new("postcard", name), data, |b, data| {
+            let serializer = PostcardSerializer;
+            b.iter(|| {
+                let bytes = serializer.serialize(black_box(data)).unwrap();
+                black_box(bytes);
+            });
+        });
+
+        // CBOR
+        #[cfg(feature = "cbor")]
+        group.bench_with_input(BenchmarkId::new("cbor", name), data, |b, data| {
+            let serializer = CborSerializer;
+            b.iter(|| {
+                let bytes = serializer.serialize(black_box(data)).unwrap();
+                black_box(bytes);
+            });
+        });
+
+        // rkyv
+        #[cfg(feature = "rkyv")]
+        group.bench_with_input(BenchmarkId::new("rkyv", name), data, |b, data| {
+            let serializer = RkyvSerializer;
+            b.iter(|| {
+                let bytes = serializer.serialize(black_box(data)).unwrap();
+                black_box(bytes);
+            });
+        });
     }
 
     group.finish();
@@ -161,6 +204,65 @@ fn bench_deserializers(c: &mut Criterion) {
                 },
             );
         }
+
+        // Postcard
+        #[cfg(feature = "postcard")]
+        {
+            let postcard_serializer = PostcardSerializer;
+            let postcard_bytes = postcard_serializer.serialize(&data).unwrap();
+            group.throughput(Throughput::Bytes(postcard_bytes.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::new("postcard", name),
+                &postcard_bytes,
+                |b, bytes| {
+                    b.iter(|| {
+                        let result: TestData =
+                            postcard_serializer.deserialize(black_box(bytes)).unwrap();
+                        black_box(result);
+                    });
+                },
+            );
+        }
+
+        // CBOR
+        #[cfg(feature = "cbor")]
+        {
+            let cbor_serializer = CborSerializer;
+            let cbor_bytes = cbor_serializer.serialize(&data).unwrap();
+            group.throughput(Throughput::Bytes(cbor_bytes.len() as u64));
+            group.bench_with_input(BenchmarkId::new("cbor", name), &cbor_bytes, |b, bytes| {
+                b.iter(|| {
+                    let result: TestData = cbor_serializer.deserialize(black_box(bytes)).unwrap();
+                    black_box(result);
+                });
+            });
+        }
+
+        // rkyv: full deserialize vs. validated in-place access
+        #[cfg(feature = "rkyv")]
+        {
+            let rkyv_serializer = RkyvSerializer;
+            let rkyv_bytes = rkyv_serializer.serialize(data).unwrap();
+            group.throughput(Throughput::Bytes(rkyv_bytes.len() as u64));
+            group.bench_with_input(BenchmarkId::new("rkyv", name), &rkyv_bytes, |b, bytes| {
+                b.iter(|| {
+                    let result: TestData = rkyv_serializer.deserialize(black_box(bytes)).unwrap();
+                    black_box(result);
+                });
+            });
+            group.bench_with_input(
+                BenchmarkId::new("rkyv_access", name),
+                &rkyv_bytes,
+                |b, bytes| {
+                    b.iter(|| {
+                        let archived = rkyv_serializer
+                            .access::<TestData>(black_box(bytes))
+                            .unwrap();
+                        black_box(archived.values.len());
+                    });
+                },
+            );
+        }
     }
 
     group.finish();
@@ -311,6 +413,27 @@ fn bench_serialized_size(c: &mut Criterion) {
         println!("Bincode:  {} bytes", bincode_bytes.len());
     }
 
+    #[cfg(feature = "postcard")]
+    {
+        let postcard_serializer = PostcardSerializer;
+        let postcard_bytes = postcard_serializer.serialize(&data).unwrap();
+        println!("Postcard: {} bytes", postcard_bytes.len());
+    }
+
+    #[cfg(feature = "cbor")]
+    {
+        let cbor_serializer = CborSerializer;
+        let cbor_bytes = cbor_serializer.serialize(&data).unwrap();
+        println!("CBOR:     {} bytes", cbor_bytes.len());
+    }
+
+    #[cfg(feature = "rkyv")]
+    {
+        let rkyv_serializer = RkyvSerializer;
+        let rkyv_bytes = rkyv_serializer.serialize(&data).unwrap();
+        println!("rkyv:     {} bytes", rkyv_bytes.len());
+    }
+
     #[cfg(feature = "compression")]
     {
         let compressor = ZstdCompressor::new(3);
@@ -0,0 +1,193 @@
+//! rkyv support for reading cached values without deserializing them
+//!
+//! Archived values are copied once into aligned storage (stored bytes carry
+//! no alignment guarantee), validated and then read in place, so large
+//! read-mostly structs skip the full deserialize that `Serializer` requires.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use rkyv::api::high::{HighDeserializer, HighSerializer, HighValidator};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::rancor;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::util::AlignedVec;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::CacheError;
+
+/// rkyv serializer
+///
+/// Not a `Serializer`: values implement rkyv's `Archive` traits instead of
+/// serde's. Use `RkyvSerializer::access` to read a value without deserializing it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RkyvSerializer;
+
+impl RkyvSerializer {
+    /// Name of the serializer (for debugging/metrics)
+    pub fn name(&self) -> &str {
+        "rkyv"
+    }
+
+    /// Serialize a value to bytes
+    pub fn serialize<T>(&self, value: &T) -> Result<Vec<u8>, CacheError>
+    where
+        T: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    {
+        rkyv::to_bytes::<rancor::Error>(value)
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+
+    /// Validate bytes and return the archived value without deserializing it
+    pub fn access<T>(&self, bytes: &[u8]) -> Result<ArchivedValue<T>, CacheError>
+    where
+        T: Archive,
+        T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    {
+        ArchivedValue::new(bytes)
+    }
+
+    /// Deserialize bytes to a value
+    pub fn deserialize<T>(&self, bytes: &[u8]) -> Result<T, CacheError>
+    where
+        T: Archive,
+        T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>
+            + Deserialize<T, HighDeserializer<rancor::Error>>,
+    {
+        self.access::<T>(bytes)?.deserialize()
+    }
+}
+
+/// A validated archived value
+///
+/// Holds an aligned copy of the archive and dereferences to `T::Archived`,
+/// which can be read without further allocation.
+pub struct ArchivedValue<T: Archive> {
+    /// Aligned copy of the archive; already validated
+    bytes: AlignedVec,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ArchivedValue<T>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+{
+    /// Copy `bytes` into aligned storage and validate them
+    pub fn new(bytes: &[u8]) -> Result<Self, CacheError> {
+        // Stored bytes carry no alignment guarantee
+        let mut aligned = AlignedVec::with_capacity(bytes.len());
+        aligned.extend_from_slice(bytes);
+        rkyv::access::<T::Archived, rancor::Error>(&aligned)
+            .map_err(|e| CacheError::Deserialization(e.to_string()))?;
+        Ok(Self {
+            bytes: aligned,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Archive> ArchivedValue<T> {
+    /// Get the archived value
+    pub fn get(&self) -> &T::Archived {
+        // SAFETY: the bytes were validated as `T::Archived` in `new` and are
+        // never modified afterwards.
+        unsafe { rkyv::access_unchecked::<T::Archived>(&self.bytes) }
+    }
+
+    /// Deserialize into an owned value
+    pub fn deserialize(&self) -> Result<T, CacheError>
+    where
+        T::Archived: Deserialize<T, HighDeserializer<rancor::Error>>,
+    {
+        rkyv::deserialize::<T, rancor::Error>(self.get())
+            .map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+
+    /// Archive size in bytes
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Check if the archive is empty
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl<T: Archive> Deref for ArchivedValue<T> {
+    type Target = T::Archived;
+
+    fn deref(&self) -> &Self::Target {
+        self.get()
+    }
+}
+
+impl<T: Archive> Clone for ArchivedValue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for ArchivedValue<T>
+where
+    T: Archive,
+    T::Archived: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArchivedValue").field(self.get()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Archive, Serialize, Deserialize)]
+    struct Profile {
+        id: u64,
+        name: String,
+        scores: Vec<u32>,
+    }
+
+    fn profile() -> Profile {
+        Profile {
+            id: 7,
+            name: "test".to_string(),
+            scores: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn test_access_without_deserialize() {
+        let serializer = RkyvSerializer;
+        let bytes = serializer.serialize(&profile()).unwrap();
+
+        let archived = serializer.access::<Profile>(&bytes).unwrap();
+        assert_eq!(archived.id, 7);
+        assert_eq!(archived.name, "test");
+        assert_eq!(archived.scores.len(), 3);
+        assert_eq!(archived.deserialize().unwrap(), profile());
+    }
+
+    #[test]
+    fn test_unaligned_bytes() {
+        let serializer = RkyvSerializer;
+        let mut bytes = vec![0];
+        bytes.extend(serializer.serialize(&profile()).unwrap());
+
+        let decoded: Profile = serializer.deserialize(&bytes[1..]).unwrap();
+        assert_eq!(decoded, profile());
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        let result = RkyvSerializer.access::<Profile>(&[0xFF; 3]);
+        assert!(matches!(result, Err(CacheError::Deserialization(_))));
+    }
+}
@@ -5,6 +5,7 @@
 use crate::CacheError;
 
 /// Compression level (1-22, higher = better compression but slower)
+#[cfg(feature = "compression")]
 pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
 
 /// Minimum size threshold for compression (bytes)
@@ -0,0 +1,246 @@
+//! Encryption-at-rest support for cached values
+//!
+//! Provides AEAD encryption (AES-256-GCM or ChaCha20-Poly1305) with key IDs
+//! so keys can be rotated while older values stay readable.
+
+use crate::CacheError;
+
+/// Trait for encryption implementations
+pub trait Encryptor: Send + Sync + Clone + 'static {
+    /// Name of the encryptor
+    fn name(&self) -> &str;
+
+    /// ID of the key used to encrypt new values
+    fn key_id(&self) -> u32;
+
+    /// Encrypt data with the current key
+    ///
+    /// `aad` is authenticated but not encrypted and must be passed again to `decrypt`.
+    fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CacheError>;
+
+    /// Decrypt data that was encrypted with key `key_id`
+    fn decrypt(&self, key_id: u32, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CacheError>;
+}
+
+#[cfg(feature = "encryption")]
+pub use aead_impl::{AeadAlgorithm, AeadEncryptor};
+
+#[cfg(feature = "encryption")]
+mod aead_impl {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::Arc;
+
+    use aes_gcm::Aes256Gcm;
+    use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+    use chacha20poly1305::ChaCha20Poly1305;
+
+    use super::Encryptor;
+    use crate::CacheError;
+
+    /// Nonce length of both supported ciphers
+    const NONCE_LEN: usize = 12;
+
+    /// AEAD cipher used by `AeadEncryptor`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AeadAlgorithm {
+        /// AES-256 in Galois/Counter Mode
+        Aes256Gcm,
+        /// ChaCha20 with a Poly1305 authenticator
+        ChaCha20Poly1305,
+    }
+
+    /// A keyed cipher instance
+    enum Cipher {
+        Aes256Gcm(Box<Aes256Gcm>),
+        ChaCha20Poly1305(Box<ChaCha20Poly1305>),
+    }
+
+    impl Cipher {
+        fn new(algorithm: AeadAlgorithm, key: &[u8; 32]) -> Self {
+            match algorithm {
+                AeadAlgorithm::Aes256Gcm => Cipher::Aes256Gcm(Box::new(Aes256Gcm::new(key.into()))),
+                AeadAlgorithm::ChaCha20Poly1305 => {
+                    Cipher::ChaCha20Poly1305(Box::new(ChaCha20Poly1305::new(key.into())))
+                }
+            }
+        }
+
+        /// Returns `nonce || ciphertext || tag`
+        fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CacheError> {
+            let payload = Payload {
+                msg: plaintext,
+                aad,
+            };
+            let (nonce, ciphertext) = match self {
+                Cipher::Aes256Gcm(cipher) => {
+                    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+                    (nonce.to_vec(), cipher.encrypt(&nonce, payload))
+                }
+                Cipher::ChaCha20Poly1305(cipher) => {
+                    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+                    (nonce.to_vec(), cipher.encrypt(&nonce, payload))
+                }
+            };
+            let ciphertext =
+                ciphertext.map_err(|_| CacheError::Encryption("encryption failed".into()))?;
+
+            let mut out = nonce;
+            out.extend_from_slice(&ciphertext);
+            Ok(out)
+        }
+
+        fn decrypt(&self, data: &[u8], aad: &[u8]) -> Result<Vec<u8>, CacheError> {
+            if data.len() < NONCE_LEN {
+                return Err(CacheError::Decryption("ciphertext too short".into()));
+            }
+            let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+            let payload = Payload {
+                msg: ciphertext,
+                aad,
+            };
+            match self {
+                Cipher::Aes256Gcm(cipher) => cipher.decrypt(nonce.into(), payload),
+                Cipher::ChaCha20Poly1305(cipher) => cipher.decrypt(nonce.into(), payload),
+            }
+            .map_err(|_| CacheError::Decryption("authentication failed".into()))
+        }
+    }
+
+    /// AEAD encryptor with a key ring for rotation
+    ///
+    /// New values are encrypted with the active key; values encrypted with any
+    /// other key in the ring can still be decrypted. To rotate, add the new key
+    /// with `with_active_key` and keep the old one via `with_key` until entries
+    /// written with it have expired.
+    #[derive(Clone)]
+    pub struct AeadEncryptor {
+        algorithm: AeadAlgorithm,
+        active: u32,
+        keys: HashMap<u32, Arc<Cipher>>,
+    }
+
+    impl AeadEncryptor {
+        /// Create an encryptor with a single 256-bit key
+        pub fn new(algorithm: AeadAlgorithm, key_id: u32, key: [u8; 32]) -> Self {
+            let mut keys = HashMap::new();
+            keys.insert(key_id, Arc::new(Cipher::new(algorithm, &key)));
+            Self {
+                algorithm,
+                active: key_id,
+                keys,
+            }
+        }
+
+        /// Add a key that is only used for decryption
+        pub fn with_key(mut self, key_id: u32, key: [u8; 32]) -> Self {
+            self.keys
+                .insert(key_id, Arc::new(Cipher::new(self.algorithm, &key)));
+            self
+        }
+
+        /// Add a key and use it to encrypt new values
+        pub fn with_active_key(mut self, key_id: u32, key: [u8; 32]) -> Self {
+            self = self.with_key(key_id, key);
+            self.active = key_id;
+            self
+        }
+
+        /// Get the cipher
+        pub fn algorithm(&self) -> AeadAlgorithm {
+            self.algorithm
+        }
+    }
+
+    impl fmt::Debug for AeadEncryptor {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut key_ids: Vec<_> = self.keys.keys().collect();
+            key_ids.sort();
+            f.debug_struct("AeadEncryptor")
+                .field("algorithm", &self.algorithm)
+                .field("active", &self.active)
+                .field("key_ids", &key_ids)
+                .finish()
+        }
+    }
+
+    impl Encryptor for AeadEncryptor {
+        fn name(&self) -> &str {
+            match self.algorithm {
+                AeadAlgorithm::Aes256Gcm => "aes-256-gcm",
+                AeadAlgorithm::ChaCha20Poly1305 => "chacha20-poly1305",
+            }
+        }
+
+        fn key_id(&self) -> u32 {
+            self.active
+        }
+
+        fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CacheError> {
+            self.keys[&self.active].encrypt(plaintext, aad)
+        }
+
+        fn decrypt(
+            &self,
+            key_id: u32,
+            ciphertext: &[u8],
+            aad: &[u8],
+        ) -> Result<Vec<u8>, CacheError> {
+            self.keys
+                .get(&key_id)
+                .ok_or_else(|| CacheError::Decryption(format!("unknown key id {}", key_id)))?
+                .decrypt(ciphertext, aad)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for algorithm in [AeadAlgorithm::Aes256Gcm, AeadAlgorithm::ChaCha20Poly1305] {
+            let encryptor = AeadEncryptor::new(algorithm, 1, [7; 32]);
+            let ciphertext = encryptor.encrypt(b"secret", b"header").unwrap();
+            assert_ne!(&ciphertext[..], b"secret");
+
+            let plaintext = encryptor.decrypt(1, &ciphertext, b"header").unwrap();
+            assert_eq!(plaintext, b"secret");
+        }
+    }
+
+    #[test]
+    fn test_tampered_data_fails() {
+        let encryptor = AeadEncryptor::new(AeadAlgorithm::Aes256Gcm, 1, [7; 32]);
+        let mut ciphertext = encryptor.encrypt(b"secret", b"header").unwrap();
+
+        assert!(matches!(
+            encryptor.decrypt(1, &ciphertext, b"other"),
+            Err(CacheError::Decryption(_))
+        ));
+
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 1;
+        assert!(matches!(
+            encryptor.decrypt(1, &ciphertext, b"header"),
+            Err(CacheError::Decryption(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old = AeadEncryptor::new(AeadAlgorithm::ChaCha20Poly1305, 1, [1; 32]);
+        let ciphertext = old.encrypt(b"secret", b"").unwrap();
+
+        let rotated = old.clone().with_active_key(2, [2; 32]);
+        assert_eq!(rotated.key_id(), 2);
+        assert_eq!(rotated.decrypt(1, &ciphertext, b"").unwrap(), b"secret");
+
+        let new = rotated.encrypt(b"secret", b"").unwrap();
+        assert!(matches!(
+            old.decrypt(2, &new, b""),
+            Err(CacheError::Decryption(_))
+        ));
+    }
+}
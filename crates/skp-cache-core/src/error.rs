@@ -53,11 +53,35 @@ pub enum CacheError {
     #[error("decompression error: {0}")]
     Decompression(String),
 
+    /// Encryption failed
+    #[error("encryption error: {0}")]
+    Encryption(String),
+
+    /// Decryption failed (wrong or unknown key, or tampered data)
+    #[error("decryption error: {0}")]
+    Decryption(String),
+
     /// Timeout
     #[error("operation timed out")]
     Timeout,
 }
 
+impl CacheError {
+    /// Check whether the error comes from the backend being unreachable,
+    /// slow or failing, rather than from the request itself
+    ///
+    /// Such errors trip circuit breakers and are worth retrying.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            CacheError::Connection(_)
+                | CacheError::Backend(_)
+                | CacheError::Timeout
+                | CacheError::Internal(_)
+        )
+    }
+}
+
 /// Result type alias for cache operations
 pub type Result<T> = std::result::Result<T, CacheError>;
 
@@ -86,4 +110,18 @@ mod tests {
         let cloned = err.clone();
         assert_eq!(err.to_string(), cloned.to_string());
     }
+
+    #[test]
+    fn test_error_is_transient() {
+        assert!(CacheError::Timeout.is_transient());
+        assert!(CacheError::Connection("refused".into()).is_transient());
+        assert!(!CacheError::NotFound("key".into()).is_transient());
+        assert!(
+            !CacheError::VersionMismatch {
+                expected: 1,
+                actual: 2
+            }
+            .is_transient()
+        );
+    }
 }
@@ -3,15 +3,31 @@
 //! This crate provides the foundational types and traits used throughout
 //! the skp-cache ecosystem.
 
+#[cfg(feature = "rkyv")]
+mod archive;
 mod compression;
+mod encryption;
 mod error;
+mod pattern;
 mod traits;
 mod types;
 
 pub use compression::{Compressor, NoopCompressor};
+pub use encryption::Encryptor;
 pub use error::{CacheError, Result};
+pub use pattern::KeyPattern;
 pub use traits::*;
 pub use types::*;
 
 #[cfg(feature = "compression")]
 pub use compression::ZstdCompressor;
+
+#[cfg(feature = "encryption")]
+pub use encryption::{AeadAlgorithm, AeadEncryptor};
+
+#[cfg(feature = "rkyv")]
+pub use archive::{ArchivedValue, RkyvSerializer};
+
+/// rkyv version used by `RkyvSerializer`, for deriving `Archive` on cached types
+#[cfg(feature = "rkyv")]
+pub use rkyv;
@@ -0,0 +1,220 @@
+//! Glob patterns over cache keys
+//!
+//! Uses the Redis `KEYS`/`SCAN MATCH` syntax so a pattern selects the same
+//! keys on every backend:
+//!
+//! - `*` matches any sequence of characters, including none
+//! - `?` matches exactly one character
+//! - `[abc]`, `[a-z]` and `[^a-z]` match one character from (or not from) a set
+//! - `\` escapes the next character
+
+use std::fmt;
+
+/// A glob pattern over cache keys
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyPattern {
+    pattern: String,
+    chars: Vec<char>,
+}
+
+impl KeyPattern {
+    /// Parse a pattern (any string is a valid pattern)
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let chars = pattern.chars().collect();
+        Self { pattern, chars }
+    }
+
+    /// Get the pattern text
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Check whether `key` matches the pattern
+    pub fn matches(&self, key: &str) -> bool {
+        let pattern = &self.chars;
+        let key: Vec<char> = key.chars().collect();
+        let (mut p, mut k) = (0, 0);
+        // Pattern position after the last `*` and the key position it was tried at
+        let mut star: Option<(usize, usize)> = None;
+
+        loop {
+            if pattern.get(p) == Some(&'*') {
+                p += 1;
+                star = Some((p, k));
+                continue;
+            }
+            let Some(&c) = key.get(k) else {
+                break;
+            };
+            if let Some(next) = self.step(p, c) {
+                p = next;
+                k += 1;
+                continue;
+            }
+            // Let the last `*` absorb one more character
+            match star {
+                Some((star_p, star_k)) => {
+                    p = star_p;
+                    k = star_k + 1;
+                    star = Some((star_p, k));
+                }
+                None => return false,
+            }
+        }
+
+        p == pattern.len()
+    }
+
+    /// Longest literal text every matching key starts with
+    pub fn literal_prefix(&self) -> String {
+        let mut prefix = String::new();
+        let mut chars = self.chars.iter();
+        while let Some(&c) = chars.next() {
+            match c {
+                '*' | '?' | '[' => break,
+                '\\' => prefix.push(chars.next().copied().unwrap_or('\\')),
+                c => prefix.push(c),
+            }
+        }
+        prefix
+    }
+
+    /// Escape `literal` so it only matches itself
+    pub fn escape(literal: &str) -> String {
+        let mut escaped = String::with_capacity(literal.len());
+        for c in literal.chars() {
+            if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    /// Match one key character against the (non-`*`) element at `p`
+    ///
+    /// Returns the position of the next pattern element on a match.
+    fn step(&self, p: usize, c: char) -> Option<usize> {
+        let pattern = &self.chars;
+        match *pattern.get(p)? {
+            '?' => Some(p + 1),
+            '\\' => match pattern.get(p + 1) {
+                Some(&escaped) => (escaped == c).then_some(p + 2),
+                None => (c == '\\').then_some(p + 1),
+            },
+            '[' => match self.class(p, c) {
+                Some((matched, next)) => matched.then_some(next),
+                // Unterminated class: a literal `[`
+                None => (c == '[').then_some(p + 1),
+            },
+            literal => (literal == c).then_some(p + 1),
+        }
+    }
+
+    /// Match `c` against the class starting at `p`
+    ///
+    /// Returns whether it matched and the position after the closing `]`, or
+    /// `None` if the class is not terminated.
+    fn class(&self, p: usize, c: char) -> Option<(bool, usize)> {
+        let pattern = &self.chars;
+        let mut i = p + 1;
+        let negate = pattern.get(i) == Some(&'^');
+        if negate {
+            i += 1;
+        }
+
+        let mut matched = false;
+        loop {
+            match *pattern.get(i)? {
+                ']' => return Some((matched != negate, i + 1)),
+                '\\' => {
+                    matched |= *pattern.get(i + 1)? == c;
+                    i += 2;
+                }
+                start => match (pattern.get(i + 1), pattern.get(i + 2)) {
+                    (Some('-'), Some(&end)) if end != ']' => {
+                        let (low, high) = if start <= end {
+                            (start, end)
+                        } else {
+                            (end, start)
+                        };
+                        matched |= (low..=high).contains(&c);
+                        i += 3;
+                    }
+                    _ => {
+                        matched |= start == c;
+                        i += 1;
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl fmt::Debug for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KeyPattern").field(&self.pattern).finish()
+    }
+}
+
+impl fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+impl From<&str> for KeyPattern {
+    fn from(pattern: &str) -> Self {
+        Self::new(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, key: &str) -> bool {
+        KeyPattern::new(pattern).matches(key)
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert!(matches("user:*:posts", "user:1:posts"));
+        assert!(matches("user:*:posts", "user::posts"));
+        assert!(matches("user:*:posts", "user:1:2:posts"));
+        assert!(!matches("user:*:posts", "user:1:posts:2"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXbYbZ"));
+        assert!(matches("user:?", "user:1"));
+        assert!(!matches("user:?", "user:12"));
+        assert!(matches("ü?", "üß"));
+    }
+
+    #[test]
+    fn test_classes_and_escapes() {
+        assert!(matches("user:[12]", "user:2"));
+        assert!(!matches("user:[12]", "user:3"));
+        assert!(matches("user:[a-c]", "user:b"));
+        assert!(matches("user:[^a-c]", "user:d"));
+        assert!(!matches("user:[^a-c]", "user:a"));
+        assert!(matches("user:[", "user:["));
+        assert!(matches(r"a\*", "a*"));
+        assert!(!matches(r"a\*", "ab"));
+        assert!(matches(r"[\]]", "]"));
+    }
+
+    #[test]
+    fn test_literal_prefix_and_escape() {
+        assert_eq!(KeyPattern::new("user:*:posts").literal_prefix(), "user:");
+        assert_eq!(KeyPattern::new(r"a\*b?").literal_prefix(), "a*b");
+        assert_eq!(KeyPattern::new("[ab]").literal_prefix(), "");
+
+        let literal = "ns[1]*?";
+        let escaped = KeyPattern::escape(literal);
+        assert!(matches(&escaped, literal));
+        assert!(!matches(&escaped, "ns1x?"));
+        assert_eq!(KeyPattern::new(escaped).literal_prefix(), literal);
+    }
+}
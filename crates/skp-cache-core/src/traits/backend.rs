@@ -1,6 +1,7 @@
 //! Cache backend trait
 
 use async_trait::async_trait;
+use std::collections::{HashSet, VecDeque};
 use crate::{CacheEntry, CacheError, CacheOptions, CacheStats};
 
 /// Core trait for all cache storage backends
@@ -74,6 +75,17 @@ pub trait TaggableBackend: CacheBackend {
     async fn delete_by_tag(&self, tag: &str) -> Result<u64, CacheError>;
 }
 
+/// Extended trait for backends that can delete keys matching a glob pattern
+///
+/// Patterns use the `KeyPattern` syntax (`*`, `?`, `[...]`, `\` escapes).
+#[async_trait]
+pub trait PatternBackend: CacheBackend {
+    /// Delete all entries whose key matches `pattern`
+    ///
+    /// Returns the number of entries deleted.
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<u64, CacheError>;
+}
+
 /// Extended trait for backends that support dependency tracking
 #[async_trait]
 pub trait DependencyBackend: CacheBackend {
@@ -81,6 +93,44 @@ pub trait DependencyBackend: CacheBackend {
     /// 
     /// If key `A` depends on key `B`, then `get_dependents("B")` should return `["A"]`.
     async fn get_dependents(&self, key: &str) -> Result<Vec<String>, CacheError>;
+
+    /// Delete `key` and every key that transitively depends on it
+    ///
+    /// Returns the deleted keys in breadth-first order, starting with `key`
+    /// if it was present. The default walks the graph with one
+    /// `get_dependents` and one `delete` per key; backends can override it
+    /// with a single atomic operation.
+    async fn delete_cascade(&self, key: &str) -> Result<Vec<String>, CacheError> {
+        delete_cascade_walk(self, key).await
+    }
+}
+
+/// Default `DependencyBackend::delete_cascade`: a breadth-first walk from `key`
+///
+/// Each key is visited once, so dependency cycles terminate.
+pub async fn delete_cascade_walk<B>(backend: &B, key: &str) -> Result<Vec<String>, CacheError>
+where
+    B: DependencyBackend + ?Sized,
+{
+    let mut queue = VecDeque::from([key.to_string()]);
+    let mut visited = HashSet::from([key.to_string()]);
+    let mut deleted = Vec::new();
+
+    while let Some(k) = queue.pop_front() {
+        // Dependents first, so they are still known once `k` is gone
+        if let Ok(dependents) = backend.get_dependents(&k).await {
+            for dependent in dependents {
+                if visited.insert(dependent.clone()) {
+                    queue.push_back(dependent);
+                }
+            }
+        }
+        if backend.delete(&k).await? {
+            deleted.push(k);
+        }
+    }
+
+    Ok(deleted)
 }
 
 /// Extended trait for distributed backends
@@ -59,6 +59,8 @@ pub enum EvictionReason {
     Replaced,
     /// Dependency was invalidated
     DependencyInvalidated,
+    /// Stored with a different serializer, compressor or schema version
+    FormatMismatch,
 }
 
 impl EvictionReason {
@@ -70,6 +72,7 @@ impl EvictionReason {
             EvictionReason::Invalidated => "invalidated",
             EvictionReason::Replaced => "replaced",
             EvictionReason::DependencyInvalidated => "dependency",
+            EvictionReason::FormatMismatch => "format_mismatch",
         }
     }
 }
@@ -87,6 +90,13 @@ pub trait CacheMetrics: Send + Sync + 'static {
     /// Record a stale hit (served stale while revalidating)
     fn record_stale_hit(&self, key: &str);
 
+    /// Record a hit on a negative entry (the key is known to have no value)
+    ///
+    /// Does nothing by default.
+    fn record_negative_hit(&self, key: &str) {
+        let _ = key;
+    }
+
     /// Record operation latency
     fn record_latency(&self, operation: CacheOperation, duration: Duration);
 
@@ -95,6 +105,34 @@ pub trait CacheMetrics: Send + Sync + 'static {
 
     /// Record cache size
     fn record_size(&self, size: usize, memory_bytes: usize);
+
+    /// Record a switch of the backend to a new primary after a failover
+    ///
+    /// `primary` is the new primary's address. Does nothing by default.
+    fn record_failover(&self, primary: &str) {
+        let _ = primary;
+    }
+
+    /// Record the number of writes waiting to be flushed to L2
+    ///
+    /// Does nothing by default.
+    fn record_write_queue_depth(&self, depth: usize) {
+        let _ = depth;
+    }
+
+    /// Record a write that was dropped before reaching L2
+    ///
+    /// Does nothing by default.
+    fn record_dropped_write(&self, key: &str) {
+        let _ = key;
+    }
+
+    /// Record an operation handled without its remote tier
+    ///
+    /// Does nothing by default.
+    fn record_degraded(&self, operation: CacheOperation) {
+        let _ = operation;
+    }
 }
 
 /// No-op metrics implementation (default)
@@ -171,6 +209,10 @@ impl CacheMetrics for MetricsCrateAdapter {
         metrics::counter!(self.metric_name("stale_hits_total")).increment(1);
     }
 
+    fn record_negative_hit(&self, _key: &str) {
+        metrics::counter!(self.metric_name("negative_hits_total")).increment(1);
+    }
+
     fn record_latency(&self, operation: CacheOperation, duration: Duration) {
         metrics::histogram!(
             self.metric_name("operation_duration_seconds"),
@@ -191,6 +233,26 @@ impl CacheMetrics for MetricsCrateAdapter {
         metrics::gauge!(self.metric_name("entries")).set(size as f64);
         metrics::gauge!(self.metric_name("memory_bytes")).set(memory_bytes as f64);
     }
+
+    fn record_failover(&self, _primary: &str) {
+        metrics::counter!(self.metric_name("failovers_total")).increment(1);
+    }
+
+    fn record_write_queue_depth(&self, depth: usize) {
+        metrics::gauge!(self.metric_name("write_queue_depth")).set(depth as f64);
+    }
+
+    fn record_dropped_write(&self, _key: &str) {
+        metrics::counter!(self.metric_name("dropped_writes_total")).increment(1);
+    }
+
+    fn record_degraded(&self, operation: CacheOperation) {
+        metrics::counter!(
+            self.metric_name("degraded_operations_total"),
+            "operation" => operation.as_str()
+        )
+        .increment(1);
+    }
 }
 
 #[cfg(test)]
@@ -221,7 +283,12 @@ mod tests {
         // Just verify these don't panic
         metrics.record_hit("key", CacheTier::L1Memory);
         metrics.record_miss("key");
+        metrics.record_negative_hit("key");
         metrics.record_latency(CacheOperation::Get, Duration::from_millis(1));
+        metrics.record_failover("10.0.0.2:6379");
+        metrics.record_write_queue_depth(3);
+        metrics.record_dropped_write("key");
+        metrics.record_degraded(CacheOperation::Get);
     }
 }
 
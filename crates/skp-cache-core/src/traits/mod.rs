@@ -5,7 +5,10 @@ mod key;
 mod metrics;
 mod serializer;
 
-pub use backend::{CacheBackend, DependencyBackend, DistributedBackend, TaggableBackend};
+pub use backend::{
+    delete_cascade_walk, CacheBackend, DependencyBackend, DistributedBackend, PatternBackend,
+    TaggableBackend,
+};
 pub use key::{CacheKey, CompositeKey};
 pub use metrics::{CacheMetrics, CacheOperation, CacheTier, EvictionReason, NoopMetrics};
 pub use serializer::{JsonSerializer, Serializer};
@@ -16,6 +19,12 @@ pub use serializer::MsgPackSerializer;
 #[cfg(feature = "bincode")]
 pub use serializer::BincodeSerializer;
 
+#[cfg(feature = "postcard")]
+pub use serializer::PostcardSerializer;
+
+#[cfg(feature = "cbor")]
+pub use serializer::CborSerializer;
+
 #[cfg(feature = "metrics")]
 pub use metrics::MetricsCrateAdapter;
 
@@ -6,7 +6,7 @@ use serde::{de::DeserializeOwned, Serialize};
 /// Trait for pluggable serialization formats
 ///
 /// Implement this trait to add custom serialization formats.
-/// Built-in implementations: JSON, MessagePack, Bincode.
+/// Built-in implementations: JSON, MessagePack, Bincode, Postcard, CBOR.
 pub trait Serializer: Send + Sync + Clone + 'static {
     /// Name of the serializer (for debugging/metrics)
     fn name(&self) -> &str;
@@ -87,6 +87,55 @@ impl Serializer for BincodeSerializer {
     }
 }
 
+/// Postcard serializer (optional)
+///
+/// Compact varint-based format with a stable wire spec, suited to small structs.
+/// Enable with `postcard` feature.
+#[cfg(feature = "postcard")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostcardSerializer;
+
+#[cfg(feature = "postcard")]
+impl Serializer for PostcardSerializer {
+    fn name(&self) -> &str {
+        "postcard"
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CacheError> {
+        postcard::to_stdvec(value).map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CacheError> {
+        postcard::from_bytes(bytes).map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+}
+
+/// CBOR serializer (optional)
+///
+/// Self-describing binary format (RFC 8949), readable from most languages.
+/// Enable with `cbor` feature.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborSerializer;
+
+#[cfg(feature = "cbor")]
+impl Serializer for CborSerializer {
+    fn name(&self) -> &str {
+        "cbor"
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CacheError> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes)
+            .map_err(|e| CacheError::Serialization(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CacheError> {
+        ciborium::from_reader(bytes).map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,4 +175,28 @@ mod tests {
     fn test_json_serializer_name() {
         assert_eq!(JsonSerializer.name(), "json");
     }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn test_postcard_roundtrip() {
+        let serializer = PostcardSerializer;
+        let value = (42u64, "test".to_string(), vec![1, 2, 3]);
+
+        let bytes = serializer.serialize(&value).unwrap();
+        let decoded: (u64, String, Vec<i32>) = serializer.deserialize(&bytes).unwrap();
+
+        assert_eq!(value, decoded);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_roundtrip() {
+        let serializer = CborSerializer;
+        let value = (42u64, "test".to_string(), vec![1, 2, 3]);
+
+        let bytes = serializer.serialize(&value).unwrap();
+        let decoded: (u64, String, Vec<i32>) = serializer.deserialize(&bytes).unwrap();
+
+        assert_eq!(value, decoded);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{CacheMetrics, CacheOperation, CacheTier, EvictionReason};
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::debug;
 
 /// Metrics adapter that logs events via `tracing`
 #[derive(Debug, Clone, Default)]
@@ -54,6 +54,16 @@ impl CacheMetrics for TracingMetrics {
         );
     }
 
+    fn record_negative_hit(&self, key: &str) {
+        debug!(
+            target: "skp_cache",
+            event = "negative_hit",
+            key = %key,
+            service = ?self.service_name,
+            "Cache Negative Hit"
+        );
+    }
+
     fn record_latency(&self, operation: CacheOperation, duration: Duration) {
         tracing::trace!(
             target: "skp_cache",
@@ -85,4 +95,44 @@ impl CacheMetrics for TracingMetrics {
             "Cache Size Update"
         );
     }
+
+    fn record_failover(&self, primary: &str) {
+        tracing::warn!(
+            target: "skp_cache",
+            event = "failover",
+            primary = %primary,
+            service = ?self.service_name,
+            "Cache Backend Failover"
+        );
+    }
+
+    fn record_write_queue_depth(&self, depth: usize) {
+        tracing::trace!(
+            target: "skp_cache",
+            event = "write_queue",
+            depth = depth,
+            service = ?self.service_name,
+            "Cache Write Queue Depth"
+        );
+    }
+
+    fn record_dropped_write(&self, key: &str) {
+        tracing::warn!(
+            target: "skp_cache",
+            event = "dropped_write",
+            key = %key,
+            service = ?self.service_name,
+            "Cache Write Dropped"
+        );
+    }
+
+    fn record_degraded(&self, operation: CacheOperation) {
+        tracing::warn!(
+            target: "skp_cache",
+            event = "degraded",
+            operation = ?operation,
+            service = ?self.service_name,
+            "Cache Operation Degraded"
+        );
+    }
 }
@@ -24,12 +24,18 @@ pub struct CacheEntry<T> {
     pub dependencies: Vec<String>,
     /// Computation cost
     pub cost: u64,
+    /// Time the value took to compute, used to schedule early refresh
+    #[serde(default)]
+    pub compute_time: Option<Duration>,
     /// Size in bytes
     pub size: usize,
     /// ETag for HTTP caching
     pub etag: Option<String>,
     /// Version for optimistic concurrency
     pub version: u64,
+    /// Negative entry: the key is known to be missing from the source
+    #[serde(default)]
+    pub negative: bool,
 }
 
 impl<T> CacheEntry<T> {
@@ -46,9 +52,11 @@ impl<T> CacheEntry<T> {
             tags: Vec::new(),
             dependencies: Vec::new(),
             cost: 1,
+            compute_time: None,
             size,
             etag: None,
             version: 0,
+            negative: false,
         }
     }
 
@@ -2,6 +2,9 @@
 
 use std::time::Duration;
 
+use super::entry::CacheEntry;
+use crate::error::{CacheError, Result};
+
 /// Configuration options for a cache entry
 #[derive(Debug, Clone, Default)]
 pub struct CacheOptions {
@@ -15,6 +18,8 @@ pub struct CacheOptions {
     pub dependencies: Vec<String>,
     /// Computation cost (for cost-aware eviction)
     pub cost: Option<u64>,
+    /// Time the value took to compute (for early refresh)
+    pub compute_time: Option<Duration>,
     /// Enable early refresh
     pub early_refresh: bool,
     /// Enable request coalescing
@@ -25,6 +30,36 @@ pub struct CacheOptions {
     pub negative: bool,
     /// Conditional set: only if version matches
     pub if_version: Option<u64>,
+    /// Store with exactly this version instead of the next one
+    ///
+    /// Used when copying entries between tiers; backends otherwise assign
+    /// the previous version plus one.
+    pub version: Option<u64>,
+}
+
+impl CacheOptions {
+    /// Check `if_version` against the entry currently stored under the key
+    ///
+    /// Missing and expired entries count as version 0.
+    pub fn check_version<T>(&self, current: Option<&CacheEntry<T>>) -> Result<()> {
+        let Some(expected) = self.if_version else {
+            return Ok(());
+        };
+        let actual = current
+            .filter(|entry| !entry.is_expired() || entry.is_stale())
+            .map_or(0, |entry| entry.version);
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(CacheError::VersionMismatch { expected, actual })
+        }
+    }
+
+    /// Version for an entry replacing `current`
+    pub fn next_version<T>(&self, current: Option<&CacheEntry<T>>) -> u64 {
+        self.version
+            .unwrap_or_else(|| current.map_or(0, |entry| entry.version) + 1)
+    }
 }
 
 /// Builder for CacheOptions with fluent API
@@ -176,6 +211,25 @@ mod tests {
         assert!(opts.early_refresh);
     }
 
+    #[test]
+    fn test_check_version() {
+        let mut entry = CacheEntry::new(1, 1);
+        entry.version = 3;
+
+        let opts = CacheOpts::new().if_version(3).build();
+        assert!(opts.check_version(Some(&entry)).is_ok());
+        assert!(opts.check_version::<i32>(None).is_err());
+        assert_eq!(opts.next_version(Some(&entry)), 4);
+
+        // Absent keys are version 0
+        let opts = CacheOpts::new().if_version(0).build();
+        assert!(opts.check_version::<i32>(None).is_ok());
+        assert_eq!(opts.next_version::<i32>(None), 1);
+
+        // Unconditional writes always pass
+        assert!(CacheOptions::default().check_version(Some(&entry)).is_ok());
+    }
+
     #[test]
     fn test_from_duration() {
         let opts: CacheOptions = Duration::from_secs(300).into();
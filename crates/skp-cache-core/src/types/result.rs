@@ -26,6 +26,11 @@ impl<T> CacheResult<T> {
         matches!(self, CacheResult::Miss)
     }
 
+    /// Check if this is a negative hit (key known to be missing)
+    pub fn is_negative(&self) -> bool {
+        matches!(self, CacheResult::NegativeHit)
+    }
+
     /// Check if result is usable (hit or stale)
     pub fn is_usable(&self) -> bool {
         matches!(self, CacheResult::Hit(_) | CacheResult::Stale(_))
@@ -68,9 +73,11 @@ impl<T> CacheResult<T> {
                 tags: entry.tags,
                 dependencies: entry.dependencies,
                 cost: entry.cost,
+                compute_time: entry.compute_time,
                 size: entry.size,
                 etag: entry.etag,
                 version: entry.version,
+                negative: entry.negative,
             }),
             CacheResult::Stale(entry) => CacheResult::Stale(CacheEntry {
                 value: f(entry.value),
@@ -82,9 +89,11 @@ impl<T> CacheResult<T> {
                 tags: entry.tags,
                 dependencies: entry.dependencies,
                 cost: entry.cost,
+                compute_time: entry.compute_time,
                 size: entry.size,
                 etag: entry.etag,
                 version: entry.version,
+                negative: entry.negative,
             }),
             CacheResult::Miss => CacheResult::Miss,
             CacheResult::NegativeHit => CacheResult::NegativeHit,
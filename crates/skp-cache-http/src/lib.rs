@@ -8,6 +8,7 @@ pub use policy::HttpCachePolicy;
 
 #[cfg(test)]
 mod tests {
+    #[allow(unused_imports)]
     use super::*;
     
     // Logic is tested in modules
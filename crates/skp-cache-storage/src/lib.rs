@@ -4,16 +4,23 @@
 pub mod memory;
 
 #[cfg(feature = "memory")]
-pub use memory::{MemoryBackend, MemoryConfig};
+pub use memory::{EvictionPolicy, KeyFilter, MemoryBackend, MemoryConfig, Weigher};
 
 #[cfg(feature = "redis")]
 pub mod redis;
 
 #[cfg(feature = "redis")]
-pub use redis::{RedisBackend, RedisConfig};
+pub use redis::{EntryEncoding, RedisBackend, RedisConfig, TrackingMode};
+
+pub mod resilient;
+
+#[cfg(any(feature = "memory", feature = "redis"))]
+mod periodic;
+
+pub use resilient::{OperationTimeouts, ResilientBackend, RetryPolicy};
 
 #[cfg(feature = "multitier")]
 pub mod multitier;
 
 #[cfg(feature = "multitier")]
-pub use multitier::{MultiTierBackend, CircuitBreaker};
+pub use multitier::{MultiTierBackend, CircuitBreaker, DegradationPolicy, DegradedMode, WriteBehindConfig};
@@ -2,35 +2,125 @@
 
 use async_trait::async_trait;
 use dashmap::DashMap;
-use parking_lot::RwLock;
+use dashmap::mapref::entry::Entry;
+use parking_lot::{Mutex, RwLock};
 use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
-use skp_cache_core::{CacheBackend, CacheEntry, CacheOptions, CacheStats, DependencyBackend, Result, TaggableBackend};
+use skp_cache_core::{
+    CacheBackend, CacheEntry, CacheError, CacheMetrics, CacheOptions, CacheStats, DependencyBackend,
+    EvictionReason, KeyPattern, NoopMetrics, PatternBackend, Result, TaggableBackend,
+};
 
+use crate::periodic::PeriodicTask;
+
+use super::bloom::KeyFilter;
+use super::eviction::{AccessTracker, EvictionPolicy, EvictionState};
+use super::prefix_index::PrefixIndex;
 use super::ttl_index::TtlIndex;
 
+/// Entry count assumed when sizing eviction structures for a cache bounded only by bytes
+const DEFAULT_ESTIMATED_ENTRIES: usize = 10_000;
+
+/// Tick length of the TTL index
+const TTL_RESOLUTION: Duration = Duration::from_millis(1);
+
+/// Computes the weight (in bytes) charged against `MemoryConfig::max_memory_bytes`
+///
+/// Called with the key and the stored value bytes on insert and again on removal,
+/// so it must return the same weight for the same input.
+#[derive(Clone)]
+pub struct Weigher(Arc<WeighFn>);
+
+/// Weight function signature: `(key, value) -> bytes`
+type WeighFn = dyn Fn(&str, &[u8]) -> usize + Send + Sync;
+
+impl Weigher {
+    /// Create a weigher from a closure
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&str, &[u8]) -> usize + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Compute the weight of an entry
+    pub fn weigh(&self, key: &str, value: &[u8]) -> usize {
+        (self.0)(key, value)
+    }
+}
+
+impl Default for Weigher {
+    /// Key length plus value length
+    fn default() -> Self {
+        Self::new(|key, value| key.len() + value.len())
+    }
+}
+
+impl fmt::Debug for Weigher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Weigher")
+    }
+}
+
 /// Configuration for the memory backend
 #[derive(Debug, Clone)]
 pub struct MemoryConfig {
     /// Maximum number of entries (0 = unlimited)
     pub max_capacity: usize,
+    /// Maximum total weight of all entries in bytes (0 = unlimited)
+    ///
+    /// Setting an entry heavier than the whole budget fails with
+    /// `CacheError::CapacityExceeded` and removes the previous value of its key.
+    pub max_memory_bytes: usize,
+    /// Weight function used for the memory budget (default: key + value length)
+    pub weigher: Weigher,
     /// Cleanup interval for expired entries
     pub cleanup_interval: Duration,
-    /// Maximum TTL supported (for TTL index sizing)
+    /// Run `cleanup_expired` in a background task every `cleanup_interval`
+    ///
+    /// Requires a Tokio runtime when the backend is created; see
+    /// `MemoryBackend::try_new`.
+    pub background_cleanup: bool,
+    /// Maximum TTL hint
+    ///
+    /// No longer used for sizing: the TTL index is a hierarchical timing wheel
+    /// that supports TTLs of any length.
     pub max_ttl: Duration,
     /// Enable TTL index for efficient expiration
     pub enable_ttl_index: bool,
+    /// Policy used to pick victims when at capacity
+    pub eviction_policy: EvictionPolicy,
+    /// Consult a bloom filter on `get`/`exists` so definite misses skip the store
+    pub bloom_filter: bool,
+    /// Target false positive rate of the bloom filter
+    pub bloom_false_positive_rate: f64,
+    /// Keep a sorted key index so `delete_by_pattern` only visits keys sharing
+    /// the pattern's literal prefix
+    ///
+    /// Off by default: every new and removed key then takes a store-wide
+    /// lock. Without it, pattern deletes check every key, so enable it if
+    /// they are frequent on a large store.
+    pub prefix_index: bool,
 }
 
 impl Default for MemoryConfig {
     fn default() -> Self {
         Self {
             max_capacity: 10_000,
+            max_memory_bytes: 0,
+            weigher: Weigher::default(),
             cleanup_interval: Duration::from_secs(60),
+            background_cleanup: false,
             max_ttl: Duration::from_secs(86400), // 24 hours
             enable_ttl_index: true,
+            eviction_policy: EvictionPolicy::default(),
+            bloom_filter: false,
+            bloom_false_positive_rate: 0.01,
+            prefix_index: false,
         }
     }
 }
@@ -51,6 +141,57 @@ impl MemoryConfig {
             ..Default::default()
         }
     }
+
+    /// Set the memory budget in bytes
+    pub fn max_memory_bytes(mut self, bytes: usize) -> Self {
+        self.max_memory_bytes = bytes;
+        self
+    }
+
+    /// Set a custom weigher for the memory budget
+    pub fn weigher<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &[u8]) -> usize + Send + Sync + 'static,
+    {
+        self.weigher = Weigher::new(f);
+        self
+    }
+
+    /// Enable or disable the background expiration task
+    pub fn background_cleanup(mut self, enabled: bool) -> Self {
+        self.background_cleanup = enabled;
+        self
+    }
+
+    /// Set cleanup interval for expired entries
+    pub fn cleanup_interval(mut self, interval: Duration) -> Self {
+        self.cleanup_interval = interval;
+        self
+    }
+
+    /// Set eviction policy
+    pub fn eviction_policy(mut self, policy: EvictionPolicy) -> Self {
+        self.eviction_policy = policy;
+        self
+    }
+
+    /// Enable or disable the negative-lookup bloom filter
+    pub fn bloom_filter(mut self, enabled: bool) -> Self {
+        self.bloom_filter = enabled;
+        self
+    }
+
+    /// Set the bloom filter false positive rate (e.g., 0.01 for 1%)
+    pub fn bloom_false_positive_rate(mut self, rate: f64) -> Self {
+        self.bloom_false_positive_rate = rate;
+        self
+    }
+
+    /// Enable or disable the sorted key index used by pattern deletes
+    pub fn prefix_index(mut self, enabled: bool) -> Self {
+        self.prefix_index = enabled;
+        self
+    }
 }
 
 /// Internal statistics tracking
@@ -81,27 +222,104 @@ pub struct MemoryBackend {
     tag_index: Arc<TagIndex>,
     /// Dependency -> keys index
     dep_index: Arc<DepIndex>,
+    /// Sorted keys for pattern deletes
+    prefix_index: Option<Arc<PrefixIndex>>,
     /// TTL expiration index
     ttl_index: Arc<RwLock<TtlIndex>>,
+    /// Eviction victim tracking
+    eviction: Arc<Mutex<EvictionState>>,
+    /// Frequency/cost tracking for the eviction policy
+    access: Arc<AccessTracker>,
+    /// Negative-lookup filter over stored keys
+    key_filter: Option<Arc<KeyFilter>>,
+    /// Running total of entry weights
+    memory_bytes: Arc<AtomicUsize>,
     /// Statistics
     stats: Arc<RwLock<MemoryStats>>,
+    /// Metrics collector for evictions
+    metrics: Arc<dyn CacheMetrics>,
+    /// Background expiration task (`None` inside the task itself)
+    reaper: Option<Arc<PeriodicTask>>,
     /// Configuration
     config: MemoryConfig,
 }
 
 impl MemoryBackend {
     /// Create a new memory backend
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.background_cleanup` is set outside a Tokio runtime.
     pub fn new(config: MemoryConfig) -> Self {
-        let ttl_index = TtlIndex::new(Duration::from_secs(1), config.max_ttl);
+        Self::with_metrics(config, NoopMetrics)
+    }
 
-        Self {
+    /// Create a new memory backend that reports evictions to `metrics`
+    ///
+    /// Starts the background expiration task if `config.background_cleanup` is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.background_cleanup` is set outside a Tokio runtime.
+    pub fn with_metrics(config: MemoryConfig, metrics: impl CacheMetrics) -> Self {
+        Self::try_with_metrics(config, metrics).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new memory backend, failing with `CacheError::Internal` if
+    /// `config.background_cleanup` is set outside a Tokio runtime
+    pub fn try_new(config: MemoryConfig) -> Result<Self> {
+        Self::try_with_metrics(config, NoopMetrics)
+    }
+
+    /// Create a new memory backend that reports evictions to `metrics`,
+    /// failing if the background expiration task cannot start
+    ///
+    /// See `try_new`.
+    pub fn try_with_metrics(config: MemoryConfig, metrics: impl CacheMetrics) -> Result<Self> {
+        let ttl_index = TtlIndex::new(TTL_RESOLUTION);
+        let bounded = config.max_capacity > 0 || config.max_memory_bytes > 0;
+        let estimated_entries = if config.max_capacity > 0 {
+            config.max_capacity
+        } else {
+            DEFAULT_ESTIMATED_ENTRIES
+        };
+        let eviction = EvictionState::new(config.eviction_policy, estimated_entries);
+        let access = AccessTracker::new(config.eviction_policy, estimated_entries, bounded);
+        let key_filter = config.bloom_filter.then(|| {
+            Arc::new(KeyFilter::new(
+                estimated_entries,
+                config.bloom_false_positive_rate,
+            ))
+        });
+
+        let mut backend = Self {
             data: Arc::new(DashMap::with_capacity(config.max_capacity.min(10_000))),
             tag_index: Arc::new(DashMap::new()),
             dep_index: Arc::new(DashMap::new()),
+            prefix_index: config.prefix_index.then(|| Arc::new(PrefixIndex::default())),
             ttl_index: Arc::new(RwLock::new(ttl_index)),
+            eviction: Arc::new(Mutex::new(eviction)),
+            access: Arc::new(access),
+            key_filter,
+            memory_bytes: Arc::new(AtomicUsize::new(0)),
             stats: Arc::new(RwLock::new(MemoryStats::default())),
+            metrics: Arc::new(metrics),
+            reaper: None,
             config,
+        };
+
+        if backend.config.background_cleanup {
+            // The task gets a handle without the reaper so dropping the last
+            // user handle stops it
+            let detached = backend.clone();
+            let reaper = PeriodicTask::spawn(backend.config.cleanup_interval, move || {
+                detached.cleanup_expired();
+                std::future::ready(())
+            })?;
+            backend.reaper = Some(Arc::new(reaper));
         }
+
+        Ok(backend)
     }
 
     /// Create with default configuration
@@ -109,37 +327,90 @@ impl MemoryBackend {
         Self::new(MemoryConfig::default())
     }
 
-    /// Evict entries if at capacity
-    fn maybe_evict(&self) {
-        if self.config.max_capacity == 0 {
-            return; // Unlimited
+    /// Stop the background expiration task, if running
+    ///
+    /// Affects every clone of this backend. Entries are kept.
+    pub fn shutdown(&self) {
+        if let Some(reaper) = &self.reaper {
+            reaper.stop();
         }
+    }
 
-        // Only evict if we're at or over capacity
-        if self.data.len() < self.config.max_capacity {
-            return;
-        }
+    /// Check whether the background expiration task is running
+    pub fn is_cleanup_running(&self) -> bool {
+        self.reaper.as_ref().is_some_and(|reaper| reaper.is_running())
+    }
+
+    /// Check whether the store holds more entries or bytes than allowed
+    fn over_capacity(&self) -> bool {
+        (self.config.max_capacity > 0 && self.data.len() > self.config.max_capacity)
+            || (self.config.max_memory_bytes > 0
+                && self.memory_usage() > self.config.max_memory_bytes)
+    }
 
-        // Simple eviction: collect keys to remove first
-        let keys_to_remove: Vec<String> = self
-            .data
-            .iter()
-            .take(self.data.len().saturating_sub(self.config.max_capacity - 1))
-            .map(|entry| entry.key().clone())
-            .collect();
+    /// Subtract a removed entry's weight from the running total
+    fn release_weight(&self, key: &str, value: &[u8]) {
+        let weight = self.config.weigher.weigh(key, value);
+        let _ = self
+            .memory_bytes
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |total| {
+                Some(total.saturating_sub(weight))
+            });
+    }
+
+    /// Evict entries chosen by the eviction policy until back under capacity
+    ///
+    /// `inserted` is the key that was just written; it is protected from
+    /// sampling so a fresh entry is not evicted right away.
+    fn evict_overflow(&self, inserted: &str) {
+        let mut eviction = self.eviction.lock();
+        let mut evicted = false;
+
+        while self.over_capacity() {
+            let Some(victim) = eviction.select_victim(&self.data, &self.access, inserted)
+            else {
+                break;
+            };
+            eviction.remove(&victim);
+            self.access.record_eviction(&victim);
+            if self.detach_entry(&victim) {
+                self.stats.write().evictions += 1;
+                self.metrics.record_eviction(EvictionReason::Capacity);
+                evicted = true;
+            }
+        }
 
-        for key in keys_to_remove {
-            self.data.remove(&key);
-            self.ttl_index.write().remove(&key);
-            self.stats.write().evictions += 1;
+        eviction.rebalance();
+        drop(eviction);
+        if evicted {
+            self.rebuild_bloom_filter_if_needed();
         }
     }
 
     /// Remove an entry and clean up indexes
     fn remove_entry(&self, key: &str) {
+        if self.detach_entry(key) {
+            self.eviction.lock().remove(key);
+            self.rebuild_bloom_filter_if_needed();
+        }
+    }
+
+    /// Remove an entry from the store and the TTL/tag/dependency indexes
+    ///
+    /// Does not touch eviction tracking. Returns `true` if the entry existed.
+    fn detach_entry(&self, key: &str) -> bool {
         if let Some((_, entry)) = self.data.remove(key) {
-            // Remove from TTL index
+            self.release_weight(key, &entry.value);
+            self.access.remove(key);
+            if let Some(filter) = &self.key_filter {
+                filter.record_removal();
+            }
+
+            // Remove from TTL and prefix indexes
             self.ttl_index.write().remove(key);
+            if let Some(index) = &self.prefix_index {
+                index.remove(key);
+            }
 
             // Remove from tag index
             for tag in &entry.tags {
@@ -154,6 +425,9 @@ impl MemoryBackend {
                     dependents.remove(key);
                 }
             }
+            true
+        } else {
+            false
         }
     }
 
@@ -163,44 +437,108 @@ impl MemoryBackend {
         let mut count = 0;
 
         for key in expired {
-            if let Some(entry) = self.data.get(&key) {
-                if entry.is_expired() && !entry.is_stale() {
-                    drop(entry);
-                    self.remove_entry(&key);
-                    self.stats.write().evictions += 1;
-                    count += 1;
-                }
+            let Some(entry) = self.data.get(&key) else {
+                continue;
+            };
+            if entry.is_expired() && !entry.is_stale() {
+                drop(entry);
+                self.remove_entry(&key);
+                self.stats.write().evictions += 1;
+                self.metrics.record_eviction(EvictionReason::Expired);
+                count += 1;
+            } else if let Some(remaining) = Self::remaining_lifetime(&entry) {
+                // Slot fired before the entry (incl. SWR window) ran out: reschedule
+                drop(entry);
+                self.ttl_index.write().schedule(key, remaining);
             }
         }
 
         count
     }
 
-    /// Get approximate memory usage
+    /// Rebuild the bloom filter from the live keys
+    ///
+    /// Deleted keys leave false positives behind; removals and evictions call
+    /// this automatically once they accumulate. No-op without `bloom_filter`.
+    pub fn rebuild_bloom_filter(&self) {
+        if let Some(filter) = &self.key_filter {
+            filter.rebuild(self.data.iter().map(|entry| entry.key().clone()));
+        }
+    }
+
+    /// Rebuild the bloom filter if removals or overfilling degraded it
+    ///
+    /// Scans every key, but only once per half capacity of removals. Must not
+    /// be called while holding a reference into the store.
+    fn rebuild_bloom_filter_if_needed(&self) {
+        if self
+            .key_filter
+            .as_ref()
+            .is_some_and(|filter| filter.needs_rebuild())
+        {
+            self.rebuild_bloom_filter();
+        }
+    }
+
+    /// Check the bloom filter: `false` if the key is definitely not stored
+    fn might_contain(&self, key: &str) -> bool {
+        self.key_filter
+            .as_ref()
+            .is_none_or(|filter| filter.might_contain(key))
+    }
+
+    /// Time left until an entry, including its SWR window, can be reaped
+    fn remaining_lifetime(entry: &CacheEntry<Vec<u8>>) -> Option<Duration> {
+        let lifetime = entry.ttl? + entry.stale_while_revalidate.unwrap_or_default();
+        lifetime.checked_sub(entry.age())
+    }
+
+    /// Earliest instant at which a scheduled entry expires
+    ///
+    /// `None` if no entry has a TTL or the TTL index is disabled.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.ttl_index.read().next_deadline()
+    }
+
+    /// Get approximate memory usage (total weight of all entries)
+    ///
+    /// Maintained incrementally on every write and removal.
     pub fn memory_usage(&self) -> usize {
-        self.data
-            .iter()
-            .map(|entry| entry.size + entry.key().len())
-            .sum()
+        self.memory_bytes.load(Ordering::Relaxed)
     }
 }
 
 #[async_trait]
 impl CacheBackend for MemoryBackend {
     async fn get(&self, key: &str) -> Result<Option<CacheEntry<Vec<u8>>>> {
+        self.access.record_request(key);
+
+        if !self.might_contain(key) {
+            self.stats.write().misses += 1;
+            return Ok(None);
+        }
+
         match self.data.get_mut(key) {
             Some(mut entry) => {
                 // Check expiration
                 if entry.is_expired() && !entry.is_stale() {
                     drop(entry);
                     self.remove_entry(key);
-                    self.stats.write().misses += 1;
+                    {
+                        let mut stats = self.stats.write();
+                        stats.misses += 1;
+                        stats.evictions += 1;
+                    }
+                    self.metrics.record_eviction(EvictionReason::Expired);
                     return Ok(None);
                 }
 
                 // Update access metadata
                 entry.last_accessed = SystemTime::now();
                 entry.access_count += 1;
+                self.access.record_access(key, entry.cost, || {
+                    self.config.weigher.weigh(key, &entry.value)
+                });
 
                 // Update stats
                 let mut stats = self.stats.write();
@@ -220,12 +558,22 @@ impl CacheBackend for MemoryBackend {
     }
 
     async fn set(&self, key: &str, value: Vec<u8>, options: &CacheOptions) -> Result<()> {
-        self.maybe_evict();
+        self.access.record_request(key);
 
         let size = value.len();
+        let weight = self.config.weigher.weigh(key, &value);
+
+        // An entry heavier than the whole budget can never fit; drop the
+        // previous value too, so reads do not return it as current
+        if self.config.max_memory_bytes > 0 && weight > self.config.max_memory_bytes {
+            options.check_version(self.data.get(key).as_deref())?;
+            self.remove_entry(key);
+            return Err(CacheError::CapacityExceeded);
+        }
+
         let now = SystemTime::now();
 
-        let entry = CacheEntry {
+        let mut entry = CacheEntry {
             value,
             created_at: now,
             last_accessed: now,
@@ -235,9 +583,26 @@ impl CacheBackend for MemoryBackend {
             tags: options.tags.clone(),
             dependencies: options.dependencies.clone(),
             cost: options.cost.unwrap_or(1),
+            compute_time: options.compute_time,
             size,
             etag: options.etag.clone(),
             version: 0,
+            negative: options.negative,
+        };
+
+        // Check and bump the version under the shard lock
+        let previous = match self.data.entry(key.to_string()) {
+            Entry::Occupied(mut occupied) => {
+                options.check_version(Some(occupied.get()))?;
+                entry.version = options.next_version(Some(occupied.get()));
+                Some(occupied.insert(entry))
+            }
+            Entry::Vacant(vacant) => {
+                options.check_version::<Vec<u8>>(None)?;
+                entry.version = options.next_version::<Vec<u8>>(None);
+                vacant.insert(entry);
+                None
+            }
         };
 
         // Schedule TTL expiration
@@ -252,7 +617,7 @@ impl CacheBackend for MemoryBackend {
         for tag in &options.tags {
             self.tag_index
                 .entry(tag.clone())
-                .or_insert_with(HashSet::new)
+                .or_default()
                 .insert(key.to_string());
         }
 
@@ -260,11 +625,23 @@ impl CacheBackend for MemoryBackend {
         for dep in &options.dependencies {
             self.dep_index
                 .entry(dep.clone())
-                .or_insert_with(HashSet::new)
+                .or_default()
                 .insert(key.to_string());
         }
 
-        if let Some(old_entry) = self.data.insert(key.to_string(), entry) {
+        self.memory_bytes.fetch_add(weight, Ordering::Relaxed);
+        if let Some(filter) = &self.key_filter {
+            // After the insert, so a concurrent rebuild either scans the key or sees this
+            let lifetime = options
+                .ttl
+                .map(|ttl| ttl + options.stale_while_revalidate.unwrap_or_default());
+            filter.insert(key, lifetime);
+        }
+        self.access.record_access(key, options.cost.unwrap_or(1), || weight);
+
+        if let Some(old_entry) = previous {
+            self.release_weight(key, &old_entry.value);
+
             // Clean up old dependencies that are no longer present
             for dep in old_entry.dependencies {
                 if !options.dependencies.contains(&dep) {
@@ -273,8 +650,15 @@ impl CacheBackend for MemoryBackend {
                     }
                 }
             }
+        } else {
+            // New key: track it for eviction and pattern deletes
+            self.eviction.lock().insert(key);
+            if let Some(index) = &self.prefix_index {
+                index.insert(key);
+            }
         }
-        
+        self.evict_overflow(key);
+
         self.stats.write().writes += 1;
 
         Ok(())
@@ -291,6 +675,10 @@ impl CacheBackend for MemoryBackend {
     }
 
     async fn exists(&self, key: &str) -> Result<bool> {
+        if !self.might_contain(key) {
+            return Ok(false);
+        }
+
         match self.data.get(key) {
             Some(entry) => Ok(!entry.is_expired() || entry.is_stale()),
             None => Ok(false),
@@ -316,17 +704,31 @@ impl CacheBackend for MemoryBackend {
     }
 
     async fn set_many(&self, entries: &[(&str, Vec<u8>, &CacheOptions)]) -> Result<()> {
+        // Entries that fit are stored even if others are too heavy
+        let mut result = Ok(());
         for (key, value, options) in entries {
-            self.set(key, value.clone(), options).await?;
+            match self.set(key, value.clone(), options).await {
+                Err(CacheError::CapacityExceeded) => result = Err(CacheError::CapacityExceeded),
+                other => other?,
+            }
         }
-        Ok(())
+        result
     }
 
     async fn clear(&self) -> Result<()> {
         self.data.clear();
+        self.memory_bytes.store(0, Ordering::Relaxed);
         self.tag_index.clear();
         self.dep_index.clear();
-        *self.ttl_index.write() = TtlIndex::new(Duration::from_secs(1), self.config.max_ttl);
+        if let Some(index) = &self.prefix_index {
+            index.clear();
+        }
+        self.ttl_index.write().clear();
+        self.eviction.lock().clear();
+        self.access.clear();
+        if let Some(filter) = &self.key_filter {
+            filter.clear();
+        }
         Ok(())
     }
 
@@ -380,6 +782,38 @@ impl TaggableBackend for MemoryBackend {
     }
 }
 
+#[async_trait]
+impl PatternBackend for MemoryBackend {
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<u64> {
+        let pattern = KeyPattern::new(pattern);
+        let candidates: Vec<String> = match &self.prefix_index {
+            Some(index) => index.with_prefix(&pattern.literal_prefix()),
+            None => self
+                .data
+                .iter()
+                .map(|entry| entry.key().clone())
+                .filter(|key| pattern.matches(key))
+                .collect(),
+        };
+
+        let mut count = 0;
+        for key in candidates {
+            if !pattern.matches(&key) {
+                continue;
+            }
+            if self.data.contains_key(&key) {
+                self.remove_entry(&key);
+                self.stats.write().deletes += 1;
+                count += 1;
+            } else if let Some(index) = &self.prefix_index {
+                // Left behind by a write racing a delete
+                index.remove(&key);
+            }
+        }
+        Ok(count)
+    }
+}
+
 #[async_trait]
 impl DependencyBackend for MemoryBackend {
     async fn get_dependents(&self, key: &str) -> Result<Vec<String>> {
@@ -501,6 +935,417 @@ mod tests {
         assert!(backend.len().await.unwrap() <= 2);
     }
 
+    #[tokio::test]
+    async fn test_lru_eviction_keeps_recently_used() {
+        let config = MemoryConfig::with_capacity(3).eviction_policy(EvictionPolicy::Lru);
+        let backend = MemoryBackend::new(config);
+        let options = CacheOptions::default();
+
+        for key in ["a", "b", "c"] {
+            backend.set(key, b"v".to_vec(), &options).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+
+        // Touch "a" so "b" becomes the least recently used
+        backend.get("a").await.unwrap();
+        backend.set("d", b"v".to_vec(), &options).await.unwrap();
+
+        assert_eq!(backend.len().await.unwrap(), 3);
+        assert!(backend.exists("a").await.unwrap());
+        assert!(!backend.exists("b").await.unwrap());
+        assert!(backend.exists("d").await.unwrap());
+        assert_eq!(backend.stats().await.unwrap().evictions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_lfu_eviction_keeps_frequently_used() {
+        let config = MemoryConfig::with_capacity(3).eviction_policy(EvictionPolicy::Lfu);
+        let backend = MemoryBackend::new(config);
+        let options = CacheOptions::default();
+
+        for key in ["a", "b", "c"] {
+            backend.set(key, b"v".to_vec(), &options).await.unwrap();
+        }
+        for _ in 0..3 {
+            backend.get("a").await.unwrap();
+            backend.get("c").await.unwrap();
+        }
+        backend.get("b").await.unwrap();
+
+        backend.set("d", b"v".to_vec(), &options).await.unwrap();
+
+        assert!(backend.exists("a").await.unwrap());
+        assert!(!backend.exists("b").await.unwrap());
+        assert!(backend.exists("c").await.unwrap());
+        assert!(backend.exists("d").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_tinylfu_resists_scans() {
+        let config = MemoryConfig::with_capacity(10).eviction_policy(EvictionPolicy::TinyLfu);
+        let backend = MemoryBackend::new(config);
+        let options = CacheOptions::default();
+
+        for i in 0..10 {
+            let key = format!("hot:{}", i);
+            backend.set(&key, b"v".to_vec(), &options).await.unwrap();
+            for _ in 0..3 {
+                backend.get(&key).await.unwrap();
+            }
+        }
+
+        // A scan of one-hit keys must not flush the popular entries
+        for i in 0..50 {
+            backend
+                .set(&format!("cold:{}", i), b"v".to_vec(), &options)
+                .await
+                .unwrap();
+        }
+
+        let mut survivors = 0;
+        for i in 0..10 {
+            if backend.exists(&format!("hot:{}", i)).await.unwrap() {
+                survivors += 1;
+            }
+        }
+        assert!(survivors >= 9, "only {} hot keys survived", survivors);
+        assert!(backend.len().await.unwrap() <= 10);
+    }
+
+    #[tokio::test]
+    async fn test_cost_aware_eviction_keeps_expensive_entries() {
+        let config = MemoryConfig::with_capacity(3).eviction_policy(EvictionPolicy::CostAware);
+        let backend = MemoryBackend::new(config);
+
+        let cheap = CacheOptions {
+            cost: Some(1),
+            ..Default::default()
+        };
+        let expensive = CacheOptions {
+            cost: Some(1_000),
+            ..Default::default()
+        };
+
+        backend.set("report", vec![0; 100], &expensive).await.unwrap();
+        backend.set("lookup:1", vec![0; 100], &cheap).await.unwrap();
+        backend.set("lookup:2", vec![0; 100], &cheap).await.unwrap();
+
+        // Cheap lookups churn while the expensive aggregate stays resident
+        for i in 3..10 {
+            backend
+                .set(&format!("lookup:{}", i), vec![0; 100], &cheap)
+                .await
+                .unwrap();
+            assert!(backend.exists("report").await.unwrap());
+        }
+        assert_eq!(backend.len().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cost_aware_prefers_evicting_large_entries() {
+        let config = MemoryConfig::with_capacity(2).eviction_policy(EvictionPolicy::CostAware);
+        let backend = MemoryBackend::new(config);
+        let options = CacheOptions {
+            cost: Some(100),
+            ..Default::default()
+        };
+
+        backend.set("small", vec![0; 10], &options).await.unwrap();
+        backend.set("large", vec![0; 10_000], &options).await.unwrap();
+        backend.set("new", vec![0; 10], &options).await.unwrap();
+
+        assert!(backend.exists("small").await.unwrap());
+        assert!(!backend.exists("large").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_does_not_evict() {
+        let backend = MemoryBackend::new(MemoryConfig::with_capacity(2));
+        let options = CacheOptions::default();
+
+        backend.set("a", b"1".to_vec(), &options).await.unwrap();
+        backend.set("b", b"1".to_vec(), &options).await.unwrap();
+        backend.set("a", b"2".to_vec(), &options).await.unwrap();
+
+        assert!(backend.exists("a").await.unwrap());
+        assert!(backend.exists("b").await.unwrap());
+        assert_eq!(backend.stats().await.unwrap().evictions, 0);
+    }
+
+    #[tokio::test]
+    async fn test_memory_usage_is_tracked_incrementally() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let options = CacheOptions::default();
+
+        backend.set("k1", vec![0; 10], &options).await.unwrap();
+        backend.set("k2", vec![0; 20], &options).await.unwrap();
+        assert_eq!(backend.memory_usage(), 2 + 10 + 2 + 20);
+
+        // Overwrite replaces the old weight
+        backend.set("k1", vec![0; 5], &options).await.unwrap();
+        assert_eq!(backend.memory_usage(), 2 + 5 + 2 + 20);
+
+        backend.delete("k2").await.unwrap();
+        assert_eq!(backend.memory_usage(), 2 + 5);
+        assert_eq!(backend.stats().await.unwrap().memory_bytes, 7);
+
+        backend.clear().await.unwrap();
+        assert_eq!(backend.memory_usage(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_byte_budget_eviction() {
+        let config = MemoryConfig::unlimited()
+            .max_memory_bytes(100)
+            .weigher(|_key, value| value.len());
+        let backend = MemoryBackend::new(config);
+        let options = CacheOptions::default();
+
+        for i in 0..10 {
+            backend
+                .set(&format!("key:{}", i), vec![0; 30], &options)
+                .await
+                .unwrap();
+            assert!(backend.memory_usage() <= 100);
+        }
+
+        assert_eq!(backend.len().await.unwrap(), 3);
+        assert_eq!(backend.memory_usage(), 90);
+        assert!(backend.exists("key:9").await.unwrap());
+
+        // Growing an existing entry also enforces the budget
+        backend.set("key:9", vec![0; 80], &options).await.unwrap();
+        assert!(backend.memory_usage() <= 100);
+        assert!(backend.exists("key:9").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_entry_is_not_stored() {
+        let config = MemoryConfig::unlimited()
+            .max_memory_bytes(50)
+            .weigher(|_key, value| value.len());
+        let backend = MemoryBackend::new(config);
+        let options = CacheOptions::default();
+
+        backend.set("small", vec![0; 10], &options).await.unwrap();
+        backend.set("big", vec![0; 10], &options).await.unwrap();
+        assert!(matches!(
+            backend.set("big", vec![0; 60], &options).await,
+            Err(CacheError::CapacityExceeded)
+        ));
+
+        // The previous value is gone and nothing was evicted
+        assert!(!backend.exists("big").await.unwrap());
+        assert!(backend.exists("small").await.unwrap());
+        assert_eq!(backend.memory_usage(), 10);
+        assert_eq!(backend.stats().await.unwrap().evictions, 0);
+    }
+
+    #[derive(Clone, Default)]
+    struct EvictionCounter {
+        expired: Arc<std::sync::atomic::AtomicU64>,
+    }
+
+    impl CacheMetrics for EvictionCounter {
+        fn record_hit(&self, _key: &str, _tier: skp_cache_core::CacheTier) {}
+        fn record_miss(&self, _key: &str) {}
+        fn record_stale_hit(&self, _key: &str) {}
+        fn record_latency(&self, _op: skp_cache_core::CacheOperation, _duration: Duration) {}
+        fn record_eviction(&self, reason: EvictionReason) {
+            if reason == EvictionReason::Expired {
+                self.expired.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        fn record_size(&self, _size: usize, _memory_bytes: usize) {}
+    }
+
+    #[tokio::test]
+    async fn test_background_cleanup_reaps_expired_entries() {
+        let counter = EvictionCounter::default();
+        let config = MemoryConfig::default()
+            .background_cleanup(true)
+            .cleanup_interval(Duration::from_millis(50));
+        let backend = MemoryBackend::with_metrics(config, counter.clone());
+        assert!(backend.is_cleanup_running());
+
+        let options = CacheOptions {
+            ttl: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        backend.set("write_once", b"v".to_vec(), &options).await.unwrap();
+        backend
+            .set("forever", b"v".to_vec(), &CacheOptions::default())
+            .await
+            .unwrap();
+
+        // Never read again: only the reaper can remove it
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+
+        assert_eq!(backend.len().await.unwrap(), 1);
+        assert_eq!(counter.expired.load(Ordering::Relaxed), 1);
+        assert_eq!(backend.stats().await.unwrap().evictions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_background_cleanup_stops_on_shutdown_and_drop() {
+        let config = MemoryConfig::default()
+            .background_cleanup(true)
+            .cleanup_interval(Duration::from_millis(10));
+
+        let backend = MemoryBackend::new(config.clone());
+        let handle = backend.clone();
+        assert!(handle.is_cleanup_running());
+        backend.shutdown();
+        assert!(!handle.is_cleanup_running());
+
+        // Dropping every handle stops the task and frees the store
+        let backend = MemoryBackend::new(config);
+        let data = Arc::downgrade(&backend.data);
+        drop(backend);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(data.upgrade().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_millisecond_ttl_and_next_expiry() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        assert!(backend.next_expiry().is_none());
+
+        let short = CacheOptions {
+            ttl: Some(Duration::from_millis(30)),
+            ..Default::default()
+        };
+        let long = CacheOptions {
+            ttl: Some(Duration::from_secs(7 * 86400)),
+            ..Default::default()
+        };
+        let before = Instant::now();
+        backend.set("short", b"v".to_vec(), &short).await.unwrap();
+        backend.set("long", b"v".to_vec(), &long).await.unwrap();
+
+        let next = backend.next_expiry().unwrap();
+        assert!(next >= before + Duration::from_millis(30));
+        assert!(next < before + Duration::from_secs(1));
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(backend.cleanup_expired(), 1);
+        assert_eq!(backend.len().await.unwrap(), 1);
+        assert!(backend.next_expiry().unwrap() > Instant::now() + Duration::from_secs(86400));
+    }
+
+    #[tokio::test]
+    async fn test_bloom_filter_lookups() {
+        let backend = MemoryBackend::new(MemoryConfig::default().bloom_filter(true));
+        let options = CacheOptions::default();
+
+        assert!(backend.get("missing").await.unwrap().is_none());
+        assert!(!backend.exists("missing").await.unwrap());
+        assert_eq!(backend.stats().await.unwrap().misses, 1);
+
+        backend.set("key", b"value".to_vec(), &options).await.unwrap();
+        assert!(backend.exists("key").await.unwrap());
+        assert_eq!(backend.get("key").await.unwrap().unwrap().value, b"value");
+
+        backend.clear().await.unwrap();
+        assert!(!backend.might_contain("key"));
+    }
+
+    #[tokio::test]
+    async fn test_bloom_filter_rebuilt_after_deletes() {
+        let backend = MemoryBackend::new(
+            MemoryConfig::with_capacity(100).bloom_filter(true),
+        );
+        let options = CacheOptions::default();
+
+        for i in 0..100 {
+            backend.set(&format!("key:{}", i), b"v".to_vec(), &options).await.unwrap();
+        }
+        for i in 0..50 {
+            backend.delete(&format!("key:{}", i)).await.unwrap();
+        }
+        assert!(backend.might_contain("key:0"));
+
+        // Removals past half the capacity rebuild the filter from live keys,
+        // without background cleanup
+        backend.delete("key:50").await.unwrap();
+        assert!(!backend.is_cleanup_running());
+        let lingering = (0..=50)
+            .filter(|i| backend.might_contain(&format!("key:{}", i)))
+            .count();
+        assert!(lingering < 10, "{} deleted keys still in filter", lingering);
+        for i in 51..100 {
+            assert!(backend.exists(&format!("key:{}", i)).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bloom_filter_rebuilt_after_evictions() {
+        let backend = MemoryBackend::new(
+            MemoryConfig::with_capacity(100).bloom_filter(true),
+        );
+        let options = CacheOptions::default();
+
+        for i in 0..400 {
+            backend.set(&format!("key:{}", i), b"v".to_vec(), &options).await.unwrap();
+        }
+        assert!(!backend.is_cleanup_running());
+        let mut lingering = 0;
+        for i in 0..400 {
+            let key = format!("key:{}", i);
+            if !backend.data.contains_key(&key) && backend.might_contain(&key) {
+                lingering += 1;
+            }
+        }
+        assert!(lingering < 100, "{} evicted keys still in filter", lingering);
+    }
+
+    #[test]
+    fn test_background_cleanup_requires_runtime() {
+        let config = MemoryConfig::default().background_cleanup(true);
+        assert!(matches!(
+            MemoryBackend::try_new(config),
+            Err(skp_cache_core::CacheError::Internal(_))
+        ));
+        assert!(MemoryBackend::try_new(MemoryConfig::default()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_background_cleanup_disabled_by_default() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        assert!(!backend.is_cleanup_running());
+    }
+
+    #[tokio::test]
+    async fn test_versions_and_if_version() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let options = CacheOptions::default();
+
+        backend.set("key", b"v1".to_vec(), &options).await.unwrap();
+        backend.set("key", b"v2".to_vec(), &options).await.unwrap();
+        assert_eq!(backend.get("key").await.unwrap().unwrap().version, 2);
+
+        let stale = CacheOptions {
+            if_version: Some(1),
+            ..Default::default()
+        };
+        let err = backend.set("key", b"v3".to_vec(), &stale).await.unwrap_err();
+        assert!(matches!(
+            err,
+            skp_cache_core::CacheError::VersionMismatch {
+                expected: 1,
+                actual: 2
+            }
+        ));
+        assert_eq!(backend.get("key").await.unwrap().unwrap().value, b"v2");
+
+        let current = CacheOptions {
+            if_version: Some(2),
+            ..Default::default()
+        };
+        backend.set("key", b"v3".to_vec(), &current).await.unwrap();
+        assert_eq!(backend.get("key").await.unwrap().unwrap().version, 3);
+    }
+
     #[tokio::test]
     async fn test_get_many() {
         let backend = MemoryBackend::new(MemoryConfig::default());
@@ -527,8 +1372,10 @@ mod tests {
         use skp_cache_core::{DependencyBackend, CacheOptions};
         let backend = MemoryBackend::new(MemoryConfig::default());
         
-        let mut opts = CacheOptions::default();
-        opts.dependencies = vec!["dep1".to_string(), "dep2".to_string()];
+        let mut opts = CacheOptions {
+            dependencies: vec!["dep1".to_string(), "dep2".to_string()],
+            ..Default::default()
+        };
 
         backend.set("key1", b"val".to_vec(), &opts).await.unwrap();
         
@@ -554,4 +1401,26 @@ mod tests {
         assert!(backend.get_dependents("dep1").await.unwrap().is_empty());
         assert!(backend.get_dependents("dep3").await.unwrap().is_empty());
     }
+
+    #[tokio::test]
+    async fn test_delete_by_pattern() {
+        for indexed in [false, true] {
+            let backend = MemoryBackend::new(MemoryConfig::default().prefix_index(indexed));
+            let opts = CacheOptions::default();
+            for key in ["user:1:posts", "user:2:posts", "user:2:profile", "users:posts", "post:1"] {
+                backend.set(key, b"val".to_vec(), &opts).await.unwrap();
+            }
+
+            assert_eq!(backend.delete_by_pattern("user:*:posts").await.unwrap(), 2);
+            assert!(!backend.exists("user:1:posts").await.unwrap());
+            assert!(backend.exists("user:2:profile").await.unwrap());
+            assert!(backend.exists("users:posts").await.unwrap());
+
+            // Deleted and evicted keys leave the index
+            backend.delete("post:1").await.unwrap();
+            assert_eq!(backend.delete_by_pattern("*").await.unwrap(), 2);
+            assert_eq!(backend.len().await.unwrap(), 0);
+            assert_eq!(backend.delete_by_pattern("*").await.unwrap(), 0);
+        }
+    }
 }
@@ -4,8 +4,10 @@
 //! if a key is definitely NOT in the cache, avoiding unnecessary backend lookups.
 //! False positives are possible, but false negatives are not.
 
+use parking_lot::{Mutex, RwLock};
 use std::hash::{Hash, Hasher};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 /// A thread-safe bloom filter optimized for cache negative lookups
 ///
@@ -39,7 +41,7 @@ impl BloomFilter {
         let num_hashes = num_hashes.clamp(1, 16); // Between 1 and 16 hash functions
 
         // Round up to multiple of 64 for atomic storage
-        let num_u64s = (num_bits + 63) / 64;
+        let num_u64s = num_bits.div_ceil(64);
         let actual_bits = num_u64s * 64;
 
         let bits: Box<[AtomicU64]> = (0..num_u64s)
@@ -56,7 +58,7 @@ impl BloomFilter {
 
     /// Create with specific size parameters
     pub fn with_size(num_bits: usize, num_hashes: usize) -> Self {
-        let num_u64s = (num_bits + 63) / 64;
+        let num_u64s = num_bits.div_ceil(64);
         let actual_bits = num_u64s * 64;
 
         let bits: Box<[AtomicU64]> = (0..num_u64s)
@@ -150,6 +152,229 @@ impl Clone for BloomFilter {
     }
 }
 
+/// Bloom filter over the keys of a cache, rebuilt as keys are removed
+///
+/// Bloom filters cannot forget keys, so every deletion leaves bits behind that
+/// turn into false positives. Once removals outnumber half the sized capacity
+/// (or inserts exceed it) the filter asks to be rebuilt. A rebuild starts a new
+/// generation while the old one keeps answering lookups, so a key that is still
+/// live is never reported as definitely absent.
+pub struct KeyFilter {
+    /// Capacity the first generation is sized for
+    expected_items: usize,
+    /// Target false positive rate
+    false_positive_rate: f64,
+    /// Current and retiring generations
+    generations: RwLock<Generations>,
+    /// Serializes rebuilds
+    rebuild_lock: Mutex<()>,
+    /// New keys inserted into the current generation
+    inserted: AtomicUsize,
+    /// Keys removed since the current generation started
+    removed: AtomicUsize,
+    /// Longest lifetime (ms) among keys of the current generation, `u64::MAX` if unbounded
+    max_lifetime_ms: AtomicU64,
+}
+
+/// Filter generations of a `KeyFilter`
+struct Generations {
+    current: BloomFilter,
+    /// Items `current` was sized for
+    capacity: usize,
+    /// Previous generation, consulted until retired
+    previous: Option<Retiring>,
+}
+
+/// A generation that is being replaced
+struct Retiring {
+    filter: BloomFilter,
+    /// When every key only present in this generation has expired
+    retire_at: Instant,
+}
+
+impl KeyFilter {
+    /// Create a key filter sized for `expected_items` at `false_positive_rate`
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let expected_items = expected_items.max(1);
+        Self {
+            expected_items,
+            false_positive_rate,
+            generations: RwLock::new(Generations {
+                current: BloomFilter::new(expected_items, false_positive_rate),
+                capacity: expected_items,
+                previous: None,
+            }),
+            rebuild_lock: Mutex::new(()),
+            inserted: AtomicUsize::new(0),
+            removed: AtomicUsize::new(0),
+            max_lifetime_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a written key
+    ///
+    /// `lifetime` is how long the key can stay in the cache (`None` = no expiry).
+    pub fn insert(&self, key: &str, lifetime: Option<Duration>) {
+        let lifetime_ms = lifetime.map_or(u64::MAX, |d| {
+            u64::try_from(d.as_millis()).unwrap_or(u64::MAX)
+        });
+
+        let generations = self.generations.read();
+        self.max_lifetime_ms
+            .fetch_max(lifetime_ms, Ordering::Relaxed);
+        if !generations.current.might_contain(key) {
+            generations.current.insert(key);
+            self.inserted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Check if a key might be in the cache (`false` = definitely not)
+    pub fn might_contain(&self, key: &str) -> bool {
+        let generations = self.generations.read();
+        generations.current.might_contain(key)
+            || generations
+                .previous
+                .as_ref()
+                .is_some_and(|previous| previous.filter.might_contain(key))
+    }
+
+    /// Record that a key was removed from the cache
+    pub fn record_removal(&self) {
+        self.removed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that `count` keys were removed from the cache
+    pub fn record_removals(&self, count: usize) {
+        self.removed.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Check whether removals or overfilling have degraded the filter
+    pub fn needs_rebuild(&self) -> bool {
+        let capacity = self.generations.read().capacity;
+        self.removed.load(Ordering::Relaxed) > capacity / 2
+            || self.inserted.load(Ordering::Relaxed) > capacity
+    }
+
+    /// Check whether a rebuild is in progress
+    pub fn is_rebuilding(&self) -> bool {
+        self.generations.read().previous.is_some()
+    }
+
+    /// Rebuild from the complete set of live keys
+    ///
+    /// Keys inserted concurrently land in the new generation; the old one is
+    /// dropped once `keys` is exhausted. Returns `false` if another rebuild is
+    /// already running.
+    pub fn rebuild<I, K>(&self, keys: I) -> bool
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<str>,
+    {
+        let Some(_guard) = self.rebuild_lock.try_lock() else {
+            return false;
+        };
+
+        // Exact rebuild: the previous generation only needs to outlive the scan
+        self.start_generation(|_| Some(Instant::now()));
+        let mut scanned = 0;
+        {
+            let generations = self.generations.read();
+            for key in keys {
+                generations.current.insert(key.as_ref());
+                scanned += 1;
+            }
+        }
+        self.inserted.fetch_add(scanned, Ordering::Relaxed);
+        // Lifetimes of the scanned keys are unknown
+        self.max_lifetime_ms.store(u64::MAX, Ordering::Relaxed);
+        self.generations.write().previous = None;
+        true
+    }
+
+    /// Rebuild without access to the live keys, relying on expiry
+    ///
+    /// Starts a new generation when the filter needs a rebuild and every key in
+    /// it has a bounded lifetime. The old generation is dropped on a later call
+    /// once all of its keys must have expired; keys written in the meantime are
+    /// recorded in the new one. Filters holding keys without a TTL are never
+    /// rotated.
+    pub fn rotate(&self) {
+        let Some(_guard) = self.rebuild_lock.try_lock() else {
+            return;
+        };
+
+        let retiring = self
+            .generations
+            .read()
+            .previous
+            .as_ref()
+            .map(|previous| previous.retire_at);
+        if let Some(retire_at) = retiring {
+            if retire_at <= Instant::now() {
+                self.generations.write().previous = None;
+            }
+            return;
+        }
+
+        if self.needs_rebuild() {
+            self.start_generation(|lifetime_ms| {
+                (lifetime_ms != u64::MAX)
+                    .then(|| Instant::now() + Duration::from_millis(lifetime_ms))
+            });
+        }
+    }
+
+    /// Reset to an empty filter
+    pub fn clear(&self) {
+        let _guard = self.rebuild_lock.lock();
+        let mut generations = self.generations.write();
+        generations.current = BloomFilter::new(self.expected_items, self.false_positive_rate);
+        generations.capacity = self.expected_items;
+        generations.previous = None;
+        self.inserted.store(0, Ordering::Relaxed);
+        self.removed.store(0, Ordering::Relaxed);
+        self.max_lifetime_ms.store(0, Ordering::Relaxed);
+    }
+
+    /// Swap in an empty generation sized for the live key estimate
+    ///
+    /// `retire_at` maps the longest key lifetime of the current generation to
+    /// the instant it may be dropped, or `None` to keep the current generation.
+    fn start_generation(&self, retire_at: impl FnOnce(u64) -> Option<Instant>) {
+        let mut generations = self.generations.write();
+        // Read under the write lock so no insert can extend it afterwards
+        let Some(retire_at) = retire_at(self.max_lifetime_ms.load(Ordering::Relaxed)) else {
+            return;
+        };
+        let live = self
+            .inserted
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.removed.load(Ordering::Relaxed));
+        let capacity = self.expected_items.max(live * 2);
+
+        let filter = std::mem::replace(
+            &mut generations.current,
+            BloomFilter::new(capacity, self.false_positive_rate),
+        );
+        generations.capacity = capacity;
+        generations.previous = Some(Retiring { filter, retire_at });
+        self.inserted.store(0, Ordering::Relaxed);
+        self.removed.store(0, Ordering::Relaxed);
+        self.max_lifetime_ms.store(0, Ordering::Relaxed);
+    }
+}
+
+impl std::fmt::Debug for KeyFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyFilter")
+            .field("expected_items", &self.expected_items)
+            .field("false_positive_rate", &self.false_positive_rate)
+            .field("inserted", &self.inserted.load(Ordering::Relaxed))
+            .field("removed", &self.removed.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,4 +431,57 @@ mod tests {
         assert!(filter.num_bits() > 0);
         assert!(filter.num_hashes() > 0);
     }
+
+    #[test]
+    fn test_key_filter_rebuild() {
+        let filter = KeyFilter::new(100, 0.01);
+        for i in 0..100 {
+            filter.insert(&format!("key:{}", i), None);
+        }
+        filter.record_removals(60);
+        assert!(filter.needs_rebuild());
+
+        assert!(filter.rebuild((60..100).map(|i| format!("key:{}", i))));
+        assert!(!filter.needs_rebuild());
+        assert!(!filter.is_rebuilding());
+        for i in 60..100 {
+            assert!(filter.might_contain(&format!("key:{}", i)));
+        }
+        let lingering = (0..60)
+            .filter(|i| filter.might_contain(&format!("key:{}", i)))
+            .count();
+        assert!(lingering < 5, "{} removed keys still present", lingering);
+    }
+
+    #[test]
+    fn test_key_filter_rotation() {
+        let filter = KeyFilter::new(100, 0.01);
+        for i in 0..100 {
+            filter.insert(&format!("key:{}", i), Some(Duration::from_millis(50)));
+        }
+        filter.record_removals(60);
+
+        // Old generation keeps answering until its keys have expired
+        filter.rotate();
+        assert!(filter.is_rebuilding());
+        assert!(filter.might_contain("key:0"));
+        filter.insert("fresh", Some(Duration::from_millis(50)));
+
+        std::thread::sleep(Duration::from_millis(60));
+        filter.rotate();
+        assert!(!filter.is_rebuilding());
+        assert!(filter.might_contain("fresh"));
+        assert!(!filter.might_contain("key:0"));
+    }
+
+    #[test]
+    fn test_key_filter_never_rotates_unbounded_keys() {
+        let filter = KeyFilter::new(10, 0.01);
+        filter.insert("forever", None);
+        filter.record_removals(10);
+
+        filter.rotate();
+        assert!(!filter.is_rebuilding());
+        assert!(filter.might_contain("forever"));
+    }
 }
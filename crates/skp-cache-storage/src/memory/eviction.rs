@@ -0,0 +1,517 @@
+//! Eviction policies for the memory backend
+//!
+//! Victims are chosen by sampling live entries and comparing the
+//! `last_accessed` / `access_count` metadata that `CacheEntry` already tracks,
+//! similar to Redis' approximated LRU/LFU. W-TinyLFU additionally keeps a small
+//! admission window and a frequency sketch so one-hit wonders cannot flush
+//! frequently used entries out of the cache. The cost-aware policy follows
+//! GreedyDual-Size, trading recompute cost against size and recency.
+
+use dashmap::DashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use skp_cache_core::CacheEntry;
+
+/// Number of entries sampled when looking for an eviction victim
+const EVICTION_SAMPLES: usize = 8;
+
+/// Share of the capacity reserved for the W-TinyLFU admission window (1%)
+const WINDOW_RATIO: usize = 100;
+
+/// Policy used to pick victims when the memory backend is at capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Evict the least recently accessed entry
+    #[default]
+    Lru,
+    /// Evict the least frequently accessed entry (ties broken by recency)
+    Lfu,
+    /// Window TinyLFU: new entries enter a small LRU window and are only
+    /// admitted to the main space if they are accessed more often than the
+    /// entry they would replace
+    TinyLfu,
+    /// GreedyDual-Size: evict the entry with the lowest `cost / weight`
+    /// priority, aged by an inflation value so long-idle entries still leave
+    /// (uses `CacheOptions::cost`)
+    CostAware,
+}
+
+impl EvictionPolicy {
+    /// Get policy as string label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvictionPolicy::Lru => "lru",
+            EvictionPolicy::Lfu => "lfu",
+            EvictionPolicy::TinyLfu => "tinylfu",
+            EvictionPolicy::CostAware => "cost_aware",
+        }
+    }
+}
+
+/// Bookkeeping needed to select eviction victims
+///
+/// Keeps a dense key list for O(1) random sampling plus, for W-TinyLFU,
+/// the admission window in insertion order.
+pub(crate) struct EvictionState {
+    policy: EvictionPolicy,
+    /// Dense list of tracked keys (for random sampling)
+    keys: Vec<String>,
+    /// Map of key -> position in `keys` for O(1) removal
+    positions: HashMap<String, usize>,
+    /// W-TinyLFU admission window (oldest first)
+    window: VecDeque<String>,
+    /// Keys currently in the admission window
+    window_keys: HashSet<String>,
+    /// Maximum number of keys in the admission window
+    window_capacity: usize,
+}
+
+impl EvictionState {
+    /// Create eviction state for a cache of the given entry capacity
+    pub(crate) fn new(policy: EvictionPolicy, capacity: usize) -> Self {
+        Self {
+            policy,
+            keys: Vec::new(),
+            positions: HashMap::new(),
+            window: VecDeque::new(),
+            window_keys: HashSet::new(),
+            window_capacity: (capacity / WINDOW_RATIO).max(1),
+        }
+    }
+
+    /// Start tracking a newly inserted key
+    pub(crate) fn insert(&mut self, key: &str) {
+        if self.positions.contains_key(key) {
+            return;
+        }
+        self.positions.insert(key.to_string(), self.keys.len());
+        self.keys.push(key.to_string());
+
+        if self.policy == EvictionPolicy::TinyLfu {
+            self.window.push_back(key.to_string());
+            self.window_keys.insert(key.to_string());
+        }
+    }
+
+    /// Stop tracking a key
+    pub(crate) fn remove(&mut self, key: &str) {
+        if let Some(pos) = self.positions.remove(key) {
+            self.keys.swap_remove(pos);
+            if let Some(moved) = self.keys.get(pos) {
+                self.positions.insert(moved.clone(), pos);
+            }
+        }
+        if self.window_keys.remove(key) {
+            self.window.retain(|k| k != key);
+        }
+    }
+
+    /// Forget all tracked keys
+    pub(crate) fn clear(&mut self) {
+        self.keys.clear();
+        self.positions.clear();
+        self.window.clear();
+        self.window_keys.clear();
+    }
+
+    /// Move keys that overflowed the admission window into the main space
+    ///
+    /// Called once the cache is back under capacity, so no competition is needed.
+    pub(crate) fn rebalance(&mut self) {
+        while self.window.len() > self.window_capacity {
+            if let Some(key) = self.window.pop_front() {
+                self.window_keys.remove(&key);
+            }
+        }
+    }
+
+    /// Select the next entry to evict
+    ///
+    /// `protected` is the key that triggered the eviction; it is never chosen
+    /// by the sampling policies so a fresh entry is not evicted immediately.
+    pub(crate) fn select_victim(
+        &mut self,
+        data: &DashMap<String, CacheEntry<Vec<u8>>>,
+        tracker: &AccessTracker,
+        protected: &str,
+    ) -> Option<String> {
+        match (self.policy, &tracker.sketch) {
+            (EvictionPolicy::TinyLfu, Some(sketch)) => {
+                self.select_tinylfu(data, tracker, sketch, protected)
+            }
+            _ => self.sample(data, tracker, protected, false),
+        }
+    }
+
+    /// Let the oldest window entry compete against a sampled main-space victim
+    fn select_tinylfu(
+        &mut self,
+        data: &DashMap<String, CacheEntry<Vec<u8>>>,
+        tracker: &AccessTracker,
+        sketch: &FrequencySketch,
+        protected: &str,
+    ) -> Option<String> {
+        let main_victim = self.sample(data, tracker, protected, true);
+
+        if self.window.len() <= self.window_capacity {
+            // Window not full: the main space pays for the new entry
+            return main_victim.or_else(|| self.window.front().cloned());
+        }
+
+        let candidate = self.window.pop_front()?;
+        self.window_keys.remove(&candidate);
+
+        match main_victim {
+            // Candidate is admitted only if it is strictly more popular
+            Some(victim) if sketch.frequency(&candidate) > sketch.frequency(&victim) => {
+                Some(victim)
+            }
+            _ => Some(candidate),
+        }
+    }
+
+    /// Sample tracked keys and return the worst one according to the policy
+    fn sample(
+        &mut self,
+        data: &DashMap<String, CacheEntry<Vec<u8>>>,
+        tracker: &AccessTracker,
+        protected: &str,
+        main_only: bool,
+    ) -> Option<String> {
+        let mut best: Option<(String, (f64, std::time::SystemTime))> = None;
+        let mut consider = |key: &String| -> bool {
+            if key == protected || (main_only && self.window_keys.contains(key)) {
+                return true;
+            }
+            let Some(entry) = data.get(key) else {
+                return false;
+            };
+            // Lower score = better victim
+            let score = match self.policy {
+                EvictionPolicy::Lfu => (entry.access_count as f64, entry.last_accessed),
+                EvictionPolicy::CostAware => (tracker.priority(key), entry.last_accessed),
+                EvictionPolicy::Lru | EvictionPolicy::TinyLfu => (0.0, entry.last_accessed),
+            };
+            if best.as_ref().is_none_or(|(_, s)| score < *s) {
+                best = Some((key.clone(), score));
+            }
+            true
+        };
+
+        let mut stale = Vec::new();
+        if self.keys.len() <= EVICTION_SAMPLES {
+            // Small cache: examine everything for an exact answer
+            for key in &self.keys {
+                if !consider(key) {
+                    stale.push(key.clone());
+                }
+            }
+        } else {
+            for _ in 0..EVICTION_SAMPLES {
+                let idx = rand::random::<u64>() as usize % self.keys.len();
+                let key = &self.keys[idx];
+                if !consider(key) {
+                    stale.push(key.clone());
+                }
+            }
+        }
+
+        // Keys that vanished from the store concurrently are dropped from tracking
+        for key in stale {
+            self.remove(&key);
+        }
+
+        best.map(|(key, _)| key)
+    }
+}
+
+/// Per-access bookkeeping for the frequency- and cost-based policies
+///
+/// Updated on the read path without taking the eviction lock.
+pub(crate) struct AccessTracker {
+    /// Access frequency sketch (W-TinyLFU only)
+    sketch: Option<FrequencySketch>,
+    /// GreedyDual-Size priorities (cost-aware only)
+    greedy_dual: Option<GreedyDual>,
+}
+
+impl AccessTracker {
+    /// Create the trackers required by a policy
+    ///
+    /// Nothing is tracked when the cache is unbounded, since nothing is evicted.
+    pub(crate) fn new(policy: EvictionPolicy, estimated_entries: usize, bounded: bool) -> Self {
+        Self {
+            sketch: (bounded && policy == EvictionPolicy::TinyLfu)
+                .then(|| FrequencySketch::new(estimated_entries)),
+            greedy_dual: (bounded && policy == EvictionPolicy::CostAware)
+                .then(GreedyDual::new),
+        }
+    }
+
+    /// Record a lookup or write attempt for a key (hit or miss)
+    pub(crate) fn record_request(&self, key: &str) {
+        if let Some(sketch) = &self.sketch {
+            sketch.increment(key);
+        }
+    }
+
+    /// Record that an entry was written or served
+    ///
+    /// `weight` is only evaluated when the cost-aware policy is active.
+    pub(crate) fn record_access(&self, key: &str, cost: u64, weight: impl FnOnce() -> usize) {
+        if let Some(greedy_dual) = &self.greedy_dual {
+            greedy_dual.touch(key, cost, weight());
+        }
+    }
+
+    /// Record that an entry is being evicted by the policy
+    pub(crate) fn record_eviction(&self, key: &str) {
+        if let Some(greedy_dual) = &self.greedy_dual {
+            greedy_dual.inflate(greedy_dual.priority(key));
+        }
+    }
+
+    /// Forget a removed key
+    pub(crate) fn remove(&self, key: &str) {
+        if let Some(greedy_dual) = &self.greedy_dual {
+            greedy_dual.priorities.remove(key);
+        }
+    }
+
+    /// Reset all tracking state
+    pub(crate) fn clear(&self) {
+        if let Some(sketch) = &self.sketch {
+            sketch.clear();
+        }
+        if let Some(greedy_dual) = &self.greedy_dual {
+            greedy_dual.priorities.clear();
+            greedy_dual.inflation.store(0f64.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    /// GreedyDual priority of a key (lower = evicted first)
+    fn priority(&self, key: &str) -> f64 {
+        self.greedy_dual
+            .as_ref()
+            .map_or(0.0, |greedy_dual| greedy_dual.priority(key))
+    }
+}
+
+/// GreedyDual-Size priorities
+///
+/// Each entry gets `H = L + cost / weight` when written or read. The entry
+/// with the lowest `H` is evicted and `L` is raised to its `H`, so entries
+/// that are not touched again gradually lose their cost advantage.
+struct GreedyDual {
+    /// Inflation value `L` (f64 bits)
+    inflation: AtomicU64,
+    /// Priority `H` per key
+    priorities: DashMap<String, f64>,
+}
+
+impl GreedyDual {
+    fn new() -> Self {
+        Self {
+            inflation: AtomicU64::new(0f64.to_bits()),
+            priorities: DashMap::new(),
+        }
+    }
+
+    /// Reset a key's priority after a write or hit
+    fn touch(&self, key: &str, cost: u64, weight: usize) {
+        let inflation = f64::from_bits(self.inflation.load(Ordering::Relaxed));
+        let priority = inflation + cost as f64 / weight.max(1) as f64;
+        self.priorities.insert(key.to_string(), priority);
+    }
+
+    /// Current priority of a key (untracked keys rank at the inflation floor)
+    fn priority(&self, key: &str) -> f64 {
+        self.priorities
+            .get(key)
+            .map(|p| *p)
+            .unwrap_or_else(|| f64::from_bits(self.inflation.load(Ordering::Relaxed)))
+    }
+
+    /// Raise `L` to the priority of an evicted entry
+    fn inflate(&self, priority: f64) {
+        let _ = self
+            .inflation
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                (priority > f64::from_bits(bits)).then(|| priority.to_bits())
+            });
+    }
+}
+
+/// Count-min sketch with 4-bit counters used by W-TinyLFU
+///
+/// Estimates how often a key was requested recently. Counters are halved
+/// periodically so that old popularity fades out. Uses atomic operations so
+/// reads can record accesses without taking a lock.
+pub(crate) struct FrequencySketch {
+    /// 16 four-bit counters per word
+    table: Box<[AtomicU64]>,
+    /// Number of increments since the last reset
+    additions: AtomicUsize,
+    /// Increments after which all counters are halved
+    sample_size: usize,
+}
+
+impl FrequencySketch {
+    /// Number of counters consulted per key
+    const DEPTH: u64 = 4;
+
+    /// Create a sketch sized for the given entry capacity
+    pub(crate) fn new(capacity: usize) -> Self {
+        let words = capacity.max(64).next_power_of_two();
+        let table: Box<[AtomicU64]> = (0..words)
+            .map(|_| AtomicU64::new(0))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        Self {
+            table,
+            additions: AtomicUsize::new(0),
+            sample_size: capacity.max(16) * 10,
+        }
+    }
+
+    /// Record an access to a key
+    pub(crate) fn increment(&self, key: &str) {
+        let (h1, h2) = Self::hashes(key);
+        for i in 0..Self::DEPTH {
+            let (word, shift) = self.slot(h1, h2, i);
+            let _ = self.table[word].fetch_update(Ordering::Relaxed, Ordering::Relaxed, |w| {
+                ((w >> shift) & 0xF < 0xF).then(|| w + (1 << shift))
+            });
+        }
+
+        if self.additions.fetch_add(1, Ordering::Relaxed) + 1 >= self.sample_size {
+            self.reset();
+        }
+    }
+
+    /// Estimate how often a key was accessed
+    pub(crate) fn frequency(&self, key: &str) -> u64 {
+        let (h1, h2) = Self::hashes(key);
+        (0..Self::DEPTH)
+            .map(|i| {
+                let (word, shift) = self.slot(h1, h2, i);
+                (self.table[word].load(Ordering::Relaxed) >> shift) & 0xF
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Reset all counters
+    pub(crate) fn clear(&self) {
+        for word in self.table.iter() {
+            word.store(0, Ordering::Relaxed);
+        }
+        self.additions.store(0, Ordering::Relaxed);
+    }
+
+    /// Halve every counter (aging)
+    fn reset(&self) {
+        self.additions.store(0, Ordering::Relaxed);
+        for word in self.table.iter() {
+            let _ = word.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |w| {
+                Some((w >> 1) & 0x7777_7777_7777_7777)
+            });
+        }
+    }
+
+    /// Word index and bit shift of the i-th counter for a key
+    fn slot(&self, h1: u64, h2: u64, i: u64) -> (usize, u32) {
+        let counters = (self.table.len() * 16) as u64;
+        let idx = h1.wrapping_add(i.wrapping_mul(h2)) % counters;
+        ((idx / 16) as usize, ((idx % 16) * 4) as u32)
+    }
+
+    /// Double hashing, as in the bloom filter
+    fn hashes(key: &str) -> (u64, u64) {
+        let mut hasher1 = std::collections::hash_map::DefaultHasher::new();
+        key.hash(&mut hasher1);
+
+        let mut hasher2 = std::collections::hash_map::DefaultHasher::new();
+        (key, 0x9e3779b97f4a7c15u64).hash(&mut hasher2);
+
+        (hasher1.finish(), hasher2.finish() | 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_insert_remove() {
+        let mut state = EvictionState::new(EvictionPolicy::Lru, 10);
+        state.insert("a");
+        state.insert("b");
+        state.insert("c");
+        state.insert("a");
+        assert_eq!(state.keys.len(), 3);
+
+        state.remove("a");
+        assert_eq!(state.keys.len(), 2);
+        assert_eq!(state.positions["c"], 0);
+        assert_eq!(state.positions["b"], 1);
+
+        state.clear();
+        assert!(state.keys.is_empty());
+    }
+
+    #[test]
+    fn test_sketch_frequency() {
+        let sketch = FrequencySketch::new(100);
+        for _ in 0..5 {
+            sketch.increment("hot");
+        }
+        sketch.increment("cold");
+
+        assert!(sketch.frequency("hot") >= 5);
+        assert!(sketch.frequency("hot") > sketch.frequency("cold"));
+        assert_eq!(sketch.frequency("never"), 0);
+    }
+
+    #[test]
+    fn test_sketch_counters_saturate_and_age() {
+        let sketch = FrequencySketch::new(16);
+        for _ in 0..20 {
+            sketch.increment("key");
+        }
+        assert_eq!(sketch.frequency("key"), 15);
+
+        sketch.reset();
+        assert_eq!(sketch.frequency("key"), 7);
+
+        sketch.clear();
+        assert_eq!(sketch.frequency("key"), 0);
+    }
+
+    #[test]
+    fn test_greedy_dual_priorities() {
+        let tracker = AccessTracker::new(EvictionPolicy::CostAware, 100, true);
+        tracker.record_access("cheap", 1, || 100);
+        tracker.record_access("expensive", 1_000, || 100);
+        assert!(tracker.priority("cheap") < tracker.priority("expensive"));
+
+        // Evicting raises the floor for entries written afterwards
+        tracker.record_eviction("cheap");
+        tracker.record_access("fresh", 1, || 100);
+        assert!(tracker.priority("fresh") > tracker.priority("cheap"));
+
+        tracker.remove("expensive");
+        tracker.clear();
+        assert_eq!(tracker.priority("fresh"), 0.0);
+    }
+
+    #[test]
+    fn test_policy_as_str() {
+        assert_eq!(EvictionPolicy::Lru.as_str(), "lru");
+        assert_eq!(EvictionPolicy::TinyLfu.as_str(), "tinylfu");
+        assert_eq!(EvictionPolicy::CostAware.as_str(), "cost_aware");
+    }
+}
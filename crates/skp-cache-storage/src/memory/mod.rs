@@ -2,8 +2,11 @@
 
 mod backend;
 mod bloom;
+mod eviction;
+mod prefix_index;
 mod ttl_index;
 
-pub use backend::{MemoryBackend, MemoryConfig};
-pub use bloom::BloomFilter;
+pub use backend::{MemoryBackend, MemoryConfig, Weigher};
+pub use bloom::{BloomFilter, KeyFilter};
+pub use eviction::EvictionPolicy;
 
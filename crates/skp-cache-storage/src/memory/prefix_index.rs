@@ -0,0 +1,64 @@
+//! Ordered key index for prefix scans
+
+use parking_lot::RwLock;
+use std::collections::BTreeSet;
+use std::ops::Bound;
+
+/// Sorted set of stored keys
+///
+/// Keys sharing a prefix are adjacent, so the candidates for a glob pattern
+/// are found with a range scan over its literal prefix instead of a scan of
+/// the whole store.
+#[derive(Debug, Default)]
+pub(crate) struct PrefixIndex {
+    keys: RwLock<BTreeSet<String>>,
+}
+
+impl PrefixIndex {
+    /// Add a key
+    pub(crate) fn insert(&self, key: &str) {
+        self.keys.write().insert(key.to_string());
+    }
+
+    /// Remove a key
+    pub(crate) fn remove(&self, key: &str) {
+        self.keys.write().remove(key);
+    }
+
+    /// Remove all keys
+    pub(crate) fn clear(&self) {
+        self.keys.write().clear();
+    }
+
+    /// Keys starting with `prefix`, in order
+    pub(crate) fn with_prefix(&self, prefix: &str) -> Vec<String> {
+        self.keys
+            .read()
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_scan() {
+        let index = PrefixIndex::default();
+        for key in ["user:1", "user:10", "user:2", "users", "post:1", "user"] {
+            index.insert(key);
+        }
+        index.remove("user:2");
+
+        assert_eq!(index.with_prefix("user:"), vec!["user:1", "user:10"]);
+        assert_eq!(index.with_prefix("user").len(), 4);
+        assert_eq!(index.with_prefix("").len(), 5);
+        assert!(index.with_prefix("zzz").is_empty());
+
+        index.clear();
+        assert!(index.with_prefix("").is_empty());
+    }
+}
@@ -3,119 +3,251 @@
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
-/// Time-wheel based TTL index for O(1) expiration lookups
+/// Slots per wheel level
+const SLOTS: usize = 64;
+
+/// Bits of the tick counter addressed by one level
+const SLOT_BITS: u32 = 6;
+
+/// Number of wheel levels (64^6 ticks, ~2.2 years at 1 ms resolution)
+const LEVELS: usize = 6;
+
+/// Where a scheduled key is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+    /// Slot in one of the wheel levels
+    Wheel { level: usize, slot: usize },
+    /// Further away than the top level covers
+    Overflow,
+}
+
+/// Hierarchical timing wheel for O(1) expiration lookups
 ///
-/// Instead of scanning all entries to find expired ones,
-/// this maintains buckets of keys organized by expiration time.
+/// Level 0 has one slot per tick; every level above covers 64 slots of the
+/// level below. Keys far in the future sit in coarse slots and cascade down
+/// as their deadline approaches, so TTLs of any length are supported without
+/// sizing the wheel and nothing wraps around early. Keys beyond the top level
+/// are kept in an overflow set until they come within range.
 pub struct TtlIndex {
-    /// Tick duration (bucket resolution)
-    tick_duration: Duration,
-    /// Buckets of keys by expiration slot
-    buckets: Vec<HashSet<String>>,
-    /// Current bucket index
-    current: usize,
-    /// Map of key -> bucket index for O(1) removal
-    key_to_bucket: HashMap<String, usize>,
-    /// Last tick time
-    last_tick: Instant,
+    /// Tick duration (resolution of level 0)
+    resolution: Duration,
+    /// Reference point for tick numbers
+    start: Instant,
+    /// Ticks processed so far
+    elapsed: u64,
+    /// `LEVELS` levels of `SLOTS` slots
+    levels: Vec<Vec<HashSet<String>>>,
+    /// Bitmask of non-empty slots per level
+    occupied: [u64; LEVELS],
+    /// Keys too far in the future for the wheel
+    overflow: HashSet<String>,
+    /// Map of key -> (deadline tick, location) for O(1) removal
+    keys: HashMap<String, (u64, Location)>,
 }
 
 impl TtlIndex {
     /// Create a new TTL index
     ///
     /// # Arguments
-    /// * `tick_duration` - Resolution of each time bucket (e.g., 1 second)
-    /// * `max_ttl` - Maximum TTL to support (determines number of buckets)
-    pub fn new(tick_duration: Duration, max_ttl: Duration) -> Self {
-        let tick_secs = tick_duration.as_secs().max(1);
-        let max_secs = max_ttl.as_secs();
-        let num_buckets = ((max_secs / tick_secs) as usize + 1).max(60);
-
+    /// * `resolution` - Duration of one tick (e.g., 1 millisecond)
+    pub fn new(resolution: Duration) -> Self {
         Self {
-            tick_duration,
-            buckets: vec![HashSet::new(); num_buckets],
-            current: 0,
-            key_to_bucket: HashMap::new(),
-            last_tick: Instant::now(),
+            resolution: resolution.max(Duration::from_nanos(1)),
+            start: Instant::now(),
+            elapsed: 0,
+            levels: (0..LEVELS)
+                .map(|_| (0..SLOTS).map(|_| HashSet::new()).collect())
+                .collect(),
+            occupied: [0; LEVELS],
+            overflow: HashSet::new(),
+            keys: HashMap::new(),
         }
     }
 
     /// Schedule a key for expiration after `ttl`
     pub fn schedule(&mut self, key: String, ttl: Duration) {
-        // Remove from old bucket if exists
-        self.remove(&key);
-
-        let tick_secs = self.tick_duration.as_secs().max(1);
-        let ticks = (ttl.as_secs() / tick_secs) as usize;
-        let bucket_idx = (self.current + ticks + 1) % self.buckets.len();
+        let expires_in = self.start.elapsed().as_nanos() + ttl.as_nanos();
+        // Round up so keys never fire before their TTL
+        let deadline = expires_in.div_ceil(self.resolution.as_nanos());
+        self.schedule_deadline(key, u64::try_from(deadline).unwrap_or(u64::MAX));
+    }
 
-        self.buckets[bucket_idx].insert(key.clone());
-        self.key_to_bucket.insert(key, bucket_idx);
+    /// Schedule a key for expiration at an absolute tick
+    fn schedule_deadline(&mut self, key: String, deadline: u64) {
+        // Remove from old slot if exists
+        self.remove(&key);
+        // The current tick has already been processed
+        self.insert(key, deadline.max(self.elapsed + 1));
     }
 
     /// Remove a key from the index
     pub fn remove(&mut self, key: &str) {
-        if let Some(bucket_idx) = self.key_to_bucket.remove(key) {
-            self.buckets[bucket_idx].remove(key);
+        match self.keys.remove(key) {
+            Some((_, Location::Wheel { level, slot })) => {
+                let bucket = &mut self.levels[level][slot];
+                bucket.remove(key);
+                if bucket.is_empty() {
+                    self.occupied[level] &= !(1 << slot);
+                }
+            }
+            Some((_, Location::Overflow)) => {
+                self.overflow.remove(key);
+            }
+            None => {}
         }
     }
 
     /// Check if a key is scheduled
+    #[allow(dead_code)]
     pub fn contains(&self, key: &str) -> bool {
-        self.key_to_bucket.contains_key(key)
+        self.keys.contains_key(key)
     }
 
     /// Advance the wheel and return expired keys
     pub fn tick(&mut self) -> Vec<String> {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_tick);
-        let tick_secs = self.tick_duration.as_secs().max(1);
-        let ticks_to_advance = (elapsed.as_secs() / tick_secs) as usize;
-
-        if ticks_to_advance == 0 {
-            return Vec::new();
-        }
-
-        let mut expired = Vec::new();
-
-        // Advance through buckets, collecting expired keys
-        for _ in 0..ticks_to_advance.min(self.buckets.len()) {
-            self.current = (self.current + 1) % self.buckets.len();
-            let bucket_expired: Vec<String> = self.buckets[self.current].drain().collect();
+        let now = self.ticks_at(Instant::now());
+        self.advance(now)
+    }
 
-            for key in &bucket_expired {
-                self.key_to_bucket.remove(key);
+    /// Earliest deadline among scheduled keys
+    pub fn next_deadline(&self) -> Option<Instant> {
+        // Every key on a level expires before any key on the levels above it,
+        // and occupied slots always lie ahead of the current position.
+        for level in 0..LEVELS {
+            if self.occupied[level] != 0 {
+                let slot = self.occupied[level].trailing_zeros() as usize;
+                let deadline = self.levels[level][slot]
+                    .iter()
+                    .filter_map(|key| self.keys.get(key).map(|(deadline, _)| *deadline))
+                    .min()?;
+                return self.instant_at(deadline);
             }
-
-            expired.extend(bucket_expired);
         }
 
-        self.last_tick = now;
-        expired
+        let deadline = self
+            .overflow
+            .iter()
+            .filter_map(|key| self.keys.get(key).map(|(deadline, _)| *deadline))
+            .min()?;
+        self.instant_at(deadline)
     }
 
     /// Get the number of scheduled keys
+    #[allow(dead_code)]
     pub fn len(&self) -> usize {
-        self.key_to_bucket.len()
+        self.keys.len()
     }
 
     /// Check if empty
+    #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
-        self.key_to_bucket.is_empty()
+        self.keys.is_empty()
     }
 
     /// Clear all scheduled keys
     pub fn clear(&mut self) {
-        for bucket in &mut self.buckets {
-            bucket.clear();
+        for level in &mut self.levels {
+            for bucket in level.iter_mut() {
+                bucket.clear();
+            }
+        }
+        self.occupied = [0; LEVELS];
+        self.overflow.clear();
+        self.keys.clear();
+    }
+
+    /// Move the wheel to tick `now`, cascading keys down and returning expired ones
+    fn advance(&mut self, now: u64) -> Vec<String> {
+        if now <= self.elapsed {
+            return Vec::new();
+        }
+        let old = self.elapsed;
+        self.elapsed = now;
+
+        // Drain every slot the clock moved into or past on each level
+        let mut due = Vec::new();
+        for level in 0..LEVELS {
+            let shift = level as u32 * SLOT_BITS;
+            let first = (old >> shift) + 1;
+            let last = now >> shift;
+            if last < first {
+                continue;
+            }
+            let passed = (last - first + 1).min(SLOTS as u64);
+            for idx in first..first + passed {
+                let slot = (idx % SLOTS as u64) as usize;
+                if self.occupied[level] & (1 << slot) != 0 {
+                    due.extend(self.levels[level][slot].drain());
+                    self.occupied[level] &= !(1 << slot);
+                }
+            }
+        }
+
+        // Overflow keys may come within range once the top level rolls over
+        let span_bits = LEVELS as u32 * SLOT_BITS;
+        if old >> span_bits != now >> span_bits {
+            due.extend(self.overflow.drain());
+        }
+
+        let mut expired = Vec::new();
+        for key in due {
+            let Some((deadline, _)) = self.keys.remove(&key) else {
+                continue;
+            };
+            if deadline <= now {
+                expired.push(key);
+            } else {
+                // Not due yet: cascade to a finer slot
+                self.insert(key, deadline);
+            }
+        }
+        expired
+    }
+
+    /// Place a key relative to the current position (`deadline > elapsed`)
+    fn insert(&mut self, key: String, deadline: u64) {
+        let location = self.location_for(deadline);
+        match location {
+            Location::Wheel { level, slot } => {
+                self.levels[level][slot].insert(key.clone());
+                self.occupied[level] |= 1 << slot;
+            }
+            Location::Overflow => {
+                self.overflow.insert(key.clone());
+            }
+        }
+        self.keys.insert(key, (deadline, location));
+    }
+
+    /// Level is chosen by the highest bit in which deadline and current tick differ
+    fn location_for(&self, deadline: u64) -> Location {
+        let significant = (deadline ^ self.elapsed) | (SLOTS as u64 - 1);
+        let level = ((63 - significant.leading_zeros()) / SLOT_BITS) as usize;
+        if level >= LEVELS {
+            return Location::Overflow;
         }
-        self.key_to_bucket.clear();
+        let slot = ((deadline >> (level as u32 * SLOT_BITS)) % SLOTS as u64) as usize;
+        Location::Wheel { level, slot }
+    }
+
+    /// Convert an instant to a tick number
+    fn ticks_at(&self, instant: Instant) -> u64 {
+        let ticks =
+            instant.saturating_duration_since(self.start).as_nanos() / self.resolution.as_nanos();
+        u64::try_from(ticks).unwrap_or(u64::MAX)
+    }
+
+    /// Convert a tick number to an instant
+    fn instant_at(&self, ticks: u64) -> Option<Instant> {
+        let nanos = self.resolution.as_nanos().checked_mul(u128::from(ticks))?;
+        self.start
+            .checked_add(Duration::from_nanos(u64::try_from(nanos).ok()?))
     }
 }
 
 impl Default for TtlIndex {
     fn default() -> Self {
-        Self::new(Duration::from_secs(1), Duration::from_secs(86400))
+        Self::new(Duration::from_millis(1))
     }
 }
 
@@ -125,7 +257,7 @@ mod tests {
 
     #[test]
     fn test_schedule_and_remove() {
-        let mut index = TtlIndex::new(Duration::from_secs(1), Duration::from_secs(60));
+        let mut index = TtlIndex::new(Duration::from_secs(1));
 
         index.schedule("key1".to_string(), Duration::from_secs(10));
         assert!(index.contains("key1"));
@@ -138,7 +270,7 @@ mod tests {
 
     #[test]
     fn test_clear() {
-        let mut index = TtlIndex::new(Duration::from_secs(1), Duration::from_secs(60));
+        let mut index = TtlIndex::new(Duration::from_secs(1));
 
         index.schedule("key1".to_string(), Duration::from_secs(10));
         index.schedule("key2".to_string(), Duration::from_secs(20));
@@ -147,11 +279,12 @@ mod tests {
         index.clear();
         assert_eq!(index.len(), 0);
         assert!(index.is_empty());
+        assert!(index.next_deadline().is_none());
     }
 
     #[test]
     fn test_reschedule() {
-        let mut index = TtlIndex::new(Duration::from_secs(1), Duration::from_secs(60));
+        let mut index = TtlIndex::new(Duration::from_secs(1));
 
         index.schedule("key1".to_string(), Duration::from_secs(10));
         index.schedule("key1".to_string(), Duration::from_secs(20));
@@ -159,4 +292,84 @@ mod tests {
         // Should only be in one bucket
         assert_eq!(index.len(), 1);
     }
+
+    #[test]
+    fn test_millisecond_resolution() {
+        let mut index = TtlIndex::default();
+        index.schedule_deadline("key".to_string(), 5);
+
+        assert!(index.advance(4).is_empty());
+        assert_eq!(index.advance(5), vec!["key".to_string()]);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_long_ttl_does_not_wrap() {
+        let mut index = TtlIndex::default();
+        // Two days in milliseconds, far beyond a single level
+        let deadline = 2 * 86_400_000;
+        index.schedule_deadline("key".to_string(), deadline);
+
+        assert!(index.advance(deadline / 2).is_empty());
+        assert!(index.advance(deadline - 1).is_empty());
+        assert_eq!(index.advance(deadline), vec!["key".to_string()]);
+    }
+
+    #[test]
+    fn test_cascade_expires_each_key_on_time() {
+        let mut index = TtlIndex::default();
+        let deadlines = [1u64, 63, 64, 65, 4_095, 4_096, 70_000, 300_000, 262_145];
+        for (i, deadline) in deadlines.iter().enumerate() {
+            index.schedule_deadline(format!("key:{}", i), *deadline);
+        }
+
+        let mut now = 0;
+        let mut expired = 0;
+        while now < 310_000 {
+            let previous = now;
+            now += 997;
+            for key in index.advance(now) {
+                let i: usize = key[4..].parse().unwrap();
+                assert!(
+                    deadlines[i] > previous && deadlines[i] <= now,
+                    "{} fired at {}",
+                    key,
+                    now
+                );
+                expired += 1;
+            }
+        }
+        assert_eq!(expired, deadlines.len());
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_overflow_keys() {
+        let mut index = TtlIndex::default();
+        let deadline = 1u64 << 40;
+        index.schedule_deadline("far".to_string(), deadline);
+        index.schedule_deadline("near".to_string(), 10);
+
+        assert_eq!(index.advance(1 << 37), vec!["near".to_string()]);
+        assert!(index.contains("far"));
+        assert!(index.advance(deadline - 1).is_empty());
+        assert_eq!(index.advance(deadline), vec!["far".to_string()]);
+    }
+
+    #[test]
+    fn test_next_deadline() {
+        let mut index = TtlIndex::default();
+        assert!(index.next_deadline().is_none());
+
+        index.schedule_deadline("a".to_string(), 300);
+        index.schedule_deadline("b".to_string(), 70);
+        index.schedule_deadline("c".to_string(), 5_000);
+        assert_eq!(index.next_deadline(), index.instant_at(70));
+
+        index.advance(70);
+        assert_eq!(index.next_deadline(), index.instant_at(300));
+
+        index.remove("a");
+        assert_eq!(index.next_deadline(), index.instant_at(5_000));
+    }
 }
@@ -1,27 +1,409 @@
 use async_trait::async_trait;
 use skp_cache_core::{
-    CacheBackend, CacheEntry, CacheError, CacheOptions, CacheStats, DependencyBackend, Result, TaggableBackend,
+    CacheBackend, CacheEntry, CacheError, CacheMetrics, CacheOperation, CacheOptions, CacheStats, DependencyBackend,
+    KeyPattern, NoopMetrics, PatternBackend, Result, TaggableBackend,
 };
+use std::sync::Arc;
 use super::circuit_breaker::CircuitBreaker;
+use super::degradation::{DegradationPolicy, DegradedMode, ReconcileQueue, Reconciliation};
+use super::generations::{Generation, Generations};
+use super::write_behind::{Flusher, WriteBehindConfig, WriteQueue};
+#[cfg(feature = "redis")]
+use super::invalidation::InvalidationListener;
+use crate::memory::KeyFilter;
+#[cfg(feature = "redis")]
+use crate::redis::{InvalidationEvent, RedisBackend};
 
 /// Multi-tier backend combining L1 (fast, local) and L2 (slow, remote) caches
 pub struct MultiTierBackend<L1, L2> {
-    l1: L1,
+    pub(super) l1: L1,
     l2: L2,
     circuit_breaker: CircuitBreaker,
+    key_filter: Option<KeyFilter>,
+    metrics: Arc<dyn CacheMetrics>,
+    degradation: DegradationPolicy,
+    /// Operations to replay on L2 under `DegradedMode::Reconcile`
+    reconcile: ReconcileQueue,
+    /// Writes waiting to be flushed to L2 in write-behind mode
+    pub(super) write_queue: Option<Arc<WriteQueue>>,
+    flusher: Option<Flusher>,
+    /// Publishes invalidations to other instances
+    #[cfg(feature = "redis")]
+    pub(super) invalidation: Option<RedisBackend>,
+    /// Evicts L1 entries invalidated by other instances
+    #[cfg(feature = "redis")]
+    pub(super) listener: Option<InvalidationListener>,
+    /// Invalidations applied by the listener, checked by L1 backfills
+    pub(super) generations: Arc<Generations>,
 }
 
 impl<L1, L2> MultiTierBackend<L1, L2> {
     /// Create a new multi-tier backend
     pub fn new(l1: L1, l2: L2, circuit_breaker: CircuitBreaker) -> Self {
+        Self::with_metrics(l1, l2, circuit_breaker, NoopMetrics)
+    }
+
+    /// Create a new multi-tier backend reporting write-behind activity and
+    /// degraded operations to `metrics`
+    pub fn with_metrics(
+        l1: L1,
+        l2: L2,
+        circuit_breaker: CircuitBreaker,
+        metrics: impl CacheMetrics,
+    ) -> Self {
+        let metrics: Arc<dyn CacheMetrics> = Arc::new(metrics);
+        let degradation = DegradationPolicy::default();
         Self {
             l1,
             l2,
             circuit_breaker,
+            key_filter: None,
+            reconcile: ReconcileQueue::new(degradation.reconcile_capacity, metrics.clone()),
+            degradation,
+            metrics,
+            write_queue: None,
+            flusher: None,
+            #[cfg(feature = "redis")]
+            invalidation: None,
+            #[cfg(feature = "redis")]
+            listener: None,
+            generations: Arc::default(),
+        }
+    }
+
+    /// Create a multi-tier backend that is the only writer of its L2
+    ///
+    /// Every write records its key in `filter`; an L1 miss for a key the filter
+    /// has never seen is answered without an L2 round-trip. The filter only
+    /// learns writes made through this backend, so L2 must be populated by it
+    /// alone: keys written by other processes or instances, or before it was
+    /// created, read as misses. Deleted keys are forgotten by rotating to a
+    /// fresh filter once all keys of the old one have expired, so rotation
+    /// only happens while every write carries a TTL.
+    pub fn single_writer(l1: L1, l2: L2, circuit_breaker: CircuitBreaker, filter: KeyFilter) -> Self {
+        Self::single_writer_with_metrics(l1, l2, circuit_breaker, filter, NoopMetrics)
+    }
+
+    /// Create a single-writer multi-tier backend reporting to `metrics`
+    ///
+    /// See `single_writer`.
+    pub fn single_writer_with_metrics(
+        l1: L1,
+        l2: L2,
+        circuit_breaker: CircuitBreaker,
+        filter: KeyFilter,
+        metrics: impl CacheMetrics,
+    ) -> Self {
+        Self {
+            key_filter: Some(filter),
+            ..Self::with_metrics(l1, l2, circuit_breaker, metrics)
+        }
+    }
+
+    /// Choose what operations do while the circuit breaker is open
+    ///
+    /// Without a policy, reads are answered from L1 and writes and deletes
+    /// fail (`DegradationPolicy::default`).
+    pub fn with_degradation_policy(mut self, policy: DegradationPolicy) -> Self {
+        self.reconcile = ReconcileQueue::new(policy.reconcile_capacity, self.metrics.clone());
+        self.degradation = policy;
+        self
+    }
+
+    /// Number of writes waiting to be flushed to L2 (0 without write-behind)
+    pub fn pending_writes(&self) -> usize {
+        self.write_queue.as_ref().map_or(0, |queue| queue.len())
+    }
+
+    /// Number of writes and deletes waiting to be replayed on L2 once it recovers
+    pub fn pending_reconciliation(&self) -> usize {
+        self.reconcile.len()
+    }
+
+    /// Number of writes that never reached L2, from write-behind or reconciliation
+    pub fn dropped_writes(&self) -> u64 {
+        self.write_queue.as_ref().map_or(0, |queue| queue.dropped()) + self.reconcile.dropped()
+    }
+
+    /// Report an operation handled without L2, failing it under `FailFast`
+    fn degrade(&self, mode: DegradedMode, operation: CacheOperation) -> Result<()> {
+        self.metrics.record_degraded(operation);
+        match mode {
+            DegradedMode::FailFast => Err(CacheError::Backend("Circuit breaker open".to_string())),
+            DegradedMode::L1Only | DegradedMode::Reconcile => Ok(()),
+        }
+    }
+
+    /// Mode of deletes that cannot be replayed key by key
+    fn bulk_delete_mode(&self) -> DegradedMode {
+        match self.degradation.deletes {
+            DegradedMode::Reconcile => DegradedMode::FailFast,
+            mode => mode,
+        }
+    }
+
+    /// Queue an operation for replay if `mode` is `Reconcile`
+    fn reconcile_later(&self, mode: DegradedMode, op: impl FnOnce() -> Reconciliation) {
+        if mode == DegradedMode::Reconcile {
+            self.reconcile.push(op());
+        }
+    }
+
+    /// Queue that takes writes, if write-behind is running
+    fn accepting_queue(&self) -> Option<&WriteQueue> {
+        self.write_queue
+            .as_deref()
+            .filter(|queue| queue.is_accepting())
+    }
+
+    /// Pending write of `key`, for reads that miss L1
+    fn pending_entry(&self, key: &str) -> Option<CacheEntry<Vec<u8>>> {
+        self.write_queue.as_ref()?.entry(key)
+    }
+
+    /// Drop pending writes matching `filter` so they cannot resurrect deleted keys
+    async fn discard_pending(&self, filter: impl Fn(&str, &CacheOptions) -> bool + Send) {
+        if let Some(queue) = &self.write_queue {
+            queue.discard(filter).await;
+        }
+    }
+
+    /// Check the key filter: `false` if the key was never written
+    fn might_contain(&self, key: &str) -> bool {
+        self.key_filter
+            .as_ref()
+            .is_none_or(|filter| filter.might_contain(key))
+    }
+
+    /// Record a successful write in the key filter
+    fn record_write(&self, key: &str, options: &CacheOptions) {
+        if let Some(filter) = &self.key_filter {
+            let lifetime = options
+                .ttl
+                .map(|ttl| ttl + options.stale_while_revalidate.unwrap_or_default());
+            filter.insert(key, lifetime);
+            filter.rotate();
+        }
+    }
+
+    /// Keys and options of `set_many` entries
+    fn writes<'a>(entries: &[(&'a str, Vec<u8>, &'a CacheOptions)]) -> Vec<(&'a str, &'a CacheOptions)> {
+        entries
+            .iter()
+            .map(|(key, _, options)| (*key, *options))
+            .collect()
+    }
+
+    /// Options for mirroring a successful L2 write into L1
+    ///
+    /// L2 is the authority for versions: L1 must not re-check `if_version`, and a
+    /// successful compare-and-set means L2 now holds the expected version plus one.
+    fn l1_options(options: &CacheOptions) -> CacheOptions {
+        CacheOptions {
+            if_version: None,
+            version: options
+                .version
+                .or(options.if_version.map(|expected| expected + 1)),
+            ..options.clone()
+        }
+    }
+
+    /// Record removed keys in the key filter
+    fn record_removals(&self, count: u64) {
+        if let Some(filter) = &self.key_filter {
+            filter.record_removals(count as usize);
+        }
+    }
+
+    /// Tell other instances to evict `keys` from their L1
+    #[cfg_attr(not(feature = "redis"), allow(unused_variables))]
+    async fn publish_keys(&self, keys: &[&str]) {
+        #[cfg(feature = "redis")]
+        {
+            let events: Vec<InvalidationEvent> = keys
+                .iter()
+                .map(|key| InvalidationEvent::Key(key.to_string()))
+                .collect();
+            self.publish(&events).await;
+        }
+    }
+
+    /// Tell other instances to evict entries tagged `tag` from their L1
+    #[cfg_attr(not(feature = "redis"), allow(unused_variables))]
+    async fn publish_tag(&self, tag: &str) {
+        #[cfg(feature = "redis")]
+        self.publish(&[InvalidationEvent::Tag(tag.to_string())]).await;
+    }
+
+    /// Tell other instances to evict keys matching `pattern` from their L1
+    #[cfg_attr(not(feature = "redis"), allow(unused_variables))]
+    async fn publish_pattern(&self, pattern: &str) {
+        #[cfg(feature = "redis")]
+        self.publish(&[InvalidationEvent::Pattern(pattern.to_string())]).await;
+    }
+
+    /// Tell other instances to clear their L1
+    async fn publish_clear(&self) {
+        #[cfg(feature = "redis")]
+        self.publish(&[InvalidationEvent::Clear]).await;
+    }
+}
+
+impl<L1, L2> MultiTierBackend<L1, L2>
+where
+    L1: CacheBackend,
+    L2: CacheBackend,
+{
+    /// Evict a backfilled L1 entry if `key` was invalidated while it was read
+    ///
+    /// The listener counts an invalidation before evicting, so either it
+    /// evicts the backfilled entry or this sees the new generation.
+    async fn undo_stale_backfill(&self, key: &str, since: Generation) {
+        if self.generations.changed(key, since) {
+            let _ = self.l1.delete(key).await;
+        }
+    }
+
+    /// Settle pending writes of keys about to be written through to L2
+    ///
+    /// A pending write flushed later would overwrite the write-through one.
+    /// Compare-and-set writes flush it first, so the version they check
+    /// includes it; other writes replace it and discard it.
+    async fn settle_pending(&self, writes: &[(&str, &CacheOptions)]) -> Result<()> {
+        let Some(queue) = &self.write_queue else {
+            return Ok(());
+        };
+        let pending = |checked: bool| -> Vec<&str> {
+            writes
+                .iter()
+                .filter(|(key, options)| {
+                    options.if_version.is_some() == checked && queue.contains(key)
+                })
+                .map(|(key, _)| *key)
+                .collect()
+        };
+
+        let replaced = pending(false);
+        if !replaced.is_empty() {
+            queue.discard(|key, _| replaced.contains(&key)).await;
+        }
+        let checked = pending(true);
+        if !checked.is_empty() {
+            queue.flush_keys(&checked, &self.l1, &self.l2, &self.circuit_breaker).await?;
+        }
+        Ok(())
+    }
+
+    /// Check whether L2 may be used, replaying reconciled operations first
+    ///
+    /// Each call replays at most one batch; L2 counts as unavailable until
+    /// the queue is drained or if the replay fails, which reopens the breaker.
+    async fn l2_available(&self) -> bool {
+        if !self.circuit_breaker.allow_request() {
+            return false;
+        }
+        if self.reconcile.is_drained() {
+            return true;
+        }
+        match self.reconcile.replay_batch(&self.l2, &self.circuit_breaker).await {
+            Ok(replayed) => {
+                let keys: Vec<&str> = replayed.iter().map(String::as_str).collect();
+                self.publish_keys(&keys).await;
+                self.reconcile.is_drained()
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+impl<L1, L2> MultiTierBackend<L1, L2>
+where
+    L1: CacheBackend + Clone,
+    L2: CacheBackend + Clone,
+{
+    /// Write to L1 immediately and to L2 in the background
+    ///
+    /// `set` and `set_many` return once L1 is updated and the write is queued.
+    /// Queued writes are coalesced by key and flushed with `set_many` every
+    /// `config.flush_interval`, at most `config.batch_size` at a time, and
+    /// published to other instances once in L2. Writes failing because L2 is
+    /// unavailable are retried on the next flush; writes that do not fit in
+    /// the queue or fail otherwise are dropped and reported to the metrics.
+    /// Until flushed, a queued write carries a provisional version from L1;
+    /// L2 numbers it when it is flushed and the flushed entry is then loaded
+    /// back into L1, so versions never go backwards in L2. The TTL of a queued
+    /// write counts from when it was queued.
+    /// Compare-and-set writes (`CacheOptions::if_version`) still go through
+    /// to L2, after flushing any pending write of their key, so they must
+    /// expect a version read after that flush. Requires a Tokio runtime.
+    ///
+    /// Call `shutdown` to flush pending writes before exiting; dropping the
+    /// backend flushes them in the background.
+    pub fn with_write_behind(mut self, config: WriteBehindConfig) -> Self {
+        let queue = Arc::new(WriteQueue::new(
+            config,
+            self.metrics.clone(),
+            self.generations.clone(),
+        ));
+        #[cfg(feature = "redis")]
+        queue.set_invalidation(self.invalidation.clone());
+        self.flusher = Some(Flusher::spawn(
+            queue.clone(),
+            self.l1.clone(),
+            self.l2.clone(),
+            self.circuit_breaker.clone(),
+        ));
+        self.write_queue = Some(queue);
+        self
+    }
+
+    /// Stop write-behind, flushing pending writes to L2
+    ///
+    /// Later writes go straight to L2. Returns the error of the last flush if
+    /// writes could not be flushed; they stay queued and can be retried with
+    /// `flush`.
+    pub async fn shutdown(&self) -> Result<()> {
+        if let Some(flusher) = &self.flusher {
+            flusher.stop().await;
+        }
+        self.flush().await
+    }
+
+    /// Flush pending writes to L2 now
+    pub async fn flush(&self) -> Result<()> {
+        match &self.write_queue {
+            Some(queue) => queue.flush_all(&self.l1, &self.l2, &self.circuit_breaker).await,
+            None => Ok(()),
         }
     }
 }
 
+/// Options writing `entry`, as read from L2, into L1
+pub(super) fn backfill_options(entry: &CacheEntry<Vec<u8>>) -> CacheOptions {
+    CacheOptions {
+        ttl: entry.ttl,
+        stale_while_revalidate: entry.stale_while_revalidate,
+        tags: entry.tags.clone(),
+        dependencies: entry.dependencies.clone(),
+        cost: Some(entry.cost),
+        compute_time: entry.compute_time,
+        etag: entry.etag.clone(),
+        negative: entry.negative,
+        version: Some(entry.version),
+        ..Default::default()
+    }
+}
+
+/// Accept an L1 write that left out entries too heavy for L1
+///
+/// Such entries are still written to L2 (or queued for it) and read from there.
+fn l1_write(result: Result<()>) -> Result<()> {
+    match result {
+        Err(CacheError::CapacityExceeded) => Ok(()),
+        result => result,
+    }
+}
+
 #[async_trait]
 impl<L1, L2> CacheBackend for MultiTierBackend<L1, L2>
 where
@@ -43,31 +425,35 @@ where
             Ok(None) => {} // Miss
         }
 
+        // Evicted from L1 before reaching L2
+        if let Some(entry) = self.pending_entry(key) {
+            return Ok(Some(entry));
+        }
+
+        // Never written: L2 cannot have it
+        if !self.might_contain(key) {
+            return Ok(None);
+        }
+
         // 2. Check Circuit Breaker for L2
-        if !self.circuit_breaker.allow_request() {
-            // Circuit open - return miss (degraded mode)
+        if !self.l2_available().await {
+            // Circuit open - L1 missed, so nothing to serve
+            self.degrade(self.degradation.reads, CacheOperation::Get)?;
             return Ok(None);
         }
 
         // 3. Try L2 (Redis)
+        let generation = self.generations.current(key);
         match self.l2.get(key).await {
             Ok(Some(entry)) => {
                 self.circuit_breaker.report_success();
                 
                 // 4. Backfill L1
-                // We recreate options from the entry roughly
-                let opts = CacheOptions {
-                    ttl: entry.ttl,
-                    stale_while_revalidate: entry.stale_while_revalidate,
-                    tags: entry.tags.clone(),
-                    dependencies: entry.dependencies.clone(),
-                    cost: Some(entry.cost),
-                    etag: entry.etag.clone(),
-                    ..Default::default()
-                };
+                let opts = backfill_options(&entry);
                 
                 // Ignore L1 set errors (it's just an optimization)
                 let _ = self.l1.set(key, entry.value.clone(), &opts).await;
+                self.undo_stale_backfill(key, generation).await;
                 
                 Ok(Some(entry))
             }
@@ -90,22 +476,45 @@ where
         value: Vec<u8>,
         options: &CacheOptions,
     ) -> Result<()> {
+        // Write-behind: L1 now, L2 when the queue is flushed
+        if let Some(queue) = self.accepting_queue().filter(|_| options.if_version.is_none()) {
+            queue.push(key, value.clone(), options);
+            self.record_write(key, options);
+            l1_write(self.l1.set(key, value, &Self::l1_options(options)).await)?;
+            return Ok(());
+        }
+
         // Write-through: Set L2 then L1
         
-        // Check breaker before L2 write?
-        // Usually writes should fail if backend is down to ensure consistency.
-        if !self.circuit_breaker.allow_request() {
-             return Err(CacheError::Backend("Circuit breaker open".to_string()));
+        if !self.l2_available().await {
+            // A version cannot be checked without L2
+            let mode = match options.if_version {
+                Some(_) => DegradedMode::FailFast,
+                None => self.degradation.writes,
+            };
+            self.degrade(mode, CacheOperation::Set)?;
+            self.settle_pending(&[(key, options)]).await?;
+            self.reconcile_later(mode, || Reconciliation::set(key, value.clone(), options));
+            self.record_write(key, options);
+            return l1_write(self.l1.set(key, value, &Self::l1_options(options)).await);
         }
 
+        self.settle_pending(&[(key, options)]).await?;
         match self.l2.set(key, value.clone(), options).await {
             Ok(_) => {
                 self.circuit_breaker.report_success();
+                self.record_write(key, options);
+                self.publish_keys(&[key]).await;
                 // L2 success, now update L1
                 // We want L1 to reflect L2.
-                self.l1.set(key, value, options).await?;
+                l1_write(self.l1.set(key, value, &Self::l1_options(options)).await)?;
                 Ok(())
             }
+            Err(e @ CacheError::VersionMismatch { .. }) => {
+                // L1 may hold an outdated version: make the next read go to L2
+                let _ = self.l1.delete(key).await;
+                Err(e)
+            }
             Err(e) => {
                 if CircuitBreaker::is_failure(&e) {
                     self.circuit_breaker.report_failure();
@@ -116,6 +525,16 @@ where
     }
 
     async fn delete(&self, key: &str) -> Result<bool> {
+        let pending = self.pending_entry(key).is_some();
+        self.discard_pending(|pending_key, _| pending_key == key).await;
+
+        if !self.l2_available().await {
+            let l1_res = self.l1.delete(key).await;
+            self.degrade(self.degradation.deletes, CacheOperation::Delete)?;
+            self.reconcile_later(self.degradation.deletes, || Reconciliation::Delete(key.to_string()));
+            return Ok(l1_res? || pending);
+        }
+
         // Delete from both. L2 first.
         let l2_res = self.l2.delete(key).await;
         // Even if L2 fails, we should delete from L1 to avoid stale data?
@@ -127,8 +546,12 @@ where
         
         match l2_res {
              Ok(deleted) => {
+                 if deleted {
+                     self.record_removals(1);
+                 }
+                 self.publish_keys(&[key]).await;
                  l1_res?; // Propagate L1 error?
-                 Ok(deleted)
+                 Ok(deleted || pending)
              }
              Err(e) => {
                  // L2 failed.
@@ -142,11 +565,16 @@ where
 
     async fn exists(&self, key: &str) -> Result<bool> {
         // Check L1 first
-        if self.l1.exists(key).await? {
+        if self.l1.exists(key).await? || self.pending_entry(key).is_some() {
             return Ok(true);
         }
+
+        if !self.might_contain(key) {
+            return Ok(false);
+        }
         
-        if !self.circuit_breaker.allow_request() {
+        if !self.l2_available().await {
+            self.degrade(self.degradation.reads, CacheOperation::Get)?;
             return Ok(false);
         }
         
@@ -165,15 +593,30 @@ where
     }
     
     async fn delete_many(&self, keys: &[&str]) -> Result<u64> {
+        self.discard_pending(|pending_key, _| keys.contains(&pending_key)).await;
+
+        if !self.l2_available().await {
+            let l1_res = self.l1.delete_many(keys).await;
+            self.degrade(self.degradation.deletes, CacheOperation::Delete)?;
+            for key in keys {
+                self.reconcile_later(self.degradation.deletes, || Reconciliation::Delete(key.to_string()));
+            }
+            return l1_res;
+        }
+
         let l2_res = self.l2.delete_many(keys).await;
         let _ = self.l1.delete_many(keys).await;
+        if l2_res.is_ok() {
+            self.publish_keys(keys).await;
+        }
         
-        l2_res.map_err(|e| {
-             if CircuitBreaker::is_failure(&e) {
-                 self.circuit_breaker.report_failure();
-             }
-             e
-        })
+        l2_res
+            .inspect(|count| self.record_removals(*count))
+            .inspect_err(|e| {
+                if CircuitBreaker::is_failure(e) {
+                    self.circuit_breaker.report_failure();
+                }
+            })
     }
 
     async fn get_many(
@@ -196,6 +639,10 @@ where
         for (i, res) in l1_results.into_iter().enumerate() {
             if res.is_some() {
                 final_results.push(res);
+            } else if let Some(entry) = self.pending_entry(keys[i]) {
+                final_results.push(Some(entry));
+            } else if !self.might_contain(keys[i]) {
+                final_results.push(None); // Never written
             } else {
                 final_results.push(None); // Placeholder
                 missing_indices.push(i);
@@ -207,10 +654,15 @@ where
             return Ok(final_results);
         }
         
-        if !self.circuit_breaker.allow_request() {
+        if !self.l2_available().await {
+            self.degrade(self.degradation.reads, CacheOperation::Get)?;
             return Ok(final_results); // Return partial results (L1 hits only)
         }
         
+        let generations: Vec<Generation> = missing_keys
+            .iter()
+            .map(|key| self.generations.current(key))
+            .collect();
         match self.l2.get_many(&missing_keys).await {
             Ok(l2_results) => {
                 self.circuit_breaker.report_success();
@@ -219,16 +671,9 @@ where
                     let original_idx = missing_indices[i];
                     if let Some(entry) = l2_res {
                          // Backfill
-                         let opts = CacheOptions {
-                            ttl: entry.ttl,
-                            stale_while_revalidate: entry.stale_while_revalidate,
-                            tags: entry.tags.clone(),
-                            dependencies: entry.dependencies.clone(),
-                            cost: Some(entry.cost),
-                            etag: entry.etag.clone(),
-                            ..Default::default()
-                        };
+                        let opts = backfill_options(&entry);
                         let _ = self.l1.set(keys[original_idx], entry.value.clone(), &opts).await;
+                        self.undo_stale_backfill(keys[original_idx], generations[i]).await;
                         final_results[original_idx] = Some(entry);
                     }
                 }
@@ -238,11 +683,9 @@ where
                 if CircuitBreaker::is_failure(&e) {
                     self.circuit_breaker.report_failure();
                 }
-                // If L2 fails, return current partial results? Or error?
-                // Returning partial (L1 only) results is safer for resilience.
-                // But caller expects Ok implies complete result attempt.
-                // MultiTier strategy usually degrades gracefully.
-                Ok(final_results)
+                // L1-only results are served through the degradation policy,
+                // once the breaker has opened
+                Err(e)
             }
         }
     }
@@ -251,16 +694,72 @@ where
         &self,
         entries: &[(&str, Vec<u8>, &CacheOptions)],
     ) -> Result<()> {
-        if !self.circuit_breaker.allow_request() {
-             return Err(CacheError::Backend("Circuit breaker open".to_string()));
+        if let Some(queue) = self
+            .accepting_queue()
+            .filter(|_| entries.iter().all(|(_, _, options)| options.if_version.is_none()))
+        {
+            let mut l1_options = Vec::with_capacity(entries.len());
+            for (key, value, options) in entries {
+                queue.push(key, value.clone(), options);
+                self.record_write(key, options);
+                l1_options.push(Self::l1_options(options));
+            }
+            let l1_entries: Vec<(&str, Vec<u8>, &CacheOptions)> = entries
+                .iter()
+                .zip(&l1_options)
+                .map(|((key, value, _), options)| (*key, value.clone(), options))
+                .collect();
+            l1_write(self.l1.set_many(&l1_entries).await)?;
+            return Ok(());
+        }
+
+        if !self.l2_available().await {
+            let mode = if entries.iter().all(|(_, _, options)| options.if_version.is_none()) {
+                self.degradation.writes
+            } else {
+                DegradedMode::FailFast
+            };
+            self.degrade(mode, CacheOperation::Set)?;
+            self.settle_pending(&Self::writes(entries)).await?;
+            let l1_options: Vec<CacheOptions> = entries
+                .iter()
+                .map(|(_, _, options)| Self::l1_options(options))
+                .collect();
+            let mut l1_entries = Vec::with_capacity(entries.len());
+            for ((key, value, options), l1_options) in entries.iter().zip(&l1_options) {
+                self.reconcile_later(mode, || Reconciliation::set(key, value.clone(), options));
+                self.record_write(key, options);
+                l1_entries.push((*key, value.clone(), l1_options));
+            }
+            return l1_write(self.l1.set_many(&l1_entries).await);
         }
         
+        self.settle_pending(&Self::writes(entries)).await?;
         match self.l2.set_many(entries).await {
              Ok(_) => {
                  self.circuit_breaker.report_success();
-                 self.l1.set_many(entries).await?;
+                 for (key, _, options) in entries {
+                     self.record_write(key, options);
+                 }
+                 let keys: Vec<&str> = entries.iter().map(|(key, _, _)| *key).collect();
+                 self.publish_keys(&keys).await;
+                 let l1_options: Vec<CacheOptions> = entries
+                     .iter()
+                     .map(|(_, _, options)| Self::l1_options(options))
+                     .collect();
+                 let l1_entries: Vec<(&str, Vec<u8>, &CacheOptions)> = entries
+                     .iter()
+                     .zip(&l1_options)
+                     .map(|((key, value, _), options)| (*key, value.clone(), options))
+                     .collect();
+                 l1_write(self.l1.set_many(&l1_entries).await)?;
                  Ok(())
              }
+             Err(e @ CacheError::VersionMismatch { .. }) => {
+                 let keys: Vec<&str> = entries.iter().map(|(key, _, _)| *key).collect();
+                 let _ = self.l1.delete_many(&keys).await;
+                 Err(e)
+             }
              Err(e) => {
                  if CircuitBreaker::is_failure(&e) {
                     self.circuit_breaker.report_failure();
@@ -271,8 +770,22 @@ where
     }
 
     async fn clear(&self) -> Result<()> {
+        self.discard_pending(|_, _| true).await;
+
+        if !self.l2_available().await {
+            let l1_res = self.l1.clear().await;
+            self.degrade(self.bulk_delete_mode(), CacheOperation::Invalidate)?;
+            return l1_res;
+        }
+
         let l2_res = self.l2.clear().await;
         let _ = self.l1.clear().await;
+        if let (Ok(()), Some(filter)) = (&l2_res, &self.key_filter) {
+            filter.clear();
+        }
+        if l2_res.is_ok() {
+            self.publish_clear().await;
+        }
         l2_res
     }
 
@@ -283,10 +796,7 @@ where
         // CacheBackend returns single CacheStats.
         // We could sum them up.
         let l1_stats = self.l1.stats().await?;
-        let l2_stats = match self.l2.stats().await {
-             Ok(s) => s,
-             Err(_) => CacheStats::default(),
-        };
+        let l2_stats = self.l2.stats().await.unwrap_or_default();
         
         Ok(CacheStats {
             hits: l1_stats.hits + l2_stats.hits,
@@ -313,8 +823,9 @@ where
 {
     async fn get_by_tag(&self, tag: &str) -> Result<Vec<String>> {
         // L2 is authority
-        if !self.circuit_breaker.allow_request() {
-             return self.l1.get_by_tag(tag).await;
+        if !self.l2_available().await {
+            self.degrade(self.degradation.reads, CacheOperation::Get)?;
+            return self.l1.get_by_tag(tag).await;
         }
         match self.l2.get_by_tag(tag).await {
              Ok(keys) => {
@@ -325,19 +836,28 @@ where
                  if CircuitBreaker::is_failure(&e) {
                     self.circuit_breaker.report_failure();
                 }
-                // Fallback to L1?
-                self.l1.get_by_tag(tag).await
+                Err(e)
              }
         }
     }
 
     async fn delete_by_tag(&self, tag: &str) -> Result<u64> {
+        self.discard_pending(|_, options| options.tags.iter().any(|t| t == tag)).await;
+
+        if !self.l2_available().await {
+            let l1_res = self.l1.delete_by_tag(tag).await;
+            self.degrade(self.bulk_delete_mode(), CacheOperation::Invalidate)?;
+            return l1_res;
+        }
+
         let l2_res = self.l2.delete_by_tag(tag).await;
         let _ = self.l1.delete_by_tag(tag).await;
         
         match l2_res {
              Ok(count) => {
                  self.circuit_breaker.report_success();
+                 self.record_removals(count);
+                 self.publish_tag(tag).await;
                  Ok(count)
              }
              Err(e) => {
@@ -350,6 +870,42 @@ where
     }
 }
 
+#[async_trait]
+impl<L1, L2> PatternBackend for MultiTierBackend<L1, L2>
+where
+    L1: PatternBackend,
+    L2: PatternBackend,
+{
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<u64> {
+        let compiled = KeyPattern::new(pattern);
+        self.discard_pending(|key, _| compiled.matches(key)).await;
+
+        if !self.l2_available().await {
+            let l1_res = self.l1.delete_by_pattern(pattern).await;
+            self.degrade(self.bulk_delete_mode(), CacheOperation::Invalidate)?;
+            return l1_res;
+        }
+
+        let l2_res = self.l2.delete_by_pattern(pattern).await;
+        let _ = self.l1.delete_by_pattern(pattern).await;
+
+        match l2_res {
+            Ok(count) => {
+                self.circuit_breaker.report_success();
+                self.record_removals(count);
+                self.publish_pattern(pattern).await;
+                Ok(count)
+            }
+            Err(e) => {
+                if CircuitBreaker::is_failure(&e) {
+                    self.circuit_breaker.report_failure();
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
 #[async_trait]
 impl<L1, L2> DependencyBackend for MultiTierBackend<L1, L2>
 where
@@ -360,6 +916,44 @@ where
         // Proxy to L2 (source of truth)
         self.l2.get_dependents(key).await
     }
+
+    /// Cascades in L2, then evicts the deleted keys from L1
+    async fn delete_cascade(&self, key: &str) -> Result<Vec<String>> {
+        // L2 must know about pending dependents; a failed flush leaves them queued
+        if let Some(queue) = &self.write_queue {
+            let _ = queue.flush_all(&self.l1, &self.l2, &self.circuit_breaker).await;
+        }
+
+        if !self.l2_available().await {
+            // Dependents are only known to L2
+            self.discard_pending(|pending_key, _| pending_key == key).await;
+            let l1_res = self.l1.delete(key).await;
+            self.degrade(self.bulk_delete_mode(), CacheOperation::Invalidate)?;
+            return Ok(if l1_res? { vec![key.to_string()] } else { Vec::new() });
+        }
+
+        let deleted = match self.l2.delete_cascade(key).await {
+            Ok(deleted) => deleted,
+            Err(e) => {
+                if CircuitBreaker::is_failure(&e) {
+                    self.circuit_breaker.report_failure();
+                }
+                return Err(e);
+            }
+        };
+
+        // The root may only be cached in L1
+        let mut evicted: Vec<&str> = deleted.iter().map(String::as_str).collect();
+        if !evicted.contains(&key) {
+            evicted.push(key);
+        }
+        self.discard_pending(|pending_key, _| evicted.contains(&pending_key)).await;
+        let _ = self.l1.delete_many(&evicted).await;
+        self.record_removals(deleted.len() as u64);
+        self.publish_keys(&evicted).await;
+
+        Ok(deleted)
+    }
 }
 
 #[cfg(test)]
@@ -409,6 +1003,71 @@ mod tests {
         assert!(l1.exists("key").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_backfill_keeps_negative_flag() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let backend = MultiTierBackend::new(l1.clone(), l2, breaker);
+
+        let opts = CacheOptions {
+            negative: true,
+            ..Default::default()
+        };
+        backend.set("missing", Vec::new(), &opts).await.unwrap();
+        l1.delete("missing").await.unwrap();
+
+        assert!(backend.get("missing").await.unwrap().unwrap().negative);
+        assert!(l1.get("missing").await.unwrap().unwrap().negative);
+    }
+
+    #[tokio::test]
+    async fn test_entry_too_heavy_for_l1_is_kept_in_l2() {
+        let l1 = MemoryBackend::new(MemoryConfig::unlimited().max_memory_bytes(64));
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let backend = MultiTierBackend::new(l1.clone(), l2, breaker);
+        let opts = CacheOptions::default();
+
+        backend.set("key", vec![1; 8], &opts).await.unwrap();
+        backend.set("key", vec![2; 128], &opts).await.unwrap();
+
+        assert!(!l1.exists("key").await.unwrap());
+        assert_eq!(backend.get("key").await.unwrap().unwrap().value, vec![2; 128]);
+    }
+
+    #[tokio::test]
+    async fn test_versions_follow_l2() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker);
+
+        let opts = CacheOptions::default();
+        l2.set("key", b"v1".to_vec(), &opts).await.unwrap();
+        backend.set("key", b"v2".to_vec(), &opts).await.unwrap();
+        assert_eq!(l2.get("key").await.unwrap().unwrap().version, 2);
+
+        // L1 has its own counter after a plain write; a conflicting CAS drops it
+        let cas = CacheOptions {
+            if_version: Some(l1.get("key").await.unwrap().unwrap().version),
+            ..Default::default()
+        };
+        assert!(backend.set("key", b"v3".to_vec(), &cas).await.is_err());
+        assert!(!l1.exists("key").await.unwrap());
+
+        // Backfill copies the L2 version, so the retry succeeds and both tiers agree
+        let version = backend.get("key").await.unwrap().unwrap().version;
+        assert_eq!(version, 2);
+        let cas = CacheOptions {
+            if_version: Some(version),
+            ..Default::default()
+        };
+        backend.set("key", b"v3".to_vec(), &cas).await.unwrap();
+        assert_eq!(l1.get("key").await.unwrap().unwrap().version, 3);
+        assert_eq!(l2.get("key").await.unwrap().unwrap().version, 3);
+    }
+
     #[derive(Clone)]
     struct FailingBackend {
         failures: Arc<RwLock<usize>>,
@@ -434,6 +1093,370 @@ mod tests {
         async fn len(&self) -> Result<usize> { Ok(0) }
     }
 
+    /// L2 whose reads race with an invalidation of the key being read
+    struct InvalidatedWhileRead {
+        inner: MemoryBackend,
+        generations: Arc<Generations>,
+    }
+
+    #[async_trait]
+    impl CacheBackend for InvalidatedWhileRead {
+        async fn get(&self, key: &str) -> Result<Option<CacheEntry<Vec<u8>>>> {
+            let entry = self.inner.get(key).await;
+            self.generations.invalidate(key);
+            entry
+        }
+        async fn set(&self, key: &str, value: Vec<u8>, opts: &CacheOptions) -> Result<()> { self.inner.set(key, value, opts).await }
+        async fn delete(&self, key: &str) -> Result<bool> { self.inner.delete(key).await }
+        async fn exists(&self, key: &str) -> Result<bool> { self.inner.exists(key).await }
+        async fn delete_many(&self, keys: &[&str]) -> Result<u64> { self.inner.delete_many(keys).await }
+        async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<CacheEntry<Vec<u8>>>>> {
+            let entries = self.inner.get_many(keys).await;
+            self.generations.invalidate_all();
+            entries
+        }
+        async fn set_many(&self, entries: &[(&str, Vec<u8>, &CacheOptions)]) -> Result<()> { self.inner.set_many(entries).await }
+        async fn clear(&self) -> Result<()> { self.inner.clear().await }
+        async fn stats(&self) -> Result<CacheStats> { self.inner.stats().await }
+        async fn len(&self) -> Result<usize> { self.inner.len().await }
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_during_read_skips_backfill() {
+        let l1 = create_memory();
+        let inner = create_memory();
+        let generations = Arc::new(Generations::default());
+        let l2 = InvalidatedWhileRead { inner: inner.clone(), generations: generations.clone() };
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let mut backend = MultiTierBackend::new(l1.clone(), l2, breaker);
+        backend.generations = generations;
+
+        inner.set("a", b"1".to_vec(), &CacheOptions::default()).await.unwrap();
+        inner.set("b", b"1".to_vec(), &CacheOptions::default()).await.unwrap();
+
+        // The value read is returned, but L1 does not keep it
+        assert_eq!(backend.get("a").await.unwrap().unwrap().value, b"1");
+        assert!(!l1.exists("a").await.unwrap());
+        let results = backend.get_many(&["b"]).await.unwrap();
+        assert!(results[0].is_some());
+        assert!(!l1.exists("b").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_key_filter_skips_l2_for_unwritten_keys() {
+        let l1 = create_memory();
+        let l2_fails = Arc::new(RwLock::new(0));
+        let l2 = FailingBackend { failures: l2_fails.clone() };
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(10));
+        let backend = MultiTierBackend::single_writer(l1, l2, breaker, KeyFilter::new(1000, 0.01));
+
+        // Never written: answered without touching L2
+        assert!(backend.get("missing").await.unwrap().is_none());
+        assert!(!backend.exists("missing").await.unwrap());
+        let results = backend.get_many(&["a", "b"]).await.unwrap();
+        assert!(results.iter().all(Option::is_none));
+        assert_eq!(*l2_fails.read(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_key_filter_passes_written_keys_to_l2() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let backend = MultiTierBackend::single_writer(l1.clone(), l2.clone(), breaker, KeyFilter::new(1000, 0.01));
+
+        let opts = CacheOptions::default();
+        backend.set("key", b"val".to_vec(), &opts).await.unwrap();
+        l2.set("foreign", b"val".to_vec(), &opts).await.unwrap();
+
+        // Written through the tier: found in L2 after an L1 miss
+        l1.delete("key").await.unwrap();
+        assert!(backend.get("key").await.unwrap().is_some());
+
+        // Written behind the tier's back: filtered out
+        assert!(backend.get("foreign").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_pattern_fans_out() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker);
+
+        let opts = CacheOptions::default();
+        backend.set("user:1:posts", b"a".to_vec(), &opts).await.unwrap();
+        backend.set("user:1:profile", b"b".to_vec(), &opts).await.unwrap();
+        l2.set("user:2:posts", b"c".to_vec(), &opts).await.unwrap();
+
+        assert_eq!(backend.delete_by_pattern("user:*:posts").await.unwrap(), 2);
+        assert!(!l1.exists("user:1:posts").await.unwrap());
+        assert!(!l2.exists("user:2:posts").await.unwrap());
+        assert!(l1.exists("user:1:profile").await.unwrap());
+        assert!(l2.exists("user:1:profile").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_cascade_evicts_l1() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker);
+
+        let depends_on = |dep: &str| CacheOptions {
+            dependencies: vec![dep.to_string()],
+            ..Default::default()
+        };
+        backend.set("org", b"o".to_vec(), &depends_on("user")).await.unwrap();
+        backend.set("user", b"u".to_vec(), &depends_on("org")).await.unwrap();
+        backend.set("post", b"p".to_vec(), &depends_on("user")).await.unwrap();
+        backend.set("other", b"x".to_vec(), &CacheOptions::default()).await.unwrap();
+
+        // `org` and `user` depend on each other; the walk still terminates
+        let deleted = backend.delete_cascade("org").await.unwrap();
+        assert_eq!(deleted.first().map(String::as_str), Some("org"));
+        assert_eq!(deleted.len(), 3);
+        for key in ["org", "user", "post"] {
+            assert!(!l1.exists(key).await.unwrap(), "{}", key);
+            assert!(!l2.exists(key).await.unwrap(), "{}", key);
+        }
+        assert!(l1.exists("other").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_write_behind_flushes_in_batches() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let config = WriteBehindConfig::default()
+            .flush_interval(Duration::from_secs(3600))
+            .batch_size(2);
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker).with_write_behind(config);
+
+        let opts = CacheOptions::default();
+        for key in ["a", "b", "c"] {
+            backend.set(key, b"1".to_vec(), &opts).await.unwrap();
+        }
+        backend.set("a", b"2".to_vec(), &opts).await.unwrap();
+        assert!(l1.exists("a").await.unwrap());
+        assert!(!l2.exists("a").await.unwrap());
+        assert_eq!(backend.pending_writes(), 3);
+
+        // Pending writes are served when L1 lost them
+        l1.delete("b").await.unwrap();
+        assert_eq!(backend.get("b").await.unwrap().unwrap().value, b"1");
+
+        // Deleted keys are not resurrected by a later flush
+        backend.delete("c").await.unwrap();
+        assert_eq!(backend.pending_writes(), 2);
+
+        backend.shutdown().await.unwrap();
+        assert_eq!(backend.pending_writes(), 0);
+        assert_eq!(l2.get("a").await.unwrap().unwrap().value, b"2");
+        assert!(l2.exists("b").await.unwrap());
+        assert!(!l2.exists("c").await.unwrap());
+
+        // After shutdown writes go straight to L2
+        backend.set("d", b"1".to_vec(), &opts).await.unwrap();
+        assert!(l2.exists("d").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_write_behind_compare_and_set() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let config = WriteBehindConfig::default().flush_interval(Duration::from_secs(3600));
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker).with_write_behind(config);
+
+        // L2 is ahead of L1: its next version differs from the one L1 would pick
+        let opts = CacheOptions::default();
+        l2.set("a", b"0".to_vec(), &opts).await.unwrap();
+        l2.set("a", b"0".to_vec(), &opts).await.unwrap();
+        backend.set("a", b"1".to_vec(), &opts).await.unwrap();
+        let provisional = backend.get("a").await.unwrap().unwrap().version;
+
+        // The pending write is flushed first and numbered by L2
+        let cas = CacheOptions {
+            if_version: Some(provisional),
+            ..Default::default()
+        };
+        let err = backend.set("a", b"2".to_vec(), &cas).await.unwrap_err();
+        assert!(matches!(err, CacheError::VersionMismatch { actual: 3, .. }));
+        assert_eq!(backend.pending_writes(), 0);
+
+        // Compare-and-set against the flushed version succeeds
+        let version = backend.get("a").await.unwrap().unwrap().version;
+        assert_eq!(version, 3);
+        let cas = CacheOptions {
+            if_version: Some(version),
+            ..Default::default()
+        };
+        backend.set("a", b"2".to_vec(), &cas).await.unwrap();
+        let entry = l2.get("a").await.unwrap().unwrap();
+        assert_eq!(entry.value, b"2");
+        assert_eq!(entry.version, 4);
+    }
+
+    #[tokio::test]
+    async fn test_write_behind_versions_follow_l2_after_eviction() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let config = WriteBehindConfig::default().flush_interval(Duration::from_secs(3600));
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker).with_write_behind(config);
+
+        // L2 holds version 7, L1 evicted the key
+        let opts = CacheOptions::default();
+        for _ in 0..7 {
+            l2.set("a", b"0".to_vec(), &opts).await.unwrap();
+        }
+        assert!(!l1.exists("a").await.unwrap());
+
+        backend.set("a", b"1".to_vec(), &opts).await.unwrap();
+        backend.flush().await.unwrap();
+
+        // The flush does not reset L2, and L1 is reloaded with its version
+        let entry = l2.get("a").await.unwrap().unwrap();
+        assert_eq!(entry.value, b"1");
+        assert_eq!(entry.version, 8);
+        assert_eq!(l1.get("a").await.unwrap().unwrap().version, 8);
+    }
+
+    #[tokio::test]
+    async fn test_write_behind_ttl_counts_from_queueing() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        let config = WriteBehindConfig::default().flush_interval(Duration::from_secs(3600));
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker).with_write_behind(config);
+
+        let short = CacheOptions {
+            ttl: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let long = CacheOptions {
+            ttl: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        backend.set("short", b"1".to_vec(), &short).await.unwrap();
+        backend.set("long", b"1".to_vec(), &long).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        backend.flush().await.unwrap();
+
+        // Expired while queued: never written
+        assert!(!l2.exists("short").await.unwrap());
+        let ttl = l2.get("long").await.unwrap().unwrap().ttl.unwrap();
+        assert!(ttl < Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_write_behind_keeps_writes_while_l2_is_down() {
+        let l1 = create_memory();
+        let l2_fails = Arc::new(RwLock::new(0));
+        let l2 = FailingBackend { failures: l2_fails.clone() };
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        let config = WriteBehindConfig::default()
+            .flush_interval(Duration::from_secs(3600))
+            .capacity(1);
+        let backend = MultiTierBackend::new(l1.clone(), l2, breaker).with_write_behind(config);
+
+        let opts = CacheOptions::default();
+        backend.set("a", b"1".to_vec(), &opts).await.unwrap();
+        // Queue full: L1 only
+        backend.set("b", b"1".to_vec(), &opts).await.unwrap();
+        assert!(l1.exists("b").await.unwrap());
+        assert_eq!(backend.dropped_writes(), 1);
+
+        assert!(backend.flush().await.is_err());
+        assert_eq!(backend.pending_writes(), 1);
+        assert!(backend.shutdown().await.is_err());
+        assert_eq!(backend.pending_writes(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_replays_after_recovery() {
+        let l1 = create_memory();
+        let l2 = create_memory();
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+        let policy = DegradationPolicy::uniform(DegradedMode::Reconcile);
+        let backend = MultiTierBackend::new(l1.clone(), l2.clone(), breaker.clone())
+            .with_degradation_policy(policy);
+
+        let opts = CacheOptions::default();
+        l2.set("old", b"0".to_vec(), &opts).await.unwrap();
+        breaker.report_failure();
+
+        backend.set("new", b"1".to_vec(), &opts).await.unwrap();
+        assert!(backend.delete("old").await.is_ok());
+        assert!(l1.exists("new").await.unwrap());
+        assert!(!l2.exists("new").await.unwrap());
+        assert_eq!(backend.pending_reconciliation(), 2);
+
+        // Tag deletes cannot be replayed
+        assert!(backend.delete_by_tag("t").await.is_err());
+
+        // Clears cannot either
+        assert!(backend.clear().await.is_err());
+        assert_eq!(backend.pending_reconciliation(), 2);
+
+        // Requests reaching L2 replay a batch each until the queue is drained
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(backend.get("old").await.unwrap().is_none());
+        assert_eq!(backend.pending_reconciliation(), 1);
+        assert!(l2.exists("new").await.unwrap());
+        assert!(backend.get("old").await.unwrap().is_none());
+        assert_eq!(backend.pending_reconciliation(), 0);
+        assert!(!l2.exists("old").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_fails_writes_fast() {
+        let l1 = create_memory();
+        let l2_fails = Arc::new(RwLock::new(0));
+        let l2 = FailingBackend { failures: l2_fails.clone() };
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        let backend = MultiTierBackend::new(l1.clone(), l2, breaker);
+
+        let opts = CacheOptions::default();
+        l1.set("cached", b"1".to_vec(), &opts).await.unwrap();
+        // L2 errors are returned while the breaker is closed
+        assert!(backend.get_many(&["cached", "missing"]).await.is_err());
+
+        // Open: reads fall back to L1, writes fail and leave L1 alone
+        let results = backend.get_many(&["cached", "missing"]).await.unwrap();
+        assert!(results[0].is_some() && results[1].is_none());
+        assert!(backend.set("key", b"1".to_vec(), &opts).await.is_err());
+        assert!(!l1.exists("key").await.unwrap());
+        assert!(backend.delete("cached").await.is_err());
+        assert!(!l1.exists("cached").await.unwrap());
+        assert_eq!(*l2_fails.read(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_l1_only_writes_skip_l2() {
+        let l1 = create_memory();
+        let l2_fails = Arc::new(RwLock::new(0));
+        let l2 = FailingBackend { failures: l2_fails.clone() };
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        breaker.report_failure();
+        let policy = DegradationPolicy::default().writes(DegradedMode::L1Only);
+        let backend = MultiTierBackend::new(l1.clone(), l2, breaker).with_degradation_policy(policy);
+
+        let opts = CacheOptions::default();
+        backend.set("key", b"1".to_vec(), &opts).await.unwrap();
+        assert!(l1.exists("key").await.unwrap());
+        assert_eq!(backend.pending_reconciliation(), 0);
+
+        // Versions cannot be checked without L2
+        let cas = CacheOptions {
+            if_version: Some(1),
+            ..Default::default()
+        };
+        assert!(backend.set("key", b"2".to_vec(), &cas).await.is_err());
+        assert_eq!(*l2_fails.read(), 0);
+    }
+
     #[tokio::test]
     async fn test_circuit_breaker() {
         let l1 = create_memory();
@@ -95,10 +95,9 @@ impl CircuitBreaker {
     }
     
     /// Helper to classify if an error should trip the breaker
+    ///
+    /// Same as `CacheError::is_transient`, which `RetryPolicy` also uses.
     pub fn is_failure(err: &CacheError) -> bool {
-        matches!(
-            err,
-            CacheError::Connection(_) | CacheError::Backend(_) | CacheError::Timeout | CacheError::Internal(_)
-        )
+        err.is_transient()
     }
 }
@@ -0,0 +1,324 @@
+//! Behaviour of `MultiTierBackend` while L2 is unavailable
+
+use parking_lot::Mutex;
+use skp_cache_core::{CacheBackend, CacheMetrics, CacheOptions, Result};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use super::circuit_breaker::CircuitBreaker;
+use super::write_behind::remaining_options;
+
+/// Maximum number of operations replayed by one `set_many` or `delete_many`
+const REPLAY_BATCH: usize = 100;
+
+/// What an operation does while the circuit breaker keeps L2 out of reach
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DegradedMode {
+    /// Use L1 alone: reads answer from L1, writes and deletes only reach L1
+    L1Only,
+    /// As `L1Only`, but writes and deletes are also queued and replayed on
+    /// L2 once the breaker lets requests through again
+    ///
+    /// The queue is replayed in batches, one per request reaching L2, and
+    /// until it is drained requests are handled as if the breaker were open,
+    /// so later operations stay ordered after the replayed ones. Replayed
+    /// writes keep the TTL they had left when they were queued.
+    Reconcile,
+    /// Fail with `CacheError::Backend` when the operation needs L2
+    ///
+    /// Reads answered by L1 still succeed and writes leave L1 untouched.
+    /// Deletes still evict L1 so this instance does not serve the entry.
+    FailFast,
+}
+
+/// Per-operation behaviour of `MultiTierBackend` while its breaker is open
+///
+/// Every operation handled this way is reported through
+/// `CacheMetrics::record_degraded`. Operations attempted while the breaker is
+/// closed return L2 errors as they are.
+#[derive(Debug, Clone)]
+pub struct DegradationPolicy {
+    /// `get`, `exists`, `get_many` and `get_by_tag` (`Reconcile` acts as `L1Only`)
+    pub reads: DegradedMode,
+    /// `set` and `set_many`, unless write-behind takes them
+    ///
+    /// Compare-and-set writes (`CacheOptions::if_version`) always fail fast.
+    pub writes: DegradedMode,
+    /// `delete`, `delete_many`, `clear`, `delete_by_tag`, `delete_by_pattern`
+    /// and `delete_cascade`
+    ///
+    /// Tag, pattern and cascade deletes and clears cannot be replayed, so
+    /// under `Reconcile` they fail fast.
+    pub deletes: DegradedMode,
+    /// Maximum number of queued operations under `Reconcile`; later ones
+    /// are dropped and reported as dropped writes
+    pub reconcile_capacity: usize,
+}
+
+impl Default for DegradationPolicy {
+    fn default() -> Self {
+        Self {
+            reads: DegradedMode::L1Only,
+            writes: DegradedMode::FailFast,
+            deletes: DegradedMode::FailFast,
+            reconcile_capacity: 10_000,
+        }
+    }
+}
+
+impl DegradationPolicy {
+    /// Same behaviour for every operation
+    pub fn uniform(mode: DegradedMode) -> Self {
+        Self {
+            reads: mode,
+            writes: mode,
+            deletes: mode,
+            ..Default::default()
+        }
+    }
+
+    /// Set the behaviour of reads
+    pub fn reads(mut self, mode: DegradedMode) -> Self {
+        self.reads = mode;
+        self
+    }
+
+    /// Set the behaviour of writes
+    pub fn writes(mut self, mode: DegradedMode) -> Self {
+        self.writes = mode;
+        self
+    }
+
+    /// Set the behaviour of deletes
+    pub fn deletes(mut self, mode: DegradedMode) -> Self {
+        self.deletes = mode;
+        self
+    }
+
+    /// Set the maximum number of queued operations under `Reconcile`
+    pub fn reconcile_capacity(mut self, capacity: usize) -> Self {
+        self.reconcile_capacity = capacity;
+        self
+    }
+}
+
+/// Operation to replay on L2
+#[derive(Debug, Clone)]
+pub(crate) enum Reconciliation {
+    Set {
+        key: String,
+        value: Vec<u8>,
+        options: Box<CacheOptions>,
+        queued_at: SystemTime,
+    },
+    Delete(String),
+}
+
+impl Reconciliation {
+    /// Write of `value` under `key`, queued now
+    pub(crate) fn set(key: &str, value: Vec<u8>, options: &CacheOptions) -> Self {
+        Reconciliation::Set {
+            key: key.to_string(),
+            value,
+            options: Box::new(options.clone()),
+            queued_at: SystemTime::now(),
+        }
+    }
+
+    /// Key the operation applies to
+    pub(crate) fn key(&self) -> &str {
+        match self {
+            Reconciliation::Set { key, .. } | Reconciliation::Delete(key) => key,
+        }
+    }
+}
+
+/// Operations applied to L1 only while L2 was unavailable, oldest first
+pub(crate) struct ReconcileQueue {
+    capacity: usize,
+    ops: Mutex<VecDeque<Reconciliation>>,
+    /// Held while replaying, so replays do not interleave
+    replaying: tokio::sync::Mutex<()>,
+    dropped: AtomicU64,
+    metrics: Arc<dyn CacheMetrics>,
+}
+
+impl ReconcileQueue {
+    pub(crate) fn new(capacity: usize, metrics: Arc<dyn CacheMetrics>) -> Self {
+        Self {
+            capacity,
+            ops: Mutex::new(VecDeque::new()),
+            replaying: tokio::sync::Mutex::new(()),
+            dropped: AtomicU64::new(0),
+            metrics,
+        }
+    }
+
+    /// Number of operations waiting to be replayed
+    pub(crate) fn len(&self) -> usize {
+        self.ops.lock().len()
+    }
+
+    /// Number of operations that will never reach L2
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Check whether every queued operation has reached L2
+    pub(crate) fn is_drained(&self) -> bool {
+        self.ops.lock().is_empty() && self.replaying.try_lock().is_ok()
+    }
+
+    /// Queue an operation
+    pub(crate) fn push(&self, op: Reconciliation) {
+        let mut ops = self.ops.lock();
+        if ops.len() >= self.capacity {
+            drop(ops);
+            self.record_dropped(&op);
+            return;
+        }
+        ops.push_back(op);
+    }
+
+    fn record_dropped(&self, op: &Reconciliation) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.metrics.record_dropped_write(op.key());
+    }
+
+    /// Take the oldest run of operations of the same kind on distinct keys
+    fn take_batch(&self) -> Vec<Reconciliation> {
+        let mut ops = self.ops.lock();
+        let mut batch: Vec<Reconciliation> = Vec::new();
+        let mut keys = HashSet::new();
+        while let Some(op) = ops.front() {
+            let same_kind = batch.first().is_none_or(|first| {
+                std::mem::discriminant(first) == std::mem::discriminant(op)
+            });
+            if batch.len() >= REPLAY_BATCH || !same_kind || !keys.insert(op.key().to_string()) {
+                break;
+            }
+            batch.extend(ops.pop_front());
+        }
+        batch
+    }
+
+    /// Replay the oldest batch of queued operations on L2
+    ///
+    /// A failure the breaker counts is reported to `breaker` and leaves the
+    /// batch queued; a batch failing otherwise is dropped. Returns the keys
+    /// of the replayed operations.
+    pub(crate) async fn replay_batch<L2: CacheBackend>(
+        &self,
+        l2: &L2,
+        breaker: &CircuitBreaker,
+    ) -> Result<Vec<String>> {
+        let _replaying = self.replaying.lock().await;
+        let batch = self.take_batch();
+        let result = match batch.first() {
+            None => return Ok(Vec::new()),
+            Some(Reconciliation::Set { .. }) => {
+                let writes: Vec<(&str, &[u8], Option<CacheOptions>)> = batch
+                    .iter()
+                    .filter_map(|op| match op {
+                        Reconciliation::Set {
+                            key,
+                            value,
+                            options,
+                            queued_at,
+                        } => Some((key.as_str(), value.as_slice(), remaining_options(options, *queued_at))),
+                        Reconciliation::Delete(_) => None,
+                    })
+                    .collect();
+                // Entries that expired while queued are skipped
+                let entries: Vec<(&str, Vec<u8>, &CacheOptions)> = writes
+                    .iter()
+                    .filter_map(|(key, value, options)| Some((*key, value.to_vec(), options.as_ref()?)))
+                    .collect();
+                if entries.is_empty() {
+                    Ok(())
+                } else {
+                    l2.set_many(&entries).await
+                }
+            }
+            Some(Reconciliation::Delete(_)) => {
+                let keys: Vec<&str> = batch.iter().map(Reconciliation::key).collect();
+                l2.delete_many(&keys).await.map(drop)
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                breaker.report_success();
+                Ok(batch.iter().map(|op| op.key().to_string()).collect())
+            }
+            Err(e) if CircuitBreaker::is_failure(&e) => {
+                breaker.report_failure();
+                let mut ops = self.ops.lock();
+                for op in batch.into_iter().rev() {
+                    ops.push_front(op);
+                }
+                Err(e)
+            }
+            Err(_) => {
+                for op in &batch {
+                    self.record_dropped(op);
+                }
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{MemoryBackend, MemoryConfig};
+    use skp_cache_core::NoopMetrics;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_replay_in_order() {
+        let queue = ReconcileQueue::new(4, Arc::new(NoopMetrics));
+        let options = CacheOptions::default();
+        queue.push(Reconciliation::set("a", b"1".to_vec(), &options));
+        queue.push(Reconciliation::set("b", b"2".to_vec(), &options));
+        queue.push(Reconciliation::Delete("a".into()));
+        queue.push(Reconciliation::set("a", b"3".to_vec(), &options));
+        queue.push(Reconciliation::Delete("c".into()));
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.dropped(), 1);
+
+        let l2 = MemoryBackend::new(MemoryConfig::default());
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        // Runs of one kind are replayed together
+        assert_eq!(queue.replay_batch(&l2, &breaker).await.unwrap(), ["a", "b"]);
+        assert!(!queue.is_drained());
+        assert_eq!(queue.replay_batch(&l2, &breaker).await.unwrap(), ["a"]);
+        assert!(!l2.exists("a").await.unwrap());
+        assert_eq!(queue.replay_batch(&l2, &breaker).await.unwrap(), ["a"]);
+        assert!(queue.is_drained());
+        assert_eq!(l2.get("a").await.unwrap().unwrap().value, b"3");
+        assert!(l2.exists("b").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_remaining_ttl() {
+        let queue = ReconcileQueue::new(10, Arc::new(NoopMetrics));
+        let ttl = |millis| CacheOptions {
+            ttl: Some(Duration::from_millis(millis)),
+            ..Default::default()
+        };
+        queue.push(Reconciliation::set("short", b"1".to_vec(), &ttl(50)));
+        queue.push(Reconciliation::set("long", b"1".to_vec(), &ttl(60_000)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let l2 = MemoryBackend::new(MemoryConfig::default());
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+        queue.replay_batch(&l2, &breaker).await.unwrap();
+        assert!(!l2.exists("short").await.unwrap());
+        let remaining = l2.get("long").await.unwrap().unwrap().ttl.unwrap();
+        assert!(remaining < Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,87 @@
+//! Counters of L1 invalidations, so backfills cannot undo them
+
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of key stripes; keys sharing a stripe share a counter
+const STRIPES: usize = 256;
+
+/// Invalidations seen so far, per key stripe and for every key
+///
+/// A read from L2 notes the generation of its key before the read and only
+/// keeps the L1 backfill if no invalidation arrived in between. Keys are
+/// striped, so an invalidation may also cancel backfills of unrelated keys,
+/// which only costs an extra L2 read later.
+pub(crate) struct Generations {
+    stripes: Box<[AtomicU64]>,
+    /// Bumped by invalidations that may cover any key (tags, patterns, clears)
+    all: AtomicU64,
+    hasher: RandomState,
+}
+
+/// Generation of a key at some point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Generation {
+    all: u64,
+    key: u64,
+}
+
+impl Default for Generations {
+    fn default() -> Self {
+        Self {
+            stripes: (0..STRIPES).map(|_| AtomicU64::new(0)).collect(),
+            all: AtomicU64::new(0),
+            hasher: RandomState::new(),
+        }
+    }
+}
+
+impl Generations {
+    fn stripe(&self, key: &str) -> &AtomicU64 {
+        &self.stripes[self.hasher.hash_one(key) as usize % STRIPES]
+    }
+
+    /// Current generation of `key`
+    pub(crate) fn current(&self, key: &str) -> Generation {
+        Generation {
+            all: self.all.load(Ordering::SeqCst),
+            key: self.stripe(key).load(Ordering::SeqCst),
+        }
+    }
+
+    /// Check whether `key` was invalidated since `since`
+    pub(crate) fn changed(&self, key: &str, since: Generation) -> bool {
+        self.current(key) != since
+    }
+
+    /// Record an invalidation of `key`, before evicting it
+    #[cfg_attr(not(feature = "redis"), allow(dead_code))]
+    pub(crate) fn invalidate(&self, key: &str) {
+        self.stripe(key).fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Record an invalidation that may cover any key, before evicting
+    #[cfg_attr(not(feature = "redis"), allow(dead_code))]
+    pub(crate) fn invalidate_all(&self) {
+        self.all.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidations_change_the_generation() {
+        let generations = Generations::default();
+        let before = generations.current("a");
+        assert!(!generations.changed("a", before));
+
+        generations.invalidate("a");
+        assert!(generations.changed("a", before));
+
+        let before = generations.current("a");
+        generations.invalidate_all();
+        assert!(generations.changed("a", before));
+    }
+}
@@ -0,0 +1,238 @@
+//! Cross-instance L1 invalidation over Redis pub/sub or client tracking
+
+use skp_cache_core::{CacheError, DistributedBackend, PatternBackend, Result, TaggableBackend};
+use tokio::task::JoinHandle;
+
+use std::sync::Arc;
+
+use super::backend::MultiTierBackend;
+use super::generations::Generations;
+use crate::redis::{InvalidationEvent, InvalidationSubscriber, RedisBackend, SubscribeError};
+
+/// Task evicting L1 entries on invalidation events; stopped when dropped
+pub(crate) struct InvalidationListener {
+    task: JoinHandle<()>,
+    /// Backend whose tracking connection produces the events
+    _tracking: Option<RedisBackend>,
+}
+
+impl Drop for InvalidationListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl<L1, L2> MultiTierBackend<L1, L2>
+where
+    L1: TaggableBackend + PatternBackend + Clone + 'static,
+{
+    /// Keep L1 coherent with other instances through Redis pub/sub
+    ///
+    /// Writes, deletes, tag and pattern deletes and clears are published on the
+    /// invalidation channel of `redis`, and events published by other
+    /// instances evict the matching L1 entries. Events this instance publishes
+    /// are not delivered back to it. If events are missed because the listener
+    /// fell behind, L1 is cleared.
+    pub async fn with_invalidation(mut self, redis: RedisBackend) -> Result<Self> {
+        redis.subscribe_invalidations().await?;
+        self.listener = Some(InvalidationListener::spawn(
+            self.l1.clone(),
+            redis.invalidation_events(),
+            self.generations.clone(),
+        ));
+        if let Some(queue) = &self.write_queue {
+            queue.set_invalidation(Some(redis.clone()));
+        }
+        self.invalidation = Some(redis);
+        Ok(self)
+    }
+
+    /// Keep L1 coherent through Redis client-side caching
+    ///
+    /// `redis` must have been created with `RedisConfig::client_tracking`;
+    /// it should be the L2 of this backend, or share its database and key
+    /// prefix, and is kept open as long as this backend. L1 entries are evicted when Redis reports their key changed,
+    /// whichever client changed it, so nothing is published. In
+    /// `TrackingMode::Default` only keys read from L2 are tracked; use
+    /// `TrackingMode::Broadcast` to also cover entries L1 got from writes.
+    pub fn with_client_tracking(mut self, redis: RedisBackend) -> Result<Self> {
+        if !redis.is_tracking() {
+            return Err(CacheError::Connection(
+                "client tracking is not enabled on the Redis backend".into(),
+            ));
+        }
+        let mut listener = InvalidationListener::spawn(
+            self.l1.clone(),
+            redis.invalidation_events(),
+            self.generations.clone(),
+        );
+        listener._tracking = Some(redis);
+        self.listener = Some(listener);
+        Ok(self)
+    }
+}
+
+impl InvalidationListener {
+    /// Apply `events` to `l1` until the publisher is dropped
+    ///
+    /// Each event is counted in `generations` before it is applied. If events
+    /// are missed because the listener fell behind, L1 is cleared.
+    fn spawn<L1>(l1: L1, mut events: InvalidationSubscriber, generations: Arc<Generations>) -> Self
+    where
+        L1: TaggableBackend + PatternBackend + 'static,
+    {
+        let task = tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => apply_event(&l1, &generations, event).await,
+                    Err(SubscribeError::Lagged(_)) => {
+                        generations.invalidate_all();
+                        let _ = l1.clear().await;
+                    }
+                    Err(SubscribeError::Closed) => break,
+                }
+            }
+        });
+        Self {
+            task,
+            _tracking: None,
+        }
+    }
+}
+
+impl<L1, L2> MultiTierBackend<L1, L2> {
+    /// Publish events to other instances
+    ///
+    /// Best effort: L2 has already been updated, so a failed publish only
+    /// leaves other L1s stale until their entries expire.
+    pub(super) async fn publish(&self, events: &[InvalidationEvent]) {
+        if let Some(redis) = &self.invalidation {
+            let _ = redis.publish_events(events).await;
+        }
+    }
+}
+
+/// Evict what `event` invalidates from L1
+async fn apply_event<L1>(l1: &L1, generations: &Generations, event: InvalidationEvent)
+where
+    L1: TaggableBackend + PatternBackend,
+{
+    match &event {
+        InvalidationEvent::Key(key) => generations.invalidate(key),
+        _ => generations.invalidate_all(),
+    }
+    let _ = match event {
+        InvalidationEvent::Key(key) => l1.delete(&key).await.map(drop),
+        InvalidationEvent::Tag(tag) => l1.delete_by_tag(&tag).await.map(drop),
+        InvalidationEvent::Pattern(pattern) => l1.delete_by_pattern(&pattern).await.map(drop),
+        InvalidationEvent::Clear => l1.clear().await,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{MemoryBackend, MemoryConfig};
+    use crate::multitier::CircuitBreaker;
+    use crate::redis::{RedisConfig, TrackingMode};
+    use skp_cache_core::{CacheBackend, CacheOptions};
+    use std::time::Duration;
+
+    /// Config for the server at `REDIS_URL` (local default port otherwise),
+    /// under a prefix of its own
+    fn redis_config() -> RedisConfig {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".into());
+        RedisConfig::new(url).prefix(format!("skp-test-{:016x}", rand::random::<u64>()))
+    }
+
+    /// L1 of a multi-tier backend tracking its Redis L2 in `mode`, and a
+    /// second client writing to the same keys
+    async fn tracked(
+        mode: TrackingMode,
+    ) -> (MemoryBackend, MultiTierBackend<MemoryBackend, RedisBackend>, RedisBackend) {
+        let config = redis_config();
+        let other = RedisBackend::new(config.clone()).await.unwrap();
+        let redis = RedisBackend::new(config.client_tracking(mode)).await.unwrap();
+        let l1 = MemoryBackend::new(MemoryConfig::default());
+        let backend = MultiTierBackend::new(l1.clone(), redis.clone(), CircuitBreaker::new(3, Duration::from_secs(10)))
+            .with_client_tracking(redis)
+            .unwrap();
+        (l1, backend, other)
+    }
+
+    /// Wait for `key` to leave `l1`, failing after a second
+    async fn evicted(l1: &MemoryBackend, key: &str) -> bool {
+        for _ in 0..100 {
+            if !l1.exists(key).await.unwrap() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server (REDIS_URL)"]
+    async fn test_default_tracking_evicts_keys_read_from_l2() {
+        let (l1, backend, other) = tracked(TrackingMode::Default).await;
+        other.set("a", b"1".to_vec(), &CacheOptions::default()).await.unwrap();
+
+        assert_eq!(backend.get("a").await.unwrap().unwrap().value, b"1");
+        assert!(l1.exists("a").await.unwrap());
+
+        other.set("a", b"2".to_vec(), &CacheOptions::default()).await.unwrap();
+        assert!(evicted(&l1, "a").await);
+        assert_eq!(backend.get("a").await.unwrap().unwrap().value, b"2");
+        other.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server (REDIS_URL)"]
+    async fn test_broadcast_tracking_evicts_written_keys() {
+        let (l1, backend, other) = tracked(TrackingMode::Broadcast).await;
+        backend.set("a", b"1".to_vec(), &CacheOptions::default()).await.unwrap();
+        // Our own write is reported too; wait for it before writing elsewhere
+        evicted(&l1, "a").await;
+        backend.get("a").await.unwrap();
+
+        other.set("a", b"2".to_vec(), &CacheOptions::default()).await.unwrap();
+        assert!(evicted(&l1, "a").await);
+        assert_eq!(backend.get("a").await.unwrap().unwrap().value, b"2");
+        other.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_events_evict_l1() {
+        let l1 = MemoryBackend::new(MemoryConfig::default());
+        let generations = Generations::default();
+        let tagged = CacheOptions {
+            tags: vec!["users".to_string()],
+            ..Default::default()
+        };
+        l1.set("a", b"1".to_vec(), &CacheOptions::default())
+            .await
+            .unwrap();
+        l1.set("b", b"2".to_vec(), &tagged).await.unwrap();
+        l1.set("c", b"3".to_vec(), &CacheOptions::default())
+            .await
+            .unwrap();
+        l1.set("user:1", b"4".to_vec(), &CacheOptions::default())
+            .await
+            .unwrap();
+
+        apply_event(&l1, &generations, InvalidationEvent::Key("a".into())).await;
+        assert!(!l1.exists("a").await.unwrap());
+        assert!(l1.exists("b").await.unwrap());
+
+        apply_event(&l1, &generations, InvalidationEvent::Tag("users".into())).await;
+        assert!(!l1.exists("b").await.unwrap());
+        assert!(l1.exists("c").await.unwrap());
+
+        apply_event(&l1, &generations, InvalidationEvent::Pattern("user:*".into())).await;
+        assert!(!l1.exists("user:1").await.unwrap());
+        assert!(l1.exists("c").await.unwrap());
+
+        apply_event(&l1, &generations, InvalidationEvent::Clear).await;
+        assert!(!l1.exists("c").await.unwrap());
+    }
+}
@@ -2,6 +2,13 @@
 
 mod backend;
 mod circuit_breaker;
+mod degradation;
+mod generations;
+#[cfg(feature = "redis")]
+mod invalidation;
+mod write_behind;
 
 pub use backend::MultiTierBackend;
 pub use circuit_breaker::CircuitBreaker;
+pub use degradation::{DegradationPolicy, DegradedMode};
+pub use write_behind::WriteBehindConfig;
@@ -0,0 +1,560 @@
+//! Asynchronous L2 writes for `MultiTierBackend`
+
+use parking_lot::Mutex;
+use skp_cache_core::{CacheBackend, CacheEntry, CacheError, CacheMetrics, CacheOptions, Result};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use super::backend::backfill_options;
+use super::circuit_breaker::CircuitBreaker;
+use super::generations::Generations;
+#[cfg(feature = "redis")]
+use crate::redis::{InvalidationEvent, RedisBackend};
+
+/// Configuration of write-behind mode
+#[derive(Debug, Clone)]
+pub struct WriteBehindConfig {
+    /// Maximum number of keys waiting to be written to L2
+    ///
+    /// Writes of new keys arriving while the queue is full only reach L1 and
+    /// are counted as dropped.
+    pub capacity: usize,
+    /// Interval between flushes
+    pub flush_interval: Duration,
+    /// Maximum number of entries per `set_many` call
+    pub batch_size: usize,
+}
+
+impl Default for WriteBehindConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            flush_interval: Duration::from_millis(100),
+            batch_size: 100,
+        }
+    }
+}
+
+impl WriteBehindConfig {
+    /// Set the maximum number of pending keys
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Set the interval between flushes
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Set the maximum number of entries per `set_many` call
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+}
+
+/// Write waiting to be flushed to L2
+#[derive(Debug, Clone)]
+struct PendingWrite {
+    value: Vec<u8>,
+    options: CacheOptions,
+    queued_at: SystemTime,
+}
+
+impl PendingWrite {
+    /// Options to write with, shortened by the time spent in the queue
+    fn remaining_options(&self) -> Option<CacheOptions> {
+        remaining_options(&self.options, self.queued_at)
+    }
+}
+
+/// `options` of a write queued at `queued_at`, with the TTL counted from then
+///
+/// `None` if the entry expired before it could be written.
+pub(crate) fn remaining_options(options: &CacheOptions, queued_at: SystemTime) -> Option<CacheOptions> {
+    let Some(ttl) = options.ttl else {
+        return Some(options.clone());
+    };
+    let queued = queued_at.elapsed().unwrap_or_default();
+    let lifetime = ttl + options.stale_while_revalidate.unwrap_or_default();
+    if queued >= lifetime {
+        return None;
+    }
+    // An entry past its TTL but still within `stale_while_revalidate` is
+    // written with the shortest TTL and what is left of the stale window
+    let ttl = ttl.saturating_sub(queued).max(Duration::from_millis(1));
+    Some(CacheOptions {
+        ttl: Some(ttl),
+        stale_while_revalidate: options
+            .stale_while_revalidate
+            .map(|_| (lifetime - queued).saturating_sub(ttl)),
+        ..options.clone()
+    })
+}
+
+/// Pending writes, one per key, oldest first
+#[derive(Default)]
+struct Pending {
+    writes: HashMap<String, PendingWrite>,
+    order: VecDeque<String>,
+}
+
+impl Pending {
+    /// Remove up to `count` of the oldest writes
+    fn take(&mut self, count: usize) -> Vec<(String, PendingWrite)> {
+        let mut batch = Vec::with_capacity(count.min(self.order.len()));
+        while batch.len() < count {
+            let Some(key) = self.order.pop_front() else {
+                break;
+            };
+            // Keys discarded since they were queued are skipped
+            if let Some(write) = self.writes.remove(&key) {
+                batch.push((key, write));
+            }
+        }
+        batch
+    }
+
+    /// Remove the writes of `keys`
+    fn take_keys(&mut self, keys: &[&str]) -> Vec<(String, PendingWrite)> {
+        let batch: Vec<(String, PendingWrite)> = keys
+            .iter()
+            .filter_map(|key| self.writes.remove_entry(*key))
+            .collect();
+        if !batch.is_empty() {
+            let Pending { writes, order } = self;
+            order.retain(|key| writes.contains_key(key));
+        }
+        batch
+    }
+}
+
+/// Coalescing queue of writes shared by a `MultiTierBackend` and its flusher
+///
+/// A key written again before it is flushed keeps its place in the queue and
+/// only its latest value is written.
+pub(crate) struct WriteQueue {
+    config: WriteBehindConfig,
+    pending: Mutex<Pending>,
+    /// Held while a batch is out of the queue, so batches reach L2 in order
+    /// and discarded keys cannot be written by a batch already taken
+    flushing: tokio::sync::Mutex<()>,
+    dropped: AtomicU64,
+    /// Set once flushing has stopped; writes then go straight to L2
+    stopped: AtomicBool,
+    metrics: Arc<dyn CacheMetrics>,
+    /// Invalidations of the backend's L1, checked when reloading flushed keys
+    generations: Arc<Generations>,
+    /// Publishes flushed keys to other instances
+    #[cfg(feature = "redis")]
+    invalidation: parking_lot::RwLock<Option<RedisBackend>>,
+}
+
+impl WriteQueue {
+    pub(crate) fn new(
+        config: WriteBehindConfig,
+        metrics: Arc<dyn CacheMetrics>,
+        generations: Arc<Generations>,
+    ) -> Self {
+        Self {
+            config,
+            pending: Mutex::new(Pending::default()),
+            flushing: tokio::sync::Mutex::new(()),
+            dropped: AtomicU64::new(0),
+            stopped: AtomicBool::new(false),
+            metrics,
+            generations,
+            #[cfg(feature = "redis")]
+            invalidation: parking_lot::RwLock::new(None),
+        }
+    }
+
+    /// Publish flushed keys through `redis`
+    #[cfg(feature = "redis")]
+    pub(crate) fn set_invalidation(&self, redis: Option<RedisBackend>) {
+        *self.invalidation.write() = redis;
+    }
+
+    /// Check whether writes are still queued
+    pub(crate) fn is_accepting(&self) -> bool {
+        !self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Number of keys waiting to be flushed
+    pub(crate) fn len(&self) -> usize {
+        self.pending.lock().writes.len()
+    }
+
+    /// Check whether `key` has a pending write
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.pending.lock().writes.contains_key(key)
+    }
+
+    /// Number of writes that never reached L2
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Queue a write, replacing any pending write of the same key
+    ///
+    /// Returns `false` if the queue is full and the write was dropped.
+    pub(crate) fn push(&self, key: &str, value: Vec<u8>, options: &CacheOptions) -> bool {
+        let write = PendingWrite {
+            value,
+            options: options.clone(),
+            queued_at: SystemTime::now(),
+        };
+
+        let depth = {
+            let mut pending = self.pending.lock();
+            if let Some(existing) = pending.writes.get_mut(key) {
+                *existing = write;
+            } else if pending.writes.len() >= self.config.capacity {
+                drop(pending);
+                self.record_dropped(key);
+                return false;
+            } else {
+                pending.writes.insert(key.to_string(), write);
+                pending.order.push_back(key.to_string());
+            }
+            pending.writes.len()
+        };
+        self.metrics.record_write_queue_depth(depth);
+        true
+    }
+
+    /// Entry a pending write will create, for reads that miss L1
+    ///
+    /// Its version is 0 unless the write set one: L2 numbers it when flushed.
+    pub(crate) fn entry(&self, key: &str) -> Option<CacheEntry<Vec<u8>>> {
+        let pending = self.pending.lock();
+        let write = pending.writes.get(key)?;
+        let mut entry = CacheEntry::new(write.value.clone(), write.value.len());
+        entry.created_at = write.queued_at;
+        entry.last_accessed = write.queued_at;
+        entry.ttl = write.options.ttl;
+        entry.stale_while_revalidate = write.options.stale_while_revalidate;
+        entry.tags = write.options.tags.clone();
+        entry.dependencies = write.options.dependencies.clone();
+        entry.cost = write.options.cost.unwrap_or(1);
+        entry.compute_time = write.options.compute_time;
+        entry.etag = write.options.etag.clone();
+        entry.negative = write.options.negative;
+        entry.version = write.options.version.unwrap_or_default();
+        (!entry.is_expired()).then_some(entry)
+    }
+
+    /// Discard the pending writes matching `filter`
+    ///
+    /// Waits for a batch being flushed, so discarded writes never reach L2.
+    pub(crate) async fn discard(&self, filter: impl Fn(&str, &CacheOptions) -> bool) {
+        let _flushing = self.flushing.lock().await;
+        let depth = {
+            let mut pending = self.pending.lock();
+            pending
+                .writes
+                .retain(|key, write| !filter(key, &write.options));
+            let Pending { writes, order } = &mut *pending;
+            order.retain(|key| writes.contains_key(key));
+            writes.len()
+        };
+        self.metrics.record_write_queue_depth(depth);
+    }
+
+    /// Write the oldest batch to L2
+    ///
+    /// Returns whether writes remain queued. Writes failing with an error the
+    /// breaker counts are queued again unless the key was written since;
+    /// other failures drop the batch.
+    pub(crate) async fn flush_batch<L1: CacheBackend, L2: CacheBackend>(
+        &self,
+        l1: &L1,
+        l2: &L2,
+        breaker: &CircuitBreaker,
+    ) -> Result<bool> {
+        let _flushing = self.flushing.lock().await;
+        let batch = self.pending.lock().take(self.config.batch_size.max(1));
+        if batch.is_empty() {
+            return Ok(false);
+        }
+        self.write_batch(batch, l1, l2, breaker).await?;
+        Ok(self.len() > 0)
+    }
+
+    /// Write the pending writes of `keys` to L2 now, ahead of older writes
+    pub(crate) async fn flush_keys<L1: CacheBackend, L2: CacheBackend>(
+        &self,
+        keys: &[&str],
+        l1: &L1,
+        l2: &L2,
+        breaker: &CircuitBreaker,
+    ) -> Result<()> {
+        let _flushing = self.flushing.lock().await;
+        let batch = self.pending.lock().take_keys(keys);
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.write_batch(batch, l1, l2, breaker).await
+    }
+
+    /// Write a batch taken out of the queue to L2, then reload it into L1
+    ///
+    /// Writes whose entry expired while queued are skipped.
+    async fn write_batch<L1: CacheBackend, L2: CacheBackend>(
+        &self,
+        batch: Vec<(String, PendingWrite)>,
+        l1: &L1,
+        l2: &L2,
+        breaker: &CircuitBreaker,
+    ) -> Result<()> {
+        if !breaker.allow_request() {
+            self.requeue(batch);
+            return Err(CacheError::Backend("Circuit breaker open".to_string()));
+        }
+
+        let options: Vec<Option<CacheOptions>> = batch
+            .iter()
+            .map(|(_, write)| write.remaining_options())
+            .collect();
+        let entries: Vec<(&str, Vec<u8>, &CacheOptions)> = batch
+            .iter()
+            .zip(&options)
+            .filter_map(|((key, write), options)| {
+                Some((key.as_str(), write.value.clone(), options.as_ref()?))
+            })
+            .collect();
+        let result = if entries.is_empty() {
+            Ok(())
+        } else {
+            l2.set_many(&entries).await
+        };
+        match result {
+            Ok(()) => {
+                if !entries.is_empty() {
+                    breaker.report_success();
+                    let keys: Vec<&str> = entries.iter().map(|(key, _, _)| *key).collect();
+                    self.reload(&keys, l1, l2).await;
+                }
+                #[cfg(feature = "redis")]
+                self.publish(&batch).await;
+                self.metrics.record_write_queue_depth(self.len());
+                Ok(())
+            }
+            Err(e) => {
+                if CircuitBreaker::is_failure(&e) {
+                    breaker.report_failure();
+                    self.requeue(batch);
+                } else {
+                    for (key, _) in &batch {
+                        self.record_dropped(key);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Load flushed keys back into L1 with the version L2 gave them
+    ///
+    /// L1 holds queued writes with a provisional version of its own. Keys
+    /// queued again since are left to their next flush; keys that cannot be
+    /// read back are evicted, so the next read goes to L2.
+    async fn reload<L1: CacheBackend, L2: CacheBackend>(&self, keys: &[&str], l1: &L1, l2: &L2) {
+        let keys: Vec<&str> = keys.iter().copied().filter(|key| !self.contains(key)).collect();
+        if keys.is_empty() {
+            return;
+        }
+        let since: Vec<_> = keys.iter().map(|key| self.generations.current(key)).collect();
+        let Ok(entries) = l2.get_many(&keys).await else {
+            let _ = l1.delete_many(&keys).await;
+            return;
+        };
+        for ((key, entry), since) in keys.iter().zip(entries).zip(since) {
+            if self.contains(key) {
+                continue;
+            }
+            match entry {
+                Some(entry) => {
+                    let _ = l1.set(key, entry.value.clone(), &backfill_options(&entry)).await;
+                    // Invalidated by another instance while being read
+                    if self.generations.changed(key, since) {
+                        let _ = l1.delete(key).await;
+                    }
+                }
+                None => {
+                    let _ = l1.delete(key).await;
+                }
+            }
+        }
+    }
+
+    /// Flush until the queue is empty or a flush fails
+    pub(crate) async fn flush_all<L1: CacheBackend, L2: CacheBackend>(
+        &self,
+        l1: &L1,
+        l2: &L2,
+        breaker: &CircuitBreaker,
+    ) -> Result<()> {
+        while self.flush_batch(l1, l2, breaker).await? {}
+        Ok(())
+    }
+
+    /// Put a batch that could not be written back at the front of the queue
+    fn requeue(&self, batch: Vec<(String, PendingWrite)>) {
+        let mut dropped = Vec::new();
+        let depth = {
+            let mut pending = self.pending.lock();
+            for (key, write) in batch.into_iter().rev() {
+                if pending.writes.contains_key(&key) {
+                    // Superseded by a newer write
+                    continue;
+                }
+                if pending.writes.len() >= self.config.capacity {
+                    dropped.push(key);
+                    continue;
+                }
+                pending.order.push_front(key.clone());
+                pending.writes.insert(key, write);
+            }
+            pending.writes.len()
+        };
+        for key in dropped {
+            self.record_dropped(&key);
+        }
+        self.metrics.record_write_queue_depth(depth);
+    }
+
+    fn record_dropped(&self, key: &str) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.metrics.record_dropped_write(key);
+    }
+
+    /// Tell other instances to evict the flushed keys from their L1
+    #[cfg(feature = "redis")]
+    async fn publish(&self, batch: &[(String, PendingWrite)]) {
+        let redis = self.invalidation.read().clone();
+        if let Some(redis) = redis {
+            let events: Vec<InvalidationEvent> = batch
+                .iter()
+                .map(|(key, _)| InvalidationEvent::Key(key.clone()))
+                .collect();
+            // Best effort, as for write-through writes
+            let _ = redis.publish_events(&events).await;
+        }
+    }
+}
+
+/// Handle to the task flushing a `WriteQueue` every `flush_interval`
+///
+/// Dropping the handle makes the task flush what is left and exit.
+pub(crate) struct Flusher {
+    stop: Arc<Notify>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Flusher {
+    /// Spawn the flusher on the current Tokio runtime
+    pub(crate) fn spawn<L1, L2>(queue: Arc<WriteQueue>, l1: L1, l2: L2, breaker: CircuitBreaker) -> Self
+    where
+        L1: CacheBackend,
+        L2: CacheBackend,
+    {
+        let stop = Arc::new(Notify::new());
+        let interval = queue.config.flush_interval.max(Duration::from_millis(1));
+
+        let task = tokio::spawn({
+            let stop = stop.clone();
+            async move {
+                loop {
+                    let stopping = tokio::time::timeout(interval, stop.notified())
+                        .await
+                        .is_ok();
+                    // Failures are retried on the next tick
+                    let _ = queue.flush_all(&l1, &l2, &breaker).await;
+                    if stopping {
+                        queue.stopped.store(true, Ordering::SeqCst);
+                        // Writes queued before `stopped` was set
+                        let _ = queue.flush_all(&l1, &l2, &breaker).await;
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            stop,
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    /// Make the task flush what is left and exit, and wait for it
+    pub(crate) async fn stop(&self) {
+        let task = self.task.lock().take();
+        if let Some(task) = task {
+            self.stop.notify_one();
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for Flusher {
+    fn drop(&mut self) {
+        self.stop.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use skp_cache_core::NoopMetrics;
+
+    fn queue(capacity: usize) -> WriteQueue {
+        WriteQueue::new(
+            WriteBehindConfig::default().capacity(capacity),
+            Arc::new(NoopMetrics),
+            Arc::default(),
+        )
+    }
+
+    #[test]
+    fn test_writes_coalesce_in_place() {
+        let queue = queue(2);
+        let options = CacheOptions::default();
+        assert!(queue.push("a", b"1".to_vec(), &options));
+        assert!(queue.push("b", b"2".to_vec(), &options));
+        assert!(queue.push("a", b"3".to_vec(), &options));
+        assert!(!queue.push("c", b"4".to_vec(), &options));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.dropped(), 1);
+
+        let batch = queue.pending.lock().take(10);
+        let keys: Vec<&str> = batch.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["a", "b"]);
+        assert_eq!(batch[0].1.value, b"3");
+    }
+
+    #[test]
+    fn test_requeue_keeps_newer_writes() {
+        let queue = queue(10);
+        let options = CacheOptions::default();
+        queue.push("a", b"1".to_vec(), &options);
+        queue.push("b", b"2".to_vec(), &options);
+        let batch = queue.pending.lock().take(10);
+
+        queue.push("b", b"3".to_vec(), &options);
+        queue.requeue(batch);
+
+        let batch = queue.pending.lock().take(10);
+        let writes: Vec<(&str, &[u8])> = batch
+            .iter()
+            .map(|(key, write)| (key.as_str(), write.value.as_slice()))
+            .collect();
+        assert_eq!(writes, [("a", &b"1"[..]), ("b", &b"3"[..])]);
+    }
+}
@@ -0,0 +1,71 @@
+//! Background tasks run on a fixed interval, shared by the backends
+
+use parking_lot::Mutex;
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use skp_cache_core::{CacheError, Result};
+
+/// Handle to a task running a job every interval
+///
+/// Backends share one handle between their clones. The task is stopped when
+/// the last handle is dropped or when `stop` is called.
+pub(crate) struct PeriodicTask {
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl PeriodicTask {
+    /// Run `job` every `interval` on the current Tokio runtime, starting one
+    /// interval from now
+    ///
+    /// `job` must not hold a reference to this handle, otherwise the task
+    /// would keep itself alive forever. Fails outside a runtime.
+    pub(crate) fn spawn<F, Fut>(interval: Duration, mut job: F) -> Result<Self>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let runtime = tokio::runtime::Handle::try_current().map_err(|_| {
+            CacheError::Internal("background tasks require a Tokio runtime".into())
+        })?;
+        let interval = interval.max(Duration::from_millis(1));
+
+        let task = runtime.spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                job().await;
+            }
+        });
+
+        Ok(Self {
+            task: Mutex::new(Some(task)),
+        })
+    }
+
+    /// Stop the task (idempotent)
+    pub(crate) fn stop(&self) {
+        if let Some(task) = self.task.lock().take() {
+            task.abort();
+        }
+    }
+
+    /// Check whether the task is still running
+    #[cfg_attr(not(feature = "memory"), allow(dead_code))]
+    pub(crate) fn is_running(&self) -> bool {
+        self.task
+            .lock()
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+    }
+}
+
+impl Drop for PeriodicTask {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
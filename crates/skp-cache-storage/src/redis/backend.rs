@@ -1,87 +1,801 @@
 use async_trait::async_trait;
-use bb8::{Pool, PooledConnection};
-use bb8_redis::RedisConnectionManager;
-use redis::{AsyncCommands, Value};
+use futures_util::StreamExt;
+use futures_util::future::try_join_all;
+use redis::{AsyncCommands, PushInfo, PushKind, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
-use parking_lot::RwLock as SyncRwLock;
+use parking_lot::{Mutex as SyncMutex, RwLock as SyncRwLock};
 use skp_cache_core::{
-    CacheBackend, CacheEntry, CacheError, CacheOptions, CacheStats, DependencyBackend, Result, TaggableBackend,
+    CacheBackend, CacheEntry, CacheError, CacheMetrics, CacheOptions, CacheStats, DependencyBackend,
+    DistributedBackend, KeyPattern, NoopMetrics, PatternBackend, Result, TaggableBackend, delete_cascade_walk,
 };
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use super::config::RedisConfig;
+use super::connection::{Connection, Connector, Scan};
+use super::encoding::decode_entry;
+use super::layout::{KeyLayout, Shard};
+use super::pubsub::{Forwarder, InvalidationEvent, InvalidationPublisher, InvalidationSubscriber, parse_message};
+use super::tracking::{self, Invalidation, Tracker, TrackingMode, TrackingSlot};
+use crate::periodic::PeriodicTask;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// Calls of the write script before giving up on a key whose tags keep changing
+const MAX_WRITE_ATTEMPTS: usize = 16;
+
+/// Set members checked per prune script call
+const PRUNE_BATCH: usize = 512;
+
+/// Buffered invalidation events per local subscriber
+const INVALIDATION_BUFFER: usize = 1024;
+
+/// Delay before re-subscribing after the pub/sub connection is lost
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+/// Deletes a lock only if it still holds the caller's token
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// Lua reading stored entries, shared by the scripts that need their index sets
+///
+/// Expects the shard namespace in ARGV[1]. `read_entry(data)` decodes a binary
+/// or JSON entry into its version and the tag and dependency sets listing it;
+/// missing or unreadable entries count as version 0 with no sets.
+macro_rules! entry_reader_lua {
+    () => {
+        r#"
+local namespace = ARGV[1]
+local magic = string.char(0, 229)
+
+local function u32(data, pos)
+    local a, b, c, d = string.byte(data, pos, pos + 3)
+    return ((a * 256 + b) * 256 + c) * 256 + d
+end
+
+local function parse_binary(data)
+    if string.byte(data, 3) ~= 1 then
+        error("unknown format version")
+    end
+    local version = 0
+    for i = 61, 68 do
+        version = version * 256 + string.byte(data, i)
+    end
+    local sets = {}
+    local pos = 69
+    for _, kind in ipairs({"__tags__:", "__deps__:"}) do
+        local count = u32(data, pos)
+        pos = pos + 4
+        for _ = 1, count do
+            local len = u32(data, pos)
+            sets[#sets + 1] = namespace .. kind .. string.sub(data, pos + 4, pos + 3 + len)
+            pos = pos + 4 + len
+        end
+    end
+    return version, sets
+end
+
+local function parse_json(data)
+    local entry = cjson.decode(data)
+    local sets = {}
+    for _, tag in ipairs(entry.tags) do
+        sets[#sets + 1] = namespace .. "__tags__:" .. tag
+    end
+    for _, dep in ipairs(entry.dependencies) do
+        sets[#sets + 1] = namespace .. "__deps__:" .. dep
+    end
+    return tonumber(entry.version) or 0, sets
+end
+
+local function read_entry(data)
+    if not data then
+        return 0, {}
+    end
+    local parse = parse_json
+    if string.sub(data, 1, 2) == magic then
+        parse = parse_binary
+    end
+    local ok, version, sets = pcall(parse, data)
+    if not ok then
+        return 0, {}
+    end
+    return version, sets
+end
+
+"#
+    };
+}
+
+/// Writes the entries of one shard, assigning versions and checking `if_version`
+///
+/// Nothing is written unless every check passes. Stored entries are decoded
+/// here (binary or JSON; unreadable ones count as absent) for their version
+/// and the tag and dependency sets they leave. Those sets must be in KEYS:
+/// sets that are not are returned, and the caller retries with them.
+///
+/// KEYS: per entry, the entry, the sets it joins, then the sets it may leave.
+/// ARGV: namespace of the shard, then per entry: expected version (empty for
+/// any), encoded entry before and after its version, version to store (empty
+/// for the stored one + 1), TTL in ms (0 for none), cache key, number of sets
+/// joined, number of sets it may leave.
+/// Returns {1} once written, {0, entry, stored version} on a version
+/// mismatch, or {2, entry, set, entry, set, ...} for sets missing from KEYS.
+const WRITE_ENTRIES_SCRIPT: &str = concat!(entry_reader_lua!(), r#"
+local function be64(n)
+    local bytes = {}
+    for i = 8, 1, -1 do
+        bytes[i] = string.char(n % 256)
+        n = math.floor(n / 256)
+    end
+    return table.concat(bytes)
+end
+
+local entries = {}
+local missing = {2}
+local k = 1
+for base = 2, #ARGV, 8 do
+    local index = #entries + 1
+    local joins = tonumber(ARGV[base + 6])
+    local leaves = tonumber(ARGV[base + 7])
+    local version, sets = read_entry(redis.call("GET", KEYS[k]))
+    if ARGV[base] ~= "" and tonumber(ARGV[base]) ~= version then
+        return {0, index, version}
+    end
+
+    local joined, declared, left = {}, {}, {}
+    for i = k + 1, k + joins do
+        joined[KEYS[i]] = true
+    end
+    for i = k + 1, k + joins + leaves do
+        declared[KEYS[i]] = true
+    end
+    for _, set in ipairs(sets) do
+        if not declared[set] then
+            missing[#missing + 1] = index
+            missing[#missing + 1] = set
+        elseif not joined[set] then
+            left[#left + 1] = set
+        end
+    end
+
+    entries[index] = {
+        key = k,
+        base = base,
+        joins = joins,
+        left = left,
+        version = tonumber(ARGV[base + 3]) or version + 1,
+    }
+    k = k + 1 + joins + leaves
+end
+if #missing > 1 then
+    return missing
+end
+
+for _, entry in ipairs(entries) do
+    local base = entry.base
+    local head = ARGV[base + 1]
+    local version
+    if string.sub(head, 1, 2) == magic then
+        version = be64(entry.version)
+    else
+        version = string.format("%d", entry.version)
+    end
+    local value = head .. version .. ARGV[base + 2]
+    if ARGV[base + 4] == "0" then
+        redis.call("SET", KEYS[entry.key], value)
+    else
+        redis.call("SET", KEYS[entry.key], value, "PX", ARGV[base + 4])
+    end
+    for i = entry.key + 1, entry.key + entry.joins do
+        redis.call("SADD", KEYS[i], ARGV[base + 5])
+    end
+    for _, set in ipairs(entry.left) do
+        redis.call("SREM", set, ARGV[base + 5])
+    end
+end
+return {1}
+"#);
+
+/// Removes the members of a tag or dependency set whose entry is gone
+///
+/// KEYS: set, then the entry of each candidate member.
+/// ARGV: candidate members. Returns the number removed.
+const PRUNE_SET_SCRIPT: &str = r#"
+local removed = 0
+for i = 2, #KEYS do
+    if redis.call("EXISTS", KEYS[i]) == 0 then
+        removed = removed + redis.call("SREM", KEYS[1], ARGV[i - 1])
+    end
+end
+return removed
+"#;
+
+/// Deletes a key and, breadth-first, every key depending on it
+///
+/// Each deleted entry leaves its tag and dependency sets, and the set of keys
+/// depending on it is deleted. Dependents are only known once their sets are
+/// read, so their keys are built here rather than passed in KEYS: only run it
+/// unsharded, where every key lives on the same node.
+///
+/// KEYS: root entry, set of the keys depending on it.
+/// ARGV: key namespace, root key. Returns the deleted keys.
+const DELETE_CASCADE_SCRIPT: &str = concat!(entry_reader_lua!(), r#"
+local queue = {ARGV[2]}
+local visited = {[ARGV[2]] = true}
+local deleted = {}
+local head = 1
+while head <= #queue do
+    local key = queue[head]
+    local entry_key, deps_key = KEYS[1], KEYS[2]
+    if head > 1 then
+        entry_key = namespace .. key
+        deps_key = namespace .. "__deps__:" .. key
+    end
+    head = head + 1
+    for _, dependent in ipairs(redis.call("SMEMBERS", deps_key)) do
+        if not visited[dependent] then
+            visited[dependent] = true
+            queue[#queue + 1] = dependent
+        end
+    end
+    local _, sets = read_entry(redis.call("GET", entry_key))
+    if redis.call("DEL", entry_key) == 1 then
+        deleted[#deleted + 1] = key
+        for _, set in ipairs(sets) do
+            redis.call("SREM", set, key)
+        end
+    end
+    redis.call("DEL", deps_key)
+end
+return deleted
+"#);
 
 /// Redis backend implementation
 #[derive(Clone)]
 pub struct RedisBackend {
-    pool: Pool<RedisConnectionManager>,
+    connector: Connector,
+    layout: KeyLayout,
+    /// Client for dedicated pub/sub connections, which cannot be pooled
+    client: redis::Client,
     config: RedisConfig,
     stats: Arc<SyncRwLock<CacheStats>>,
+    /// Tags published messages so this backend and its clones can skip their own
+    instance_id: Arc<str>,
+    /// Fans out events received on the invalidation channel
+    invalidations: InvalidationPublisher,
+    /// Forwards the invalidation channel once `subscribe_invalidations` is called
+    forwarder: Arc<SyncMutex<Option<Forwarder>>>,
+    sweeper: Option<Arc<PeriodicTask>>,
+    tracker: Option<Arc<Tracker>>,
 }
 
 impl RedisBackend {
     /// Create a new Redis backend
     pub async fn new(config: RedisConfig) -> Result<Self> {
-        let manager = RedisConnectionManager::new(config.url.as_str())
-            .map_err(|e| CacheError::Connection(e.to_string()))?;
-            
-        let pool = Pool::builder()
-            .max_size(config.pool_size)
-            .connection_timeout(config.connection_timeout)
-            .build(manager)
-            .await
+        Self::with_metrics(config, NoopMetrics).await
+    }
+
+    /// Create a new Redis backend reporting Sentinel failovers to `metrics`
+    ///
+    /// Starts the background index sweep if `config.background_index_sweep` is
+    /// set, and opens the tracking connection if `config.client_tracking` is.
+    pub async fn with_metrics(config: RedisConfig, metrics: impl CacheMetrics) -> Result<Self> {
+        let connector = Connector::connect(&config, Arc::new(metrics)).await?;
+        // Cluster nodes forward PUBLISHed messages to each other, so any one will do
+        let pubsub_url = config.cluster_nodes.first().unwrap_or(&config.url);
+        let client = redis::Client::open(pubsub_url.as_str())
             .map_err(|e| CacheError::Connection(e.to_string()))?;
-            
-        Ok(Self {
-            pool,
+        let layout = if config.cluster_nodes.is_empty() {
+            KeyLayout::single(config.key_prefix.clone())
+        } else {
+            KeyLayout::sharded(config.key_prefix.clone(), config.cluster_shards)
+        };
+
+        let (invalidations, _) = InvalidationPublisher::new(INVALIDATION_BUFFER);
+
+        let mut backend = Self {
+            connector,
+            layout,
+            client,
             config,
             stats: Arc::new(SyncRwLock::new(CacheStats::default())),
-        })
+            instance_id: format!("{:016x}", rand::random::<u64>()).into(),
+            invalidations,
+            forwarder: Arc::default(),
+            sweeper: None,
+            tracker: None,
+        };
+        // Background tasks get a handle without the sweeper and tracker so
+        // dropping the last user handle stops them
+        let detached = backend.clone();
+
+        if let Some(mode) = detached.config.client_tracking {
+            if !detached.config.cluster_nodes.is_empty() {
+                return Err(CacheError::Connection(
+                    "client tracking is not supported in cluster mode".into(),
+                ));
+            }
+            let (pushes, received) = mpsc::unbounded_channel();
+            let conn = detached.tracking_connection(mode, pushes.clone()).await?;
+            let slot: TrackingSlot = Arc::new(SyncRwLock::new(Some(conn)));
+            let task = tokio::spawn(detached.clone().track(mode, slot.clone(), pushes, received));
+            backend.tracker = Some(Arc::new(Tracker::new(mode, slot, task)));
+        }
+
+        if detached.config.background_index_sweep {
+            let sweeper = PeriodicTask::spawn(backend.config.index_sweep_interval, move || {
+                let detached = detached.clone();
+                // Failures are retried on the next tick
+                async move {
+                    let _ = detached.sweep_indexes().await;
+                }
+            })?;
+            backend.sweeper = Some(Arc::new(sweeper));
+        }
+
+        Ok(backend)
     }
-    
-    /// Get prefix for a key
-    fn prefixed_key(&self, key: &str) -> String {
-        match &self.config.key_prefix {
-            Some(prefix) => format!("{}:{}", prefix, key),
-            None => key.to_string(),
+
+    /// Check whether changed keys are reported through `CLIENT TRACKING`
+    pub fn is_tracking(&self) -> bool {
+        self.tracker.is_some()
+    }
+
+    /// Stop the background index sweep, if running
+    ///
+    /// Affects every clone of this backend.
+    pub fn shutdown(&self) {
+        if let Some(sweeper) = &self.sweeper {
+            sweeper.stop();
+        }
+    }
+
+    /// Remove keys of entries that no longer exist from tag and dependency sets
+    ///
+    /// Overwrites and deletes keep the sets up to date, but entries expiring
+    /// through their TTL, or removed by other clients, stay listed until
+    /// swept. Returns the number of members removed.
+    pub async fn sweep_indexes(&self) -> Result<u64> {
+        let mut conn = self.get_connection().await?;
+        let mut removed = 0;
+
+        for namespace in ["__tags__:*", "__deps__:*"] {
+            let pattern = KeyPattern::new(namespace);
+            for mut scan in self.scans(&mut conn, namespace).await? {
+                while let Some(sets) = scan.next_batch(&mut conn).await? {
+                    for set in sets {
+                        if self.layout.scanned_key(&set, &pattern).is_some() {
+                            removed += self.prune_set(&mut conn, &set).await?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Scans for keys of any shard matching `pattern`, one per node
+    async fn scans(&self, conn: &mut Connection<'_>, pattern: &str) -> Result<Vec<Scan>> {
+        Scan::per_node(conn, &self.layout.scan_pattern(pattern), &self.layout.slots()).await
+    }
+
+    /// Members of the set `set_key` names in each shard
+    ///
+    /// Sets of different shards cannot share a cluster pipeline, so they are
+    /// read concurrently: the cluster connection pipelines the commands bound
+    /// for the same node, and the read costs about one round trip.
+    async fn shard_members(&self, set_key: impl Fn(Shard) -> String) -> Result<Vec<(Shard, Vec<String>)>> {
+        let reads = self.layout.shards().into_iter().map(|shard| {
+            let set = set_key(shard);
+            async move {
+                let mut conn = self.get_connection().await?;
+                let members: Vec<String> = conn.smembers(&set).await
+                    .map_err(|e| CacheError::Backend(e.to_string()))?;
+                Ok::<_, CacheError>((shard, members))
+            }
+        });
+        try_join_all(reads).await
+    }
+
+    /// Remove the members of one set whose entry no longer exists
+    ///
+    /// A member is only removed if its entry is missing when the script runs,
+    /// so an entry written during the sweep keeps its membership.
+    async fn prune_set(&self, conn: &mut Connection<'_>, set: &str) -> Result<u64> {
+        let members: Vec<String> = conn.smembers(set).await
+            .map_err(|e| CacheError::Backend(e.to_string()))?;
+        let script = redis::Script::new(PRUNE_SET_SCRIPT);
+
+        let mut removed = 0;
+        for batch in members.chunks(PRUNE_BATCH) {
+            let mut invocation = script.key(set);
+            for member in batch {
+                // Members share the set's shard
+                invocation.key(self.layout.data_key(member));
+            }
+            invocation.arg(batch);
+
+            let count: u64 = invocation.invoke_async(conn).await
+                .map_err(|e| CacheError::Backend(e.to_string()))?;
+            removed += count;
+        }
+        Ok(removed)
+    }
+
+    /// Receive invalidation events published by other instances
+    ///
+    /// Events from other instances only arrive after `subscribe_invalidations`
+    /// has been called, and events published by this backend or any of its
+    /// clones are not delivered. The subscription lasts until the backend and
+    /// all its clones are dropped; when its connection drops,
+    /// `InvalidationEvent::Clear` is delivered, and again once re-subscribed.
+    /// With client tracking enabled, keys reported by Redis arrive as
+    /// `InvalidationEvent::Key`, whoever changed them.
+    pub fn invalidation_events(&self) -> InvalidationSubscriber {
+        self.invalidations.subscribe()
+    }
+
+    /// ID attached to the invalidation messages this backend publishes
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Publish invalidation events to all other instances
+    pub async fn publish_events(&self, events: &[InvalidationEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
         }
+        let mut conn = self.get_connection().await?;
+
+        let mut pipe = redis::pipe();
+        for event in events {
+            let message = event.to_message_from(&self.instance_id);
+            pipe.publish(&self.config.invalidation_channel, message).ignore();
+        }
+
+        pipe.query_async::<()>(&mut conn)
+            .await
+            .map_err(|e| CacheError::Backend(e.to_string()))
     }
     
-    /// Get tag key
-    fn tag_key(&self, tag: &str) -> String {
-        match &self.config.key_prefix {
-            Some(prefix) => format!("{}:__tags__:{}", prefix, tag),
-            None => format!("__tags__:{}", tag),
+    /// Get a connection
+    async fn get_connection(&self) -> Result<Connection<'_>> {
+        self.connector.get().await
+    }
+
+    /// Get a connection for reads, to a replica if configured
+    ///
+    /// Reads go over the tracking connection in `TrackingMode::Default`, so
+    /// Redis tracks the keys they return.
+    async fn get_read_connection(&self) -> Result<Connection<'_>> {
+        if let Some(conn) = self.tracker.as_ref().and_then(|tracker| tracker.read_connection()) {
+            return Ok(Connection::Tracked(conn));
+        }
+        self.connector.get_for_read().await
+    }
+
+    /// Client for dedicated connections to the primary
+    async fn primary_client(&self) -> Result<redis::Client> {
+        // Follow the primary across failovers in Sentinel mode
+        match self.connector.discovered_primary().await? {
+            Some(client) => Ok(client),
+            None => Ok(self.client.clone()),
+        }
+    }
+
+    /// Open a pub/sub connection subscribed to the invalidation channel
+    async fn invalidation_stream(&self) -> Result<redis::aio::PubSubStream> {
+        let mut pubsub = self
+            .primary_client()
+            .await?
+            .get_async_pubsub()
+            .await
+            .map_err(|e| CacheError::Connection(e.to_string()))?;
+        pubsub
+            .subscribe(&self.config.invalidation_channel)
+            .await
+            .map_err(|e| CacheError::Connection(e.to_string()))?;
+        Ok(pubsub.into_on_message())
+    }
+
+    /// Forward messages to local subscribers, re-subscribing when the connection drops
+    ///
+    /// Messages published while disconnected are lost, so local copies are
+    /// cleared both when the connection drops and once it is back.
+    async fn forward_invalidations(self, mut stream: redis::aio::PubSubStream) {
+        loop {
+            while let Some(msg) = stream.next().await {
+                let Ok(payload) = msg.get_payload::<String>() else {
+                    continue;
+                };
+                let Some((origin, event)) = parse_message(&payload) else {
+                    continue;
+                };
+                if origin != Some(&*self.instance_id) {
+                    // No local subscribers is not an error
+                    let _ = self.invalidations.publish(event);
+                }
+            }
+
+            let _ = self.invalidations.publish(InvalidationEvent::Clear);
+            stream = loop {
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                if let Ok(stream) = self.invalidation_stream().await {
+                    break stream;
+                }
+            };
+            let _ = self.invalidations.publish(InvalidationEvent::Clear);
+        }
+    }
+
+    /// Open a tracking connection forwarding its pushes to `pushes`
+    async fn tracking_connection(
+        &self,
+        mode: TrackingMode,
+        pushes: UnboundedSender<PushInfo>,
+    ) -> Result<redis::aio::MultiplexedConnection> {
+        let client = self.primary_client().await?;
+        let namespace = self.layout.unsharded_namespace().unwrap_or_default();
+        tracking::connect(&client, mode, &namespace, pushes)
+            .await
+            .map_err(|e| CacheError::Connection(e.to_string()))
+    }
+
+    /// Turn tracking pushes into invalidation events, reconnecting when the
+    /// connection drops
+    ///
+    /// Keys changed while disconnected are never reported, so local copies are
+    /// cleared both when the connection drops and once it is back.
+    async fn track(
+        self,
+        mode: TrackingMode,
+        slot: TrackingSlot,
+        pushes: UnboundedSender<PushInfo>,
+        mut received: UnboundedReceiver<PushInfo>,
+    ) {
+        while let Some(push) = received.recv().await {
+            if push.kind == PushKind::Disconnection {
+                *slot.write() = None;
+                let _ = self.invalidations.publish(InvalidationEvent::Clear);
+                let conn = loop {
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                    if let Ok(conn) = self.tracking_connection(mode, pushes.clone()).await {
+                        break conn;
+                    }
+                };
+                *slot.write() = Some(conn);
+                let _ = self.invalidations.publish(InvalidationEvent::Clear);
+                continue;
+            }
+
+            // No local subscribers is not an error
+            match tracking::parse_push(push) {
+                Some(Invalidation::All) => {
+                    let _ = self.invalidations.publish(InvalidationEvent::Clear);
+                }
+                Some(Invalidation::Keys(keys)) => {
+                    for key in keys {
+                        if let Some(key) = self.layout.cache_key(&key) {
+                            let _ = self.invalidations.publish(InvalidationEvent::Key(key.to_string()));
+                        }
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Write entries, assigning versions and checking `if_version` in Redis
+    ///
+    /// Each shard is written with one `WRITE_ENTRIES_SCRIPT` call, so on a
+    /// single node the whole batch is written, or nothing if a check fails.
+    async fn write_entries(&self, entries: &[EntryWrite<'_>]) -> Result<()> {
+        let mut conn = self.get_connection().await?;
+        let mut shards: HashMap<Shard, Vec<&EntryWrite<'_>>> = HashMap::new();
+        for entry in entries {
+            shards.entry(self.layout.shard_of(entry.0)).or_default().push(entry);
+        }
+
+        for (shard, entries) in shards {
+            self.write_shard_entries(&mut conn, shard, &entries).await?;
+        }
+        self.stats.write().writes += entries.len() as u64;
+        Ok(())
+    }
+
+    /// Write entries of a single shard, see `write_entries`
+    async fn write_shard_entries(
+        &self,
+        conn: &mut Connection<'_>,
+        shard: Shard,
+        entries: &[&EntryWrite<'_>],
+    ) -> Result<()> {
+        let mut encoded = Vec::with_capacity(entries.len());
+        for (key, value, options) in entries {
+            let joined = self.layout.index_sets(key, &options.tags, &options.dependencies);
+            let parts = self.config.entry_encoding.encode_around_version(&new_entry(value, options))?;
+            encoded.push((joined, parts));
+        }
+        // Sets of the stored entries, learnt from the script
+        let mut left: Vec<Vec<String>> = vec![Vec::new(); entries.len()];
+        let script = redis::Script::new(WRITE_ENTRIES_SCRIPT);
+
+        for _ in 0..MAX_WRITE_ATTEMPTS {
+            let mut invocation = script.arg(self.layout.namespace(shard));
+            for (((key, _, options), (joined, (head, tail))), left) in entries.iter().zip(&encoded).zip(&left) {
+                let ttl_ms = options.ttl.map_or(0, |ttl| {
+                    let total_ttl = ttl + options.stale_while_revalidate.unwrap_or_default();
+                    // PX rejects 0
+                    (total_ttl.as_millis() as u64).max(1)
+                });
+                invocation
+                    .key(self.layout.data_key(key))
+                    .key(joined)
+                    .key(left)
+                    .arg(options.if_version.map(|v| v.to_string()).unwrap_or_default())
+                    .arg(head)
+                    .arg(tail)
+                    .arg(options.version.map(|v| v.to_string()).unwrap_or_default())
+                    .arg(ttl_ms)
+                    .arg(*key)
+                    .arg(joined.len())
+                    .arg(left.len());
+            }
+
+            let reply: Vec<Value> = invocation.invoke_async(conn).await
+                .map_err(|e| CacheError::Backend(e.to_string()))?;
+            match parse_write_reply(reply)? {
+                WriteReply::Written => return Ok(()),
+                WriteReply::VersionMismatch { entry, actual } => {
+                    let expected = entries.get(entry).and_then(|e| e.2.if_version).unwrap_or_default();
+                    return Err(CacheError::VersionMismatch { expected, actual });
+                }
+                WriteReply::Undeclared(sets) => {
+                    for (entry, set) in sets {
+                        if let Some(left) = left.get_mut(entry) {
+                            left.push(set);
+                        }
+                    }
+                }
+            }
         }
+
+        Err(CacheError::LockConflict(entries[0].0.to_string()))
     }
 
-    /// Get dependency key
-    fn dep_key(&self, dep: &str) -> String {
-        match &self.config.key_prefix {
-            Some(prefix) => format!("{}:__deps__:{}", prefix, dep),
-            None => format!("__deps__:{}", dep),
+    /// Delete entries and remove them from their tag and dependency sets
+    ///
+    /// The entries are read first to find their sets. Members left behind by
+    /// a concurrent overwrite are removed by `sweep_indexes`. Keys are handled
+    /// shard by shard, as cluster pipelines must stay within one slot.
+    async fn delete_entries(&self, keys: &[&str]) -> Result<u64> {
+        let mut conn = self.get_connection().await?;
+        let mut shards: HashMap<Shard, Vec<&str>> = HashMap::new();
+        for key in keys {
+            shards.entry(self.layout.shard_of(key)).or_default().push(key);
+        }
+
+        let mut count = 0;
+        for keys in shards.values() {
+            count += self.delete_shard_entries(&mut conn, keys, None).await?;
+        }
+        self.stats.write().deletes += count;
+        Ok(count)
+    }
+
+    /// Delete entries of a single shard, see `delete_entries`
+    ///
+    /// `set`, if given, is deleted along with them, atomically. Returns the
+    /// number of entries that existed.
+    async fn delete_shard_entries(
+        &self,
+        conn: &mut Connection<'_>,
+        keys: &[&str],
+        set: Option<&str>,
+    ) -> Result<u64> {
+        let data_keys: Vec<String> = keys.iter().map(|k| self.layout.data_key(k)).collect();
+        let current: Vec<Option<Vec<u8>>> = redis::cmd("MGET")
+            .arg(&data_keys)
+            .query_async(conn)
+            .await
+            .map_err(|e| CacheError::Backend(e.to_string()))?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for ((key, data_key), current) in keys.iter().zip(&data_keys).zip(current) {
+            pipe.del(data_key);
+            // An entry that cannot be decoded is still deleted
+            if let Some(entry) = current.and_then(|data| decode_entry(&data).ok()) {
+                for set in self.layout.index_sets(key, &entry.tags, &entry.dependencies) {
+                    pipe.srem(set, *key).ignore();
+                }
+            }
+        }
+        if let Some(set) = set {
+            pipe.del(set).ignore();
         }
+
+        let deleted: Vec<u64> = pipe.query_async(conn).await
+            .map_err(|e| CacheError::Backend(e.to_string()))?;
+        Ok(deleted.iter().sum())
+    }
+
+}
+
+/// Entry passed to `set_many`
+type EntryWrite<'a> = (&'a str, Vec<u8>, &'a CacheOptions);
+
+/// Outcome of `WRITE_ENTRIES_SCRIPT`
+enum WriteReply {
+    Written,
+    /// `if_version` of the entry at this index did not match
+    VersionMismatch { entry: usize, actual: u64 },
+    /// Sets the entries at these indexes leave, missing from KEYS
+    Undeclared(Vec<(usize, String)>),
+}
+
+fn parse_write_reply(reply: Vec<Value>) -> Result<WriteReply> {
+    let malformed = || CacheError::Backend("unexpected write script reply".into());
+    // Entries are numbered from 1 in Lua
+    let index = |value: &Value| match value {
+        Value::Int(n) if *n >= 1 => Ok(*n as usize - 1),
+        _ => Err(malformed()),
+    };
+
+    match reply.as_slice() {
+        [Value::Int(1)] => Ok(WriteReply::Written),
+        [Value::Int(0), entry, Value::Int(actual)] => Ok(WriteReply::VersionMismatch {
+            entry: index(entry)?,
+            actual: *actual as u64,
+        }),
+        [Value::Int(2), rest @ ..] => rest
+            .chunks(2)
+            .map(|pair| match pair {
+                [entry, Value::BulkString(set)] => Ok((index(entry)?, String::from_utf8_lossy(set).into_owned())),
+                _ => Err(malformed()),
+            })
+            .collect::<Result<_>>()
+            .map(WriteReply::Undeclared),
+        _ => Err(malformed()),
     }
+}
 
-    /// Get connection from pool
-    async fn get_connection(&self) -> Result<PooledConnection<'_, RedisConnectionManager>> {
-        self.pool.get().await.map_err(|e| CacheError::Connection(e.to_string()))
+/// Build the entry stored for a write, its version left to the write script
+fn new_entry(value: &[u8], options: &CacheOptions) -> CacheEntry<Vec<u8>> {
+    CacheEntry {
+        value: value.to_vec(),
+        created_at: SystemTime::now(),
+        last_accessed: SystemTime::now(),
+        access_count: 0,
+        ttl: options.ttl,
+        stale_while_revalidate: options.stale_while_revalidate,
+        tags: options.tags.clone(),
+        dependencies: options.dependencies.clone(),
+        cost: options.cost.unwrap_or(1),
+        compute_time: options.compute_time,
+        size: 0, // Not easily calculable here without serialization first, but we will serialize next
+        etag: options.etag.clone(),
+        version: 0,
+        negative: options.negative,
     }
 }
 
 #[async_trait]
 impl CacheBackend for RedisBackend {
     async fn get(&self, key: &str) -> Result<Option<CacheEntry<Vec<u8>>>> {
-        let mut conn = self.get_connection().await?;
-        let prefixed = self.prefixed_key(key);
+        let mut conn = self.get_read_connection().await?;
+        let prefixed = self.layout.data_key(key);
         
         let bytes: Option<Vec<u8>> = conn.get(&prefixed).await
             .map_err(|e| CacheError::Backend(e.to_string()))?;
             
         match bytes {
             Some(data) => {
-                let entry: CacheEntry<Vec<u8>> = serde_json::from_slice(&data)
-                    .map_err(|e| CacheError::Deserialization(e.to_string()))?;
+                let entry = decode_entry(&data)?;
                 
                 // Update hit stats
                 self.stats.write().hits += 1;
@@ -101,107 +815,39 @@ impl CacheBackend for RedisBackend {
         value: Vec<u8>,
         options: &CacheOptions,
     ) -> Result<()> {
-        let mut conn = self.get_connection().await?;
-        
-        // Create entry wrapper
-        let entry = CacheEntry {
-            value,
-            created_at: SystemTime::now(),
-            last_accessed: SystemTime::now(),
-            access_count: 0,
-            ttl: options.ttl,
-            stale_while_revalidate: options.stale_while_revalidate,
-            tags: options.tags.clone(),
-            dependencies: options.dependencies.clone(),
-            cost: options.cost.unwrap_or(1),
-            size: 0, // Not easily calculable here without serialization first, but we will serialize next
-            etag: options.etag.clone(),
-            version: 0,
-        };
-        
-        // Serialize
-        let serialized = serde_json::to_vec(&entry)
-            .map_err(|e| CacheError::Serialization(e.to_string()))?;
-            
-        let prefixed = self.prefixed_key(key);
-        
-        // Use pipeline for atomicity (set key + update tags)
-        let mut pipe = redis::pipe();
-        pipe.atomic();
-        
-        // Set with TTL
-        if let Some(ttl) = options.ttl {
-             let total_ttl = ttl + options.stale_while_revalidate.unwrap_or_default();
-             pipe.set_ex(&prefixed, &serialized, total_ttl.as_secs());
-        } else {
-             pipe.set(&prefixed, &serialized);
-        }
-        
-        
-        // Add to tags
-        for tag in &options.tags {
-            let tag_k = self.tag_key(tag);
-            pipe.sadd(&tag_k, key);
-        }
-
-        // Add to dependencies
-        for dep in &options.dependencies {
-            let dep_k = self.dep_key(dep);
-            pipe.sadd(&dep_k, key);
-        }
-        
-        pipe.query_async::<Vec<Value>>(&mut *conn).await
-            .map_err(|e| CacheError::Backend(e.to_string()))?;
-            
-        self.stats.write().writes += 1;
-        Ok(())
+        self.write_entries(&[(key, value, options)]).await
     }
 
     async fn delete(&self, key: &str) -> Result<bool> {
-        let mut conn = self.get_connection().await?;
-        let prefixed = self.prefixed_key(key);
-        
-        let deleted: bool = conn.del(&prefixed).await
-            .map_err(|e| CacheError::Backend(e.to_string()))?;
-            
-        if deleted {
-            self.stats.write().deletes += 1;
-        }
-        Ok(deleted)
+        let deleted = self.delete_entries(&[key]).await?;
+        Ok(deleted > 0)
     }
 
     async fn exists(&self, key: &str) -> Result<bool> {
-        let mut conn = self.get_connection().await?;
-        let prefixed = self.prefixed_key(key);
+        let mut conn = self.get_read_connection().await?;
+        let prefixed = self.layout.data_key(key);
         
         conn.exists(&prefixed).await
             .map_err(|e| CacheError::Backend(e.to_string()))
     }
     
     async fn delete_many(&self, keys: &[&str]) -> Result<u64> {
-        let mut conn = self.get_connection().await?;
         if keys.is_empty() {
              return Ok(0);
         }
-        
-        let prefixed_keys: Vec<String> = keys.iter().map(|k| self.prefixed_key(k)).collect();
-        let count: u64 = conn.del(&prefixed_keys).await
-             .map_err(|e| CacheError::Backend(e.to_string()))?;
-             
-        self.stats.write().deletes += count;
-        Ok(count)
+        self.delete_entries(keys).await
     }
 
     async fn get_many(
         &self,
         keys: &[&str],
     ) -> Result<Vec<Option<CacheEntry<Vec<u8>>>>> {
-        let mut conn = self.get_connection().await?;
+        let mut conn = self.get_read_connection().await?;
         if keys.is_empty() {
             return Ok(Vec::new());
         }
 
-        let prefixed_keys: Vec<String> = keys.iter().map(|k| self.prefixed_key(k)).collect();
+        let prefixed_keys: Vec<String> = keys.iter().map(|k| self.layout.data_key(k)).collect();
         let raw_results: Vec<Option<Vec<u8>>> = conn.mget(&prefixed_keys).await
              .map_err(|e| CacheError::Backend(e.to_string()))?;
              
@@ -212,8 +858,7 @@ impl CacheBackend for RedisBackend {
         for raw in raw_results {
             match raw {
                 Some(data) => {
-                    let entry: CacheEntry<Vec<u8>> = serde_json::from_slice(&data)
-                        .map_err(|e| CacheError::Deserialization(e.to_string()))?;
+                    let entry = decode_entry(&data)?;
                     results.push(Some(entry));
                     hits += 1;
                 },
@@ -237,89 +882,27 @@ impl CacheBackend for RedisBackend {
         &self,
         entries: &[(&str, Vec<u8>, &CacheOptions)],
     ) -> Result<()> {
-        let mut conn = self.get_connection().await?;
-        let mut pipe = redis::pipe();
-        pipe.atomic();
-        
-        for (key, value, options) in entries {
-            let entry = CacheEntry {
-                value: value.clone(),
-                created_at: SystemTime::now(),
-                last_accessed: SystemTime::now(),
-                access_count: 0,
-                ttl: options.ttl,
-                stale_while_revalidate: options.stale_while_revalidate,
-                tags: options.tags.clone(),
-                dependencies: options.dependencies.clone(),
-                cost: options.cost.unwrap_or(1),
-                size: 0,
-                etag: options.etag.clone(),
-                version: 0,
-            };
-            
-            let serialized = serde_json::to_vec(&entry)
-                .map_err(|e| CacheError::Serialization(e.to_string()))?;
-            let prefixed = self.prefixed_key(key);
-            
-             if let Some(ttl) = options.ttl {
-                 let total_ttl = ttl + options.stale_while_revalidate.unwrap_or_default();
-                 pipe.set_ex(&prefixed, &serialized, total_ttl.as_secs());
-            } else {
-                 pipe.set(&prefixed, &serialized);
-            }
-            
-            for tag in &options.tags {
-                let tag_k = self.tag_key(tag);
-                pipe.sadd(&tag_k, key);
-            }
-
-            for dep in &options.dependencies {
-                let dep_k = self.dep_key(dep);
-                pipe.sadd(&dep_k, key);
-            }
+        if entries.is_empty() {
+            return Ok(());
         }
-        
-        pipe.query_async::<Vec<Value>>(&mut *conn).await
-            .map_err(|e| CacheError::Backend(e.to_string()))?;
-            
-        self.stats.write().writes += entries.len() as u64;
-        Ok(())
+        self.write_entries(entries).await
     }
 
     async fn clear(&self) -> Result<()> {
         let mut conn = self.get_connection().await?;
-        
-        let match_pattern = match &self.config.key_prefix {
-             Some(prefix) => format!("{}:*", prefix),
-             None => "*".to_string(),
-        };
-        
-        // Scan and delete
-        let mut cursor = 0u64;
-        let count_per_scan = 1000;
-        
-        loop {
-            let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
-                .cursor_arg(cursor)
-                .arg("MATCH")
-                .arg(&match_pattern)
-                .arg("COUNT")
-                .arg(count_per_scan)
-                .query_async(&mut *conn)
-                .await
-                .map_err(|e| CacheError::Backend(e.to_string()))?;
-                
-            if !keys.is_empty() {
-                let _: usize = conn.unlink(&keys).await
-                    .map_err(|e| CacheError::Backend(e.to_string()))?;
-            }
-            
-            cursor = next_cursor;
-            if cursor == 0 {
-                break;
+        let all = KeyPattern::new("*");
+
+        // Scan and delete, node by node
+        for mut scan in self.scans(&mut conn, "*").await? {
+            while let Some(keys) = scan.next_batch(&mut conn).await? {
+                let keys: Vec<String> = keys.into_iter().filter(|key| self.layout.scanned_key(key, &all).is_some()).collect();
+                if !keys.is_empty() {
+                    let _: usize = conn.unlink(&keys).await
+                        .map_err(|e| CacheError::Backend(e.to_string()))?;
+                }
             }
         }
-        
+
         Ok(())
     }
 
@@ -336,95 +919,388 @@ impl CacheBackend for RedisBackend {
         // For now, let's implement O(N) scan count as len() is widely used for debugging/metrics
         // But warning: this is slow on large datasets
         
-        if self.config.key_prefix.is_some() {
-             let match_pattern = format!("{}:*", self.config.key_prefix.as_ref().unwrap());
-             let mut cursor = 0u64;
-             let mut count = 0;
-             loop {
-                 let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
-                    .cursor_arg(cursor)
-                    .arg("MATCH")
-                    .arg(&match_pattern)
-                    .arg("COUNT")
-                    .arg(1000)
-                    .query_async(&mut *conn)
-                    .await
-                    .map_err(|e| CacheError::Backend(e.to_string()))?;
-                    
-                 count += keys.len();
-                 cursor = next_cursor;
-                 if cursor == 0 {
-                     break;
-                 }
-             }
-             Ok(count)
-        } else {
+        if self.layout.owns_database() {
              let size: usize = redis::cmd("DBSIZE")
-                .query_async(&mut *conn)
+                .query_async(&mut conn)
                 .await
                 .map_err(|e| CacheError::Backend(e.to_string()))?;
-             Ok(size)
+             return Ok(size);
+        }
+
+        let all = KeyPattern::new("*");
+        let mut count = 0;
+        for mut scan in self.scans(&mut conn, "*").await? {
+            while let Some(keys) = scan.next_batch(&mut conn).await? {
+                count += keys.iter().filter(|key| self.layout.scanned_key(key, &all).is_some()).count();
+            }
         }
+        Ok(count)
     }
 }
 
 #[async_trait]
 impl TaggableBackend for RedisBackend {
     async fn get_by_tag(&self, tag: &str) -> Result<Vec<String>> {
-        let mut conn = self.get_connection().await?;
-        let tag_k = self.tag_key(tag);
-        
-        let keys: Vec<String> = conn.smembers(&tag_k).await
-             .map_err(|e| CacheError::Backend(e.to_string()))?;
-             
-        Ok(keys)
+        let sets = self.shard_members(|shard| self.layout.tag_key(shard, tag)).await?;
+        Ok(sets.into_iter().flat_map(|(_, members)| members).collect())
     }
 
+    /// Deletes the tagged keys and the tag set atomically within each shard
+    ///
+    /// The keys are also removed from their other tag and dependency sets.
+    /// Members whose entry no longer exists are not counted.
     async fn delete_by_tag(&self, tag: &str) -> Result<u64> {
+        let sets = self.shard_members(|shard| self.layout.tag_key(shard, tag)).await?;
         let mut conn = self.get_connection().await?;
-        let tag_k = self.tag_key(tag);
-        
-        // 1. Get keys
-        let keys: Vec<String> = conn.smembers(&tag_k).await
-             .map_err(|e| CacheError::Backend(e.to_string()))?;
-             
-        if keys.is_empty() {
-             return Ok(0);
+        let mut count = 0;
+
+        // Stored members are raw keys, without prefix
+        for (shard, keys) in sets {
+            if keys.is_empty() {
+                 continue;
+            }
+            let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let tag_k = self.layout.tag_key(shard, tag);
+            count += self.delete_shard_entries(&mut conn, &keys, Some(&tag_k)).await?;
         }
-        
-        let prefixed_keys: Vec<String> = keys.iter().map(|k| self.prefixed_key(k)).collect();
-        
-        // 2. Delete keys and the tag key itself in a transaction?
-        // But we need to make sure we prefix them correctly.
-        // Wait, stored members in SET are raw keys or prefixed keys?
-        // In set(): `pipe.sadd(&tag_k, key);` <- stores raw key WITHOUT prefix.
-        // So `prefixed_keys` above requires prefixing.
-        
-        let mut pipe = redis::pipe();
-        pipe.atomic();
-        
-        for k in &prefixed_keys {
-             pipe.del(k);
+
+        self.stats.write().deletes += count;
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl PatternBackend for RedisBackend {
+    /// Incrementally SCANs for matching keys and deletes each batch
+    ///
+    /// Deleted keys are removed from their tag and dependency sets, as with
+    /// `delete_many`. Not atomic: keys written while the scan runs may survive.
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<u64> {
+        let mut conn = self.get_connection().await?;
+        let matcher = KeyPattern::new(pattern);
+        let mut count = 0u64;
+
+        for mut scan in self.scans(&mut conn, pattern).await? {
+            while let Some(keys) = scan.next_batch(&mut conn).await? {
+                let keys: Vec<&str> = keys
+                    .iter()
+                    .filter(|key| self.layout.scanned_key(key, &matcher).is_some())
+                    .filter_map(|key| self.layout.cache_key(key))
+                    .collect();
+                if !keys.is_empty() {
+                    count += self.delete_entries(&keys).await?;
+                }
+            }
         }
-        pipe.del(&tag_k);
-        
-        pipe.query_async::<Vec<Value>>(&mut *conn).await
-            .map_err(|e| CacheError::Backend(e.to_string()))?;
-            
-        self.stats.write().deletes += keys.len() as u64;
-        Ok(keys.len() as u64)
+
+        Ok(count)
     }
 }
 
 #[async_trait]
 impl DependencyBackend for RedisBackend {
     async fn get_dependents(&self, key: &str) -> Result<Vec<String>> {
+        let sets = self.shard_members(|shard| self.layout.dep_key(shard, key)).await?;
+        Ok(sets.into_iter().flat_map(|(_, members)| members).collect())
+    }
+
+    /// Runs the whole cascade in one Lua script, so no other writer can
+    /// interleave with it
+    ///
+    /// Dependents may live in other slots of a cluster, where the graph is
+    /// walked one key at a time instead.
+    async fn delete_cascade(&self, key: &str) -> Result<Vec<String>> {
+        let Some(namespace) = self.layout.unsharded_namespace() else {
+            return delete_cascade_walk(self, key).await;
+        };
         let mut conn = self.get_connection().await?;
-        let dep_k = self.dep_key(key);
-        
-        let keys: Vec<String> = conn.smembers(&dep_k).await
-             .map_err(|e| CacheError::Backend(e.to_string()))?;
-             
-        Ok(keys)
+
+        let shard = self.layout.shard_of(key);
+        let deleted: Vec<String> = redis::Script::new(DELETE_CASCADE_SCRIPT)
+            .key(self.layout.data_key(key))
+            .key(self.layout.dep_key(shard, key))
+            .arg(namespace)
+            .arg(key)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| CacheError::Backend(e.to_string()))?;
+
+        self.stats.write().deletes += deleted.len() as u64;
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl DistributedBackend for RedisBackend {
+    async fn acquire_lock(&self, key: &str, ttl: Duration) -> Result<String> {
+        let mut conn = self.get_connection().await?;
+        let token = format!("{:032x}", rand::random::<u128>());
+        // PX rejects 0
+        let ttl_ms = (ttl.as_millis() as u64).max(1);
+
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(self.layout.lock_key(key))
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl_ms)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| CacheError::Backend(e.to_string()))?;
+
+        match acquired {
+            Some(_) => Ok(token),
+            None => Err(CacheError::LockConflict(key.to_string())),
+        }
+    }
+
+    async fn release_lock(&self, key: &str, token: &str) -> Result<bool> {
+        let mut conn = self.get_connection().await?;
+
+        let deleted: i64 = redis::Script::new(RELEASE_LOCK_SCRIPT)
+            .key(self.layout.lock_key(key))
+            .arg(token)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| CacheError::Backend(e.to_string()))?;
+
+        Ok(deleted == 1)
+    }
+
+    async fn publish_invalidation(&self, keys: &[&str]) -> Result<()> {
+        let events: Vec<InvalidationEvent> = keys
+            .iter()
+            .map(|key| InvalidationEvent::Key(key.to_string()))
+            .collect();
+        self.publish_events(&events).await
+    }
+
+    async fn subscribe_invalidations(&self) -> Result<()> {
+        if self.forwarder.lock().is_some() {
+            return Ok(());
+        }
+
+        let stream = self.invalidation_stream().await?;
+        let mut forwarder = self.forwarder.lock();
+        // Another call subscribed while this one connected
+        if forwarder.is_none() {
+            // Without the sweeper, tracker and forwarder, so the task does not
+            // keep them or itself running
+            let detached = Self {
+                sweeper: None,
+                tracker: None,
+                forwarder: Arc::default(),
+                ..self.clone()
+            };
+            let task = tokio::spawn(detached.forward_invalidations(stream));
+            *forwarder = Some(Forwarder::new(task));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Backend on the server at `REDIS_URL` (local default port otherwise),
+    /// under a prefix of its own
+    async fn backend() -> RedisBackend {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".into());
+        let prefix = format!("skp-test-{:016x}", rand::random::<u64>());
+        RedisBackend::new(RedisConfig::new(url).prefix(prefix)).await.unwrap()
+    }
+
+    fn tagged(tags: &[&str]) -> CacheOptions {
+        CacheOptions {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server (REDIS_URL)"]
+    async fn test_overwrite_and_delete_prune_indexes() {
+        let redis = backend().await;
+
+        redis.set("a", b"1".to_vec(), &tagged(&["old"])).await.unwrap();
+        redis.set("b", b"1".to_vec(), &tagged(&["old"])).await.unwrap();
+        redis.set("a", b"2".to_vec(), &tagged(&["new"])).await.unwrap();
+        assert_eq!(redis.get_by_tag("old").await.unwrap(), vec!["b".to_string()]);
+        assert_eq!(redis.get_by_tag("new").await.unwrap(), vec!["a".to_string()]);
+
+        assert_eq!(redis.delete_many(&["a", "b", "missing"]).await.unwrap(), 2);
+        assert!(redis.get_by_tag("old").await.unwrap().is_empty());
+        assert!(redis.get_by_tag("new").await.unwrap().is_empty());
+        redis.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server (REDIS_URL)"]
+    async fn test_tag_and_pattern_deletes_prune_indexes() {
+        let redis = backend().await;
+        let depends_on_org = CacheOptions {
+            dependencies: vec!["org".to_string()],
+            ..tagged(&["users", "active"])
+        };
+
+        redis.set("user:1", b"1".to_vec(), &depends_on_org).await.unwrap();
+        redis.set("user:2", b"1".to_vec(), &tagged(&["users", "active"])).await.unwrap();
+        redis.set("post:1", b"1".to_vec(), &tagged(&["active"])).await.unwrap();
+
+        // Only deleted entries are counted, not members whose entry is gone
+        let mut conn = redis.get_connection().await.unwrap();
+        let _: () = conn.del(redis.layout.data_key("user:2")).await.unwrap();
+        assert_eq!(redis.delete_by_tag("users").await.unwrap(), 1);
+        // `user:2` was deleted behind the backend's back: left to `sweep_indexes`
+        let mut active = redis.get_by_tag("active").await.unwrap();
+        active.sort();
+        assert_eq!(active, ["post:1", "user:2"]);
+        assert!(redis.get_dependents("org").await.unwrap().is_empty());
+
+        redis.set("user:3", b"1".to_vec(), &depends_on_org).await.unwrap();
+        assert_eq!(redis.delete_by_pattern("user:*").await.unwrap(), 1);
+        assert!(redis.get_dependents("org").await.unwrap().is_empty());
+        assert!(!redis.get_by_tag("active").await.unwrap().contains(&"user:3".to_string()));
+        assert!(!redis.get_by_tag("users").await.unwrap().contains(&"user:3".to_string()));
+        redis.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server (REDIS_URL)"]
+    async fn test_versions_are_assigned_by_redis() {
+        let redis = backend().await;
+        let opts = CacheOptions::default();
+
+        redis.set("key", b"1".to_vec(), &opts).await.unwrap();
+        redis.set("key", b"2".to_vec(), &opts).await.unwrap();
+        assert_eq!(redis.get("key").await.unwrap().unwrap().version, 2);
+
+        let cas = |version| CacheOptions {
+            if_version: Some(version),
+            ..Default::default()
+        };
+        redis.set("key", b"3".to_vec(), &cas(2)).await.unwrap();
+        assert!(matches!(
+            redis.set("key", b"4".to_vec(), &cas(2)).await,
+            Err(CacheError::VersionMismatch { expected: 2, actual: 3 })
+        ));
+        redis.set("new", b"1".to_vec(), &cas(0)).await.unwrap();
+
+        // Unreadable values are overwritten, sub-second TTLs are kept
+        let mut conn = redis.get_connection().await.unwrap();
+        let _: () = conn.set(redis.layout.data_key("key"), "garbage").await.unwrap();
+        let short = CacheOptions {
+            ttl: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+        redis.set("key", b"5".to_vec(), &short).await.unwrap();
+        assert_eq!(redis.get("key").await.unwrap().unwrap().version, 1);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(redis.get("key").await.unwrap().is_none());
+        redis.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server (REDIS_URL)"]
+    async fn test_delete_cascade_removes_index_entries() {
+        let redis = backend().await;
+        let depends_on = |dep: &str| CacheOptions {
+            dependencies: vec![dep.to_string()],
+            ..tagged(&["users"])
+        };
+
+        redis.set("org", b"1".to_vec(), &tagged(&["orgs"])).await.unwrap();
+        redis.set("user", b"1".to_vec(), &depends_on("org")).await.unwrap();
+        redis.set("post", b"1".to_vec(), &depends_on("user")).await.unwrap();
+        redis.set("other", b"1".to_vec(), &tagged(&["users"])).await.unwrap();
+
+        let mut deleted = redis.delete_cascade("org").await.unwrap();
+        deleted.sort();
+        assert_eq!(deleted, ["org", "post", "user"]);
+        assert!(redis.get_by_tag("orgs").await.unwrap().is_empty());
+        assert_eq!(redis.get_by_tag("users").await.unwrap(), vec!["other".to_string()]);
+
+        let mut conn = redis.get_connection().await.unwrap();
+        let shard = redis.layout.shard_of("org");
+        for dep in ["org", "user"] {
+            let exists: bool = conn.exists(redis.layout.dep_key(shard, dep)).await.unwrap();
+            assert!(!exists, "{}", dep);
+        }
+        redis.clear().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server (REDIS_URL)"]
+    async fn test_sweep_removes_expired_members() {
+        let redis = backend().await;
+        let expiring = CacheOptions {
+            ttl: Some(Duration::from_millis(50)),
+            ..tagged(&["users"])
+        };
+
+        redis.set("gone", b"1".to_vec(), &expiring).await.unwrap();
+        redis.set("kept", b"1".to_vec(), &tagged(&["users"])).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(redis.sweep_indexes().await.unwrap(), 1);
+        assert_eq!(redis.get_by_tag("users").await.unwrap(), vec!["kept".to_string()]);
+        assert_eq!(redis.sweep_indexes().await.unwrap(), 0);
+        redis.clear().await.unwrap();
+    }
+
+    /// Nodes of the cluster at `REDIS_CLUSTER_NODES` (comma-separated URLs)
+    #[cfg(feature = "redis-cluster")]
+    fn cluster_nodes() -> Vec<String> {
+        std::env::var("REDIS_CLUSTER_NODES")
+            .expect("REDIS_CLUSTER_NODES")
+            .split(',')
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Calls of `command` counted by each node's `INFO commandstats`, summed
+    #[cfg(feature = "redis-cluster")]
+    async fn command_calls(nodes: &[String], command: &str) -> u64 {
+        let field = format!("cmdstat_{}:calls=", command);
+        let mut calls = 0;
+        for node in nodes {
+            let mut conn = redis::Client::open(node.as_str()).unwrap()
+                .get_multiplexed_async_connection().await.unwrap();
+            let info: String = redis::cmd("INFO").arg("commandstats").query_async(&mut conn).await.unwrap();
+            calls += info
+                .lines()
+                .filter_map(|line| line.strip_prefix(&field))
+                .filter_map(|rest| rest.split(',').next()?.parse::<u64>().ok())
+                .sum::<u64>();
+        }
+        calls
+    }
+
+    #[cfg(feature = "redis-cluster")]
+    #[tokio::test]
+    #[ignore = "requires a Redis Cluster (REDIS_CLUSTER_NODES)"]
+    async fn test_sharded_commands_per_set() {
+        let nodes = cluster_nodes();
+        let prefix = format!("skp-test-{:016x}", rand::random::<u64>());
+        let config = RedisConfig::default().cluster(nodes.clone()).prefix(prefix);
+        let shards = u64::from(config.cluster_shards);
+        let redis = RedisBackend::new(config).await.unwrap();
+        redis.set("warm", b"1".to_vec(), &tagged(&["users"])).await.unwrap();
+
+        // A `CacheManager` write: the key's dependents, then the write script
+        let smembers = command_calls(&nodes, "smembers").await;
+        let scripts = command_calls(&nodes, "evalsha").await + command_calls(&nodes, "eval").await;
+        redis.get_dependents("key").await.unwrap();
+        redis.set("key", b"1".to_vec(), &tagged(&["users"])).await.unwrap();
+        assert_eq!(command_calls(&nodes, "smembers").await - smembers, shards);
+        let scripts_after = command_calls(&nodes, "evalsha").await + command_calls(&nodes, "eval").await;
+        assert_eq!(scripts_after - scripts, 1);
+
+        // Scans run once per node, not once per shard
+        let scans = command_calls(&nodes, "scan").await;
+        assert!(redis.len().await.unwrap() >= 2);
+        assert!(command_calls(&nodes, "scan").await - scans <= nodes.len() as u64);
+        redis.clear().await.unwrap();
     }
 }
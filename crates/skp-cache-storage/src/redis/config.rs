@@ -2,6 +2,8 @@
 
 use std::time::Duration;
 
+use super::{EntryEncoding, TrackingMode};
+
 /// Configuration for Redis backend connection and behavior
 #[derive(Debug, Clone)]
 pub struct RedisConfig {
@@ -19,6 +21,37 @@ pub struct RedisConfig {
     
     /// Channel name for invalidation pub/sub
     pub invalidation_channel: String,
+
+    /// How entries are written (both encodings are always readable)
+    pub entry_encoding: EntryEncoding,
+
+    /// Cluster node URLs; when set, `url` and `pool_size` are unused
+    pub cluster_nodes: Vec<String>,
+
+    /// Hash tags keys are spread over in cluster mode (default 16)
+    ///
+    /// See `RedisConfig::cluster_shards` for the trade-off.
+    pub cluster_shards: u16,
+
+    /// Sentinel URLs; when set, the primary is discovered through Sentinel and
+    /// `url` only supplies the database and credentials of the data nodes
+    pub sentinel_nodes: Vec<String>,
+
+    /// Name of the master monitored by Sentinel
+    pub sentinel_master: String,
+
+    /// Send `get`, `get_many` and `exists` to replicas (Sentinel or cluster mode)
+    pub read_from_replicas: bool,
+
+    /// Remove expired entries from tag and dependency sets in a background
+    /// task every `index_sweep_interval`
+    pub background_index_sweep: bool,
+
+    /// Interval of the background index sweep
+    pub index_sweep_interval: Duration,
+
+    /// Receive RESP3 invalidation pushes for cached keys (single node or Sentinel)
+    pub client_tracking: Option<TrackingMode>,
 }
 
 impl Default for RedisConfig {
@@ -29,6 +62,15 @@ impl Default for RedisConfig {
             connection_timeout: Duration::from_secs(5),
             key_prefix: Some("skp".to_string()),
             invalidation_channel: "skp:invalidation".to_string(),
+            entry_encoding: EntryEncoding::default(),
+            cluster_nodes: Vec::new(),
+            cluster_shards: 16,
+            sentinel_nodes: Vec::new(),
+            sentinel_master: "mymaster".to_string(),
+            read_from_replicas: false,
+            background_index_sweep: false,
+            index_sweep_interval: Duration::from_secs(300),
+            client_tracking: None,
         }
     }
 }
@@ -53,4 +95,95 @@ impl RedisConfig {
         self.key_prefix = Some(prefix.into());
         self
     }
+
+    /// Set the channel used for invalidation pub/sub
+    pub fn invalidation_channel(mut self, channel: impl Into<String>) -> Self {
+        self.invalidation_channel = channel.into();
+        self
+    }
+
+    /// Set the entry encoding
+    ///
+    /// Only switch to `EntryEncoding::Binary` once no instance running an
+    /// older release, which only reads JSON, shares the same Redis.
+    pub fn entry_encoding(mut self, encoding: EntryEncoding) -> Self {
+        self.entry_encoding = encoding;
+        self
+    }
+
+    /// Connect to a Redis Cluster through the given seed nodes
+    ///
+    /// Requires the `redis-cluster` feature.
+    pub fn cluster<I, S>(mut self, nodes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.cluster_nodes = nodes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the number of hash tags keys are spread over in cluster mode
+    ///
+    /// Each shard maps to one slot, so this bounds how many nodes share the
+    /// load. Tag and dependency lookups and invalidations (`get_by_tag`,
+    /// `delete_by_tag`, `get_dependents`, `delete_cascade`, and so every
+    /// `CacheManager` write) send one command per shard, concurrently; scans
+    /// run once per node whatever the count. Raise it for clusters of more
+    /// than a few primaries. Changing it moves every key, so it must match
+    /// across instances.
+    pub fn cluster_shards(mut self, shards: u16) -> Self {
+        self.cluster_shards = shards;
+        self
+    }
+
+    /// Discover the primary of `master` through the given Sentinels
+    ///
+    /// Connections follow the primary across failovers. Requires the
+    /// `redis-sentinel` feature.
+    pub fn sentinel<I, S>(mut self, master: impl Into<String>, nodes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sentinel_master = master.into();
+        self.sentinel_nodes = nodes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Send reads (`get`, `get_many`, `exists`) to replicas
+    ///
+    /// Replication is asynchronous, so a read right after a write may miss it
+    /// or return the previous value. Writes, tags and locks always use the primary.
+    pub fn read_from_replicas(mut self, enabled: bool) -> Self {
+        self.read_from_replicas = enabled;
+        self
+    }
+
+    /// Enable or disable the background index sweep (off by default)
+    ///
+    /// Entries expiring through their TTL stay listed in their tag and
+    /// dependency sets until swept. Each sweep SCANs the whole keyspace for
+    /// these sets and checks every member, so enable it on one instance per
+    /// Redis, or call `RedisBackend::sweep_indexes` yourself.
+    pub fn background_index_sweep(mut self, enabled: bool) -> Self {
+        self.background_index_sweep = enabled;
+        self
+    }
+
+    /// Set the interval of the background index sweep
+    pub fn index_sweep_interval(mut self, interval: Duration) -> Self {
+        self.index_sweep_interval = interval;
+        self
+    }
+
+    /// Enable client-side caching with `CLIENT TRACKING` (Redis 6+)
+    ///
+    /// Changed keys are delivered through `RedisBackend::invalidation_events`,
+    /// so `MultiTierBackend::with_client_tracking` keeps its L1 coherent
+    /// without pub/sub. Not available in cluster mode.
+    pub fn client_tracking(mut self, mode: TrackingMode) -> Self {
+        self.client_tracking = Some(mode);
+        self
+    }
 }
@@ -0,0 +1,396 @@
+//! Connections to a single Redis node, a Sentinel-managed primary and its
+//! replicas, or a Redis Cluster
+
+use bb8::{ManageConnection, Pool, PooledConnection};
+use bb8_redis::RedisConnectionManager;
+use redis::aio::{ConnectionLike, MultiplexedConnection};
+use redis::{Cmd, Pipeline, RedisError, RedisFuture, Value};
+use skp_cache_core::{CacheError, CacheMetrics, Result};
+use std::sync::Arc;
+
+#[cfg(feature = "redis-cluster")]
+use redis::cluster_async::ClusterConnection;
+#[cfg(feature = "redis-cluster")]
+use redis::cluster_routing::{Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr};
+
+use super::config::RedisConfig;
+#[cfg(feature = "redis-sentinel")]
+use super::sentinel::{Role, SentinelManager};
+
+/// Keys requested per SCAN call
+const SCAN_COUNT: usize = 1000;
+
+/// Opens the pooled connections to one node
+pub(crate) enum NodeManager {
+    /// Node at a fixed address
+    Fixed(RedisConnectionManager),
+    /// Primary or replica found through Sentinel
+    #[cfg(feature = "redis-sentinel")]
+    Sentinel(Arc<SentinelManager>),
+}
+
+impl ManageConnection for NodeManager {
+    type Connection = MultiplexedConnection;
+    type Error = RedisError;
+
+    async fn connect(&self) -> std::result::Result<MultiplexedConnection, RedisError> {
+        match self {
+            NodeManager::Fixed(manager) => manager.connect().await,
+            #[cfg(feature = "redis-sentinel")]
+            NodeManager::Sentinel(manager) => manager.connect().await,
+        }
+    }
+
+    async fn is_valid(
+        &self,
+        conn: &mut MultiplexedConnection,
+    ) -> std::result::Result<(), RedisError> {
+        match self {
+            NodeManager::Fixed(manager) => manager.is_valid(conn).await,
+            #[cfg(feature = "redis-sentinel")]
+            NodeManager::Sentinel(manager) => manager.is_valid(conn).await,
+        }
+    }
+
+    fn has_broken(&self, conn: &mut MultiplexedConnection) -> bool {
+        match self {
+            NodeManager::Fixed(manager) => manager.has_broken(conn),
+            #[cfg(feature = "redis-sentinel")]
+            NodeManager::Sentinel(manager) => manager.has_broken(conn),
+        }
+    }
+}
+
+/// Source of connections
+#[derive(Clone)]
+pub(crate) enum Connector {
+    /// Pool of connections to the primary, and to replicas if reads go there
+    Pool {
+        primary: Pool<NodeManager>,
+        replicas: Option<Pool<NodeManager>>,
+        /// Resolves the primary in Sentinel mode
+        #[cfg(feature = "redis-sentinel")]
+        sentinel: Option<Arc<SentinelManager>>,
+    },
+    /// Multiplexed connection to every node of a cluster
+    #[cfg(feature = "redis-cluster")]
+    Cluster(ClusterConnection),
+}
+
+impl Connector {
+    /// Connect as configured, reporting failovers to `metrics`
+    pub(crate) async fn connect(
+        config: &RedisConfig,
+        metrics: Arc<dyn CacheMetrics>,
+    ) -> Result<Self> {
+        if !config.cluster_nodes.is_empty() {
+            return Self::connect_cluster(config).await;
+        }
+        if !config.sentinel_nodes.is_empty() {
+            return Self::connect_sentinel(config, metrics).await;
+        }
+        if config.read_from_replicas {
+            return Err(CacheError::Connection(
+                "reading from replicas requires Sentinel or cluster mode".into(),
+            ));
+        }
+
+        let manager = RedisConnectionManager::new(config.url.as_str())
+            .map_err(|e| CacheError::Connection(e.to_string()))?;
+        let primary = build_pool(config, NodeManager::Fixed(manager)).await?;
+        Ok(Connector::Pool {
+            primary,
+            replicas: None,
+            #[cfg(feature = "redis-sentinel")]
+            sentinel: None,
+        })
+    }
+
+    #[cfg(feature = "redis-sentinel")]
+    async fn connect_sentinel(
+        config: &RedisConfig,
+        metrics: Arc<dyn CacheMetrics>,
+    ) -> Result<Self> {
+        let sentinel = Arc::new(SentinelManager::new(
+            config,
+            Role::Primary,
+            metrics.clone(),
+        )?);
+        let primary = build_pool(config, NodeManager::Sentinel(sentinel.clone())).await?;
+        let replicas = if config.read_from_replicas {
+            let manager = SentinelManager::new(config, Role::Replica, metrics)?;
+            Some(build_pool(config, NodeManager::Sentinel(Arc::new(manager))).await?)
+        } else {
+            None
+        };
+        Ok(Connector::Pool {
+            primary,
+            replicas,
+            sentinel: Some(sentinel),
+        })
+    }
+
+    #[cfg(not(feature = "redis-sentinel"))]
+    async fn connect_sentinel(
+        _config: &RedisConfig,
+        _metrics: Arc<dyn CacheMetrics>,
+    ) -> Result<Self> {
+        Err(CacheError::Connection(
+            "sentinel nodes configured but the redis-sentinel feature is disabled".into(),
+        ))
+    }
+
+    #[cfg(feature = "redis-cluster")]
+    async fn connect_cluster(config: &RedisConfig) -> Result<Self> {
+        let mut builder = redis::cluster::ClusterClient::builder(config.cluster_nodes.clone())
+            .connection_timeout(config.connection_timeout);
+        if config.read_from_replicas {
+            builder = builder.read_from_replicas();
+        }
+        let client = builder
+            .build()
+            .map_err(|e| CacheError::Connection(e.to_string()))?;
+        let conn = client
+            .get_async_connection()
+            .await
+            .map_err(|e| CacheError::Connection(e.to_string()))?;
+        Ok(Connector::Cluster(conn))
+    }
+
+    #[cfg(not(feature = "redis-cluster"))]
+    async fn connect_cluster(_config: &RedisConfig) -> Result<Self> {
+        Err(CacheError::Connection(
+            "cluster nodes configured but the redis-cluster feature is disabled".into(),
+        ))
+    }
+
+    /// Get a connection to the primary
+    pub(crate) async fn get(&self) -> Result<Connection<'_>> {
+        match self {
+            Connector::Pool { primary, .. } => primary
+                .get()
+                .await
+                .map(Connection::Pooled)
+                .map_err(|e| CacheError::Connection(e.to_string())),
+            #[cfg(feature = "redis-cluster")]
+            Connector::Cluster(conn) => Ok(Connection::Cluster(conn.clone())),
+        }
+    }
+
+    /// Get a connection for reads
+    ///
+    /// Goes to a replica if configured, falling back to the primary when no
+    /// replica is reachable. A cluster connection routes reads itself.
+    pub(crate) async fn get_for_read(&self) -> Result<Connection<'_>> {
+        if let Connector::Pool {
+            replicas: Some(replicas),
+            ..
+        } = self
+        {
+            if let Ok(conn) = replicas.get().await {
+                return Ok(Connection::Pooled(conn));
+            }
+        }
+        self.get().await
+    }
+
+    /// Client for the current primary, when it is discovered through Sentinel
+    pub(crate) async fn discovered_primary(&self) -> Result<Option<redis::Client>> {
+        match self {
+            #[cfg(feature = "redis-sentinel")]
+            Connector::Pool {
+                sentinel: Some(sentinel),
+                ..
+            } => sentinel
+                .client()
+                .await
+                .map(Some)
+                .map_err(|e| CacheError::Connection(e.to_string())),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Build a connection pool
+async fn build_pool(config: &RedisConfig, manager: NodeManager) -> Result<Pool<NodeManager>> {
+    Pool::builder()
+        .max_size(config.pool_size)
+        .connection_timeout(config.connection_timeout)
+        .build(manager)
+        .await
+        .map_err(|e| CacheError::Connection(e.to_string()))
+}
+
+/// Connection used for one backend operation
+pub(crate) enum Connection<'a> {
+    Pooled(PooledConnection<'a, NodeManager>),
+    /// The tracking connection, for reads whose keys Redis must track
+    Tracked(MultiplexedConnection),
+    #[cfg(feature = "redis-cluster")]
+    Cluster(ClusterConnection),
+}
+
+impl ConnectionLike for Connection<'_> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            Connection::Pooled(conn) => conn.req_packed_command(cmd),
+            Connection::Tracked(conn) => conn.req_packed_command(cmd),
+            #[cfg(feature = "redis-cluster")]
+            Connection::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            Connection::Pooled(conn) => conn.req_packed_commands(cmd, offset, count),
+            Connection::Tracked(conn) => conn.req_packed_commands(cmd, offset, count),
+            #[cfg(feature = "redis-cluster")]
+            Connection::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Connection::Pooled(conn) => conn.get_db(),
+            Connection::Tracked(conn) => conn.get_db(),
+            #[cfg(feature = "redis-cluster")]
+            Connection::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
+
+/// Incremental SCAN over the keys matching a pattern
+///
+/// In a cluster, SCAN only covers the node it is sent to, so it is routed to
+/// the node serving `slot`.
+pub(crate) struct Scan {
+    pattern: String,
+    slot: Option<u16>,
+    cursor: u64,
+    done: bool,
+}
+
+impl Scan {
+    /// Scan for `pattern` on the node serving `slot` (any node if `None`)
+    pub(crate) fn new(pattern: String, slot: Option<u16>) -> Self {
+        Self {
+            pattern,
+            slot,
+            cursor: 0,
+            done: false,
+        }
+    }
+
+    /// Scans for `pattern` covering the keys of `slots`, one per node
+    ///
+    /// In a cluster, nodes serving several of the slots are scanned once,
+    /// through the first slot they serve; elsewhere a single scan covers all.
+    #[cfg_attr(not(feature = "redis-cluster"), allow(unused_variables))]
+    pub(crate) async fn per_node(
+        conn: &mut Connection<'_>,
+        pattern: &str,
+        slots: &[Option<u16>],
+    ) -> Result<Vec<Self>> {
+        let nodes = match conn {
+            #[cfg(feature = "redis-cluster")]
+            Connection::Cluster(conn) => {
+                let slots: Vec<u16> = slots.iter().flatten().copied().collect();
+                primary_slots(conn, &slots).await?.into_iter().map(Some).collect()
+            }
+            _ => vec![None],
+        };
+        Ok(nodes
+            .into_iter()
+            .map(|slot| Self::new(pattern.to_string(), slot))
+            .collect())
+    }
+
+    /// Get the next batch of keys, or `None` once the scan is complete
+    pub(crate) async fn next_batch(
+        &mut self,
+        conn: &mut Connection<'_>,
+    ) -> Result<Option<Vec<String>>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let mut cmd = redis::cmd("SCAN");
+        cmd.cursor_arg(self.cursor)
+            .arg("MATCH")
+            .arg(&self.pattern)
+            .arg("COUNT")
+            .arg(SCAN_COUNT);
+
+        let (cursor, keys): (u64, Vec<String>) = match (conn, self.slot) {
+            #[cfg(feature = "redis-cluster")]
+            (Connection::Cluster(conn), Some(slot)) => {
+                let route = Route::new(slot, SlotAddr::Master);
+                let value = conn
+                    .route_command(
+                        cmd,
+                        RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(route)),
+                    )
+                    .await
+                    .map_err(|e| CacheError::Backend(e.to_string()))?;
+                redis::from_redis_value(value).map_err(|e| CacheError::Backend(e.to_string()))?
+            }
+            (conn, _) => cmd
+                .query_async(conn)
+                .await
+                .map_err(|e| CacheError::Backend(e.to_string()))?,
+        };
+
+        self.cursor = cursor;
+        self.done = cursor == 0;
+        Ok(Some(keys))
+    }
+}
+
+/// The first of `slots` served by each primary of the cluster
+///
+/// Slots missing from `CLUSTER SLOTS`, e.g. while being migrated, are kept.
+#[cfg(feature = "redis-cluster")]
+async fn primary_slots(conn: &mut ClusterConnection, slots: &[u16]) -> Result<Vec<u16>> {
+    let ranges: Vec<Value> = redis::cmd("CLUSTER")
+        .arg("SLOTS")
+        .query_async(conn)
+        .await
+        .map_err(|e| CacheError::Backend(e.to_string()))?;
+
+    // (first slot, last slot, primary address) of each range
+    let ranges: Vec<(i64, i64, &[Value])> = ranges
+        .iter()
+        .filter_map(|range| match range {
+            Value::Array(parts) => match parts.as_slice() {
+                [Value::Int(start), Value::Int(end), Value::Array(primary), ..] => {
+                    Some((*start, *end, primary.get(..2)?))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    let mut seen = Vec::new();
+    let mut first_slots = Vec::new();
+    for &slot in slots {
+        let primary = ranges
+            .iter()
+            .find(|(start, end, _)| (*start..=*end).contains(&i64::from(slot)))
+            .map(|(_, _, primary)| *primary);
+        match primary {
+            Some(primary) if seen.contains(&primary) => {}
+            Some(primary) => {
+                seen.push(primary);
+                first_slots.push(slot);
+            }
+            None => first_slots.push(slot),
+        }
+    }
+    Ok(first_slots)
+}
@@ -0,0 +1,401 @@
+//! Wire format of entries stored in Redis
+//!
+//! Binary layout (integers big endian, durations and timestamps in nanoseconds):
+//!
+//! ```text
+//! magic (2) | format version (1) | flags (1)
+//! | created_at (8) | last_accessed (8) | access_count (8) | ttl (8)
+//! | stale_while_revalidate (8) | cost (8) | size (8) | version (8)
+//! | tag count (4) | (length (4) | tag)* | dependency count (4) | (length (4) | dependency)*
+//! | etag length (4) | etag (if FLAG_ETAG)
+//! | compute time (8, if FLAG_COMPUTE_TIME)
+//! | value (remaining bytes)
+//! ```
+//!
+//! Entries written by earlier releases are JSON-encoded `CacheEntry`s and are
+//! still decoded.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use skp_cache_core::{CacheEntry, CacheError, Result};
+
+/// Marks a binary entry; JSON entries start with `{`
+const MAGIC: [u8; 2] = [0x00, 0xE5];
+
+/// Current binary layout
+const FORMAT_VERSION: u8 = 1;
+
+/// Entry is negative
+const FLAG_NEGATIVE: u8 = 0b0000_0001;
+/// `ttl` is set
+const FLAG_TTL: u8 = 0b0000_0010;
+/// `stale_while_revalidate` is set
+const FLAG_SWR: u8 = 0b0000_0100;
+/// `etag` is set
+const FLAG_ETAG: u8 = 0b0000_1000;
+/// `compute_time` is set
+const FLAG_COMPUTE_TIME: u8 = 0b0001_0000;
+
+/// Size of the fixed part of the header
+const FIXED_LEN: usize = 4 + 8 * 8;
+
+/// Offset of the version, the last field of the fixed header
+const VERSION_OFFSET: usize = FIXED_LEN - 8;
+
+/// Version field of a JSON entry written with version 0
+const JSON_VERSION: &[u8] = b"\"version\":0";
+
+/// How `RedisBackend` writes entries
+///
+/// Both encodings are always read. JSON stays the default so instances
+/// running earlier releases, which only read JSON, can share the same Redis
+/// during a rolling upgrade; opt into `Binary` once all of them read it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EntryEncoding {
+    /// JSON-encoded `CacheEntry`, readable by releases before the binary layout
+    #[default]
+    Json,
+    /// Compact binary layout with the value stored as raw bytes
+    Binary,
+}
+
+impl EntryEncoding {
+    /// Encode an entry for storage
+    pub(crate) fn encode(self, entry: &CacheEntry<Vec<u8>>) -> Result<Vec<u8>> {
+        match self {
+            EntryEncoding::Binary => encode_binary(entry),
+            EntryEncoding::Json => {
+                serde_json::to_vec(entry).map_err(|e| CacheError::Serialization(e.to_string()))
+            }
+        }
+    }
+
+    /// Encode an entry without its version, as the bytes before and after it
+    ///
+    /// The write script puts the version in between: 8 bytes big endian in
+    /// the binary layout, decimal digits in JSON.
+    pub(crate) fn encode_around_version(
+        self,
+        entry: &CacheEntry<Vec<u8>>,
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let entry = CacheEntry {
+            version: 0,
+            ..entry.clone()
+        };
+        let mut encoded = self.encode(&entry)?;
+        let (start, end) = match self {
+            EntryEncoding::Binary => (VERSION_OFFSET, VERSION_OFFSET + 8),
+            EntryEncoding::Json => {
+                // Quotes inside JSON strings are escaped, so this only matches the field
+                let field = encoded
+                    .windows(JSON_VERSION.len())
+                    .position(|window| window == JSON_VERSION)
+                    .ok_or_else(|| CacheError::Serialization("entry has no version".into()))?;
+                (field + JSON_VERSION.len() - 1, field + JSON_VERSION.len())
+            }
+        };
+        let tail = encoded.split_off(end);
+        encoded.truncate(start);
+        Ok((encoded, tail))
+    }
+}
+
+/// Decode a stored entry in either encoding
+pub(crate) fn decode_entry(data: &[u8]) -> Result<CacheEntry<Vec<u8>>> {
+    if data.starts_with(&MAGIC) {
+        decode_binary(data)
+    } else {
+        serde_json::from_slice(data).map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+}
+
+fn encode_binary(entry: &CacheEntry<Vec<u8>>) -> Result<Vec<u8>> {
+    let mut flags = 0;
+    if entry.negative {
+        flags |= FLAG_NEGATIVE;
+    }
+    if entry.ttl.is_some() {
+        flags |= FLAG_TTL;
+    }
+    if entry.stale_while_revalidate.is_some() {
+        flags |= FLAG_SWR;
+    }
+    if entry.etag.is_some() {
+        flags |= FLAG_ETAG;
+    }
+    if entry.compute_time.is_some() {
+        flags |= FLAG_COMPUTE_TIME;
+    }
+
+    let strings_len: usize = entry
+        .tags
+        .iter()
+        .chain(&entry.dependencies)
+        .chain(&entry.etag)
+        .map(|s| 4 + s.len())
+        .sum();
+    let mut out = Vec::with_capacity(FIXED_LEN + 16 + strings_len + entry.value.len());
+
+    out.extend_from_slice(&MAGIC);
+    out.push(FORMAT_VERSION);
+    out.push(flags);
+    out.extend_from_slice(&timestamp_nanos(entry.created_at).to_be_bytes());
+    out.extend_from_slice(&timestamp_nanos(entry.last_accessed).to_be_bytes());
+    out.extend_from_slice(&entry.access_count.to_be_bytes());
+    out.extend_from_slice(&duration_nanos(entry.ttl).to_be_bytes());
+    out.extend_from_slice(&duration_nanos(entry.stale_while_revalidate).to_be_bytes());
+    out.extend_from_slice(&entry.cost.to_be_bytes());
+    out.extend_from_slice(&(entry.size as u64).to_be_bytes());
+    out.extend_from_slice(&entry.version.to_be_bytes());
+
+    write_strings(&mut out, &entry.tags)?;
+    write_strings(&mut out, &entry.dependencies)?;
+    if let Some(etag) = &entry.etag {
+        write_bytes(&mut out, etag.as_bytes())?;
+    }
+    if entry.compute_time.is_some() {
+        out.extend_from_slice(&duration_nanos(entry.compute_time).to_be_bytes());
+    }
+
+    out.extend_from_slice(&entry.value);
+    Ok(out)
+}
+
+fn decode_binary(data: &[u8]) -> Result<CacheEntry<Vec<u8>>> {
+    let mut reader = Reader {
+        data,
+        pos: MAGIC.len(),
+    };
+
+    let version = reader.u8()?;
+    if version != FORMAT_VERSION {
+        return Err(CacheError::Deserialization(format!(
+            "unsupported entry format version {}",
+            version
+        )));
+    }
+    let flags = reader.u8()?;
+
+    let created_at = UNIX_EPOCH + Duration::from_nanos(reader.u64()?);
+    let last_accessed = UNIX_EPOCH + Duration::from_nanos(reader.u64()?);
+    let access_count = reader.u64()?;
+    let ttl = Duration::from_nanos(reader.u64()?);
+    let swr = Duration::from_nanos(reader.u64()?);
+    let cost = reader.u64()?;
+    let size = reader.u64()? as usize;
+    let version = reader.u64()?;
+
+    let tags = reader.strings()?;
+    let dependencies = reader.strings()?;
+    let etag = if flags & FLAG_ETAG != 0 {
+        Some(reader.string()?)
+    } else {
+        None
+    };
+    let compute_time = if flags & FLAG_COMPUTE_TIME != 0 {
+        Some(Duration::from_nanos(reader.u64()?))
+    } else {
+        None
+    };
+
+    Ok(CacheEntry {
+        value: reader.rest().to_vec(),
+        created_at,
+        last_accessed,
+        access_count,
+        ttl: (flags & FLAG_TTL != 0).then_some(ttl),
+        stale_while_revalidate: (flags & FLAG_SWR != 0).then_some(swr),
+        tags,
+        dependencies,
+        cost,
+        compute_time,
+        size,
+        etag,
+        version,
+        negative: flags & FLAG_NEGATIVE != 0,
+    })
+}
+
+/// Nanoseconds since the Unix epoch (0 for earlier times)
+fn timestamp_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+fn duration_nanos(duration: Option<Duration>) -> u64 {
+    duration.map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> Result<()> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| CacheError::Serialization("entry field too long".into()))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn write_strings(out: &mut Vec<u8>, strings: &[String]) -> Result<()> {
+    let count = u32::try_from(strings.len())
+        .map_err(|_| CacheError::Serialization("too many entry fields".into()))?;
+    out.extend_from_slice(&count.to_be_bytes());
+    for s in strings {
+        write_bytes(out, s.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Cursor over a binary entry
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.data.len());
+        let Some(end) = end else {
+            return Err(CacheError::Deserialization("truncated entry".into()));
+        };
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>> {
+        let count = self.u32()? as usize;
+        // Each string needs at least its length prefix
+        let mut strings = Vec::with_capacity(count.min(self.data.len() / 4));
+        for _ in 0..count {
+            strings.push(self.string()?);
+        }
+        Ok(strings)
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> CacheEntry<Vec<u8>> {
+        let mut entry = CacheEntry::new(vec![0, 1, 2, 255], 4);
+        entry.ttl = Some(Duration::from_millis(1500));
+        entry.stale_while_revalidate = Some(Duration::from_secs(30));
+        entry.tags = vec!["users".to_string(), "team:1".to_string()];
+        entry.dependencies = vec!["org:1".to_string()];
+        entry.cost = 42;
+        entry.compute_time = Some(Duration::from_micros(2500));
+        entry.etag = Some("\"abc\"".to_string());
+        entry.version = 7;
+        entry
+    }
+
+    fn assert_same(a: &CacheEntry<Vec<u8>>, b: &CacheEntry<Vec<u8>>) {
+        assert_eq!(a.value, b.value);
+        assert_eq!(a.created_at, b.created_at);
+        assert_eq!(a.last_accessed, b.last_accessed);
+        assert_eq!(a.access_count, b.access_count);
+        assert_eq!(a.ttl, b.ttl);
+        assert_eq!(a.stale_while_revalidate, b.stale_while_revalidate);
+        assert_eq!(a.tags, b.tags);
+        assert_eq!(a.dependencies, b.dependencies);
+        assert_eq!(a.cost, b.cost);
+        assert_eq!(a.compute_time, b.compute_time);
+        assert_eq!(a.size, b.size);
+        assert_eq!(a.etag, b.etag);
+        assert_eq!(a.version, b.version);
+        assert_eq!(a.negative, b.negative);
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let entry = entry();
+        let encoded = EntryEncoding::Binary.encode(&entry).unwrap();
+        assert_same(&decode_entry(&encoded).unwrap(), &entry);
+
+        let mut negative = CacheEntry::new(Vec::new(), 0);
+        negative.negative = true;
+        let encoded = EntryEncoding::Binary.encode(&negative).unwrap();
+        let decoded = decode_entry(&encoded).unwrap();
+        assert!(decoded.negative);
+        assert_eq!(decoded.ttl, None);
+        assert_eq!(decoded.etag, None);
+        assert_eq!(decoded.compute_time, None);
+    }
+
+    #[test]
+    fn test_binary_stores_raw_value() {
+        let mut entry = entry();
+        entry.value = vec![7; 1000];
+        let binary = EntryEncoding::Binary.encode(&entry).unwrap();
+        let json = EntryEncoding::Json.encode(&entry).unwrap();
+
+        assert!(binary.ends_with(&entry.value));
+        assert!(binary.len() < json.len() / 2);
+    }
+
+    #[test]
+    fn test_reads_json_entries() {
+        let entry = entry();
+        let json = serde_json::to_vec(&entry).unwrap();
+        assert_same(&decode_entry(&json).unwrap(), &entry);
+    }
+
+    #[test]
+    fn test_version_can_be_filled_in() {
+        let entry = entry();
+
+        let (head, tail) = EntryEncoding::Binary.encode_around_version(&entry).unwrap();
+        let encoded = [head, 7u64.to_be_bytes().to_vec(), tail].concat();
+        assert_same(&decode_entry(&encoded).unwrap(), &entry);
+
+        let (head, tail) = EntryEncoding::Json.encode_around_version(&entry).unwrap();
+        let encoded = [head, b"7".to_vec(), tail].concat();
+        assert_same(&decode_entry(&encoded).unwrap(), &entry);
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_unknown_versions() {
+        let encoded = EntryEncoding::Binary.encode(&entry()).unwrap();
+        assert!(matches!(
+            decode_entry(&encoded[..20]),
+            Err(CacheError::Deserialization(_))
+        ));
+
+        let mut future = encoded.clone();
+        future[2] = FORMAT_VERSION + 1;
+        assert!(matches!(
+            decode_entry(&future),
+            Err(CacheError::Deserialization(_))
+        ));
+    }
+}
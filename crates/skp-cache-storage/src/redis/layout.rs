@@ -0,0 +1,307 @@
+//! Redis key layout
+//!
+//! Single node (unchanged from earlier releases):
+//!
+//! ```text
+//! prefix:key    prefix:__tags__:tag    prefix:__deps__:dep    prefix:__locks__:key
+//! ```
+//!
+//! Cluster: keys are spread over a fixed number of shards, each with its own
+//! hash tag. An entry, the tag and dependency sets it is added to and its lock
+//! all live in the entry's shard, so one write touches a single slot:
+//!
+//! ```text
+//! {prefix:3}:key    {prefix:3}:__tags__:tag    {prefix:3}:__deps__:dep    {prefix:3}:__locks__:key
+//! ```
+//!
+//! Tag and dependency lookups read the set of every shard, so they send one
+//! command per shard (`RedisConfig::cluster_shards`). Scans match every shard
+//! with one pattern, `{prefix:*}:pattern`, and run once per node.
+
+use skp_cache_core::KeyPattern;
+
+/// Number of hash slots in a Redis Cluster
+const CLUSTER_SLOTS: u16 = 16384;
+
+/// Namespaces of the backend's own bookkeeping keys
+const INTERNAL_NAMESPACES: [&str; 3] = ["__tags__:", "__deps__:", "__locks__:"];
+
+/// A group of keys sharing a hash slot (the whole keyspace on a single node)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Shard(Option<u16>);
+
+/// Maps cache keys to Redis keys
+#[derive(Debug, Clone)]
+pub(crate) struct KeyLayout {
+    prefix: Option<String>,
+    /// Shard count in cluster mode
+    shards: Option<u16>,
+}
+
+impl KeyLayout {
+    /// Layout for a single node
+    pub(crate) fn single(prefix: Option<String>) -> Self {
+        Self {
+            prefix,
+            shards: None,
+        }
+    }
+
+    /// Layout spreading keys over `shards` hash tags
+    pub(crate) fn sharded(prefix: Option<String>, shards: u16) -> Self {
+        Self {
+            prefix,
+            shards: Some(shards.max(1)),
+        }
+    }
+
+    /// All shards
+    pub(crate) fn shards(&self) -> Vec<Shard> {
+        match self.shards {
+            Some(count) => (0..count).map(|n| Shard(Some(n))).collect(),
+            None => vec![Shard(None)],
+        }
+    }
+
+    /// Shard holding `key` and its index updates
+    pub(crate) fn shard_of(&self, key: &str) -> Shard {
+        Shard(self.shards.map(|count| crc16(key.as_bytes()) % count))
+    }
+
+    /// Cluster slot of a shard (`None` on a single node)
+    pub(crate) fn slot(&self, shard: Shard) -> Option<u16> {
+        shard.0.map(|_| key_slot(self.namespace(shard).as_bytes()))
+    }
+
+    /// Key of an entry
+    pub(crate) fn data_key(&self, key: &str) -> String {
+        format!("{}{}", self.namespace(self.shard_of(key)), key)
+    }
+
+    /// Set of the keys in `shard` tagged `tag`
+    pub(crate) fn tag_key(&self, shard: Shard, tag: &str) -> String {
+        format!("{}__tags__:{}", self.namespace(shard), tag)
+    }
+
+    /// Set of the keys in `shard` depending on `dep`
+    pub(crate) fn dep_key(&self, shard: Shard, dep: &str) -> String {
+        format!("{}__deps__:{}", self.namespace(shard), dep)
+    }
+
+    /// Key of the lock on `key`
+    pub(crate) fn lock_key(&self, key: &str) -> String {
+        format!("{}__locks__:{}", self.namespace(self.shard_of(key)), key)
+    }
+
+    /// Tag and dependency sets listing `key`
+    pub(crate) fn index_sets(
+        &self,
+        key: &str,
+        tags: &[String],
+        dependencies: &[String],
+    ) -> Vec<String> {
+        let shard = self.shard_of(key);
+        tags.iter()
+            .map(|tag| self.tag_key(shard, tag))
+            .chain(dependencies.iter().map(|dep| self.dep_key(shard, dep)))
+            .collect()
+    }
+
+    /// Slots of all shards (`[None]` on a single node)
+    pub(crate) fn slots(&self) -> Vec<Option<u16>> {
+        self.shards().into_iter().map(|shard| self.slot(shard)).collect()
+    }
+
+    /// SCAN MATCH pattern for keys of any shard matching `pattern`
+    ///
+    /// In a cluster the pattern may match keys of other layouts too: check
+    /// what a scan returns with `scanned_key`.
+    pub(crate) fn scan_pattern(&self, pattern: &str) -> String {
+        match (self.shards, &self.prefix) {
+            (Some(_), Some(prefix)) => format!("{{{}:*}}:{}", KeyPattern::escape(prefix), pattern),
+            (Some(_), None) => format!("{{*}}:{}", pattern),
+            (None, _) => format!("{}{}", KeyPattern::escape(&self.namespace(Shard(None))), pattern),
+        }
+    }
+
+    /// Key within its shard of a Redis key found by scanning for `pattern`
+    ///
+    /// `None` if the key does not belong to a shard of this layout or does
+    /// not match `pattern` once its namespace is removed.
+    pub(crate) fn scanned_key<'a>(&self, redis_key: &'a str, pattern: &KeyPattern) -> Option<&'a str> {
+        self.split(redis_key)
+            .map(|(_, key)| key)
+            .filter(|key| pattern.matches(key))
+    }
+
+    /// Text in front of every key on a single node (`None` when sharded)
+    pub(crate) fn unsharded_namespace(&self) -> Option<String> {
+        self.shards.is_none().then(|| self.namespace(Shard(None)))
+    }
+
+    /// Cache key of an entry's Redis key
+    ///
+    /// `None` for tag, dependency and lock keys and for keys outside the namespace.
+    pub(crate) fn cache_key<'a>(&self, redis_key: &'a str) -> Option<&'a str> {
+        let (_, key) = self.split(redis_key)?;
+        (!INTERNAL_NAMESPACES.iter().any(|ns| key.starts_with(ns))).then_some(key)
+    }
+
+    /// Check whether keys are neither prefixed nor sharded
+    ///
+    /// The backend then treats the whole database as its own, e.g. `len` uses `DBSIZE`.
+    pub(crate) fn owns_database(&self) -> bool {
+        self.prefix.is_none() && self.shards.is_none()
+    }
+
+    /// Text in front of the keys of `shard`
+    pub(crate) fn namespace(&self, shard: Shard) -> String {
+        match (shard.0, &self.prefix) {
+            (Some(n), Some(prefix)) => format!("{{{}:{}}}:", prefix, n),
+            (Some(n), None) => format!("{{{}}}:", n),
+            (None, Some(prefix)) => format!("{}:", prefix),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Split a Redis key into its shard and the key within the shard
+    ///
+    /// `None` for keys outside this layout's namespaces.
+    fn split<'a>(&self, redis_key: &'a str) -> Option<(Shard, &'a str)> {
+        let Some(count) = self.shards else {
+            let key = redis_key.strip_prefix(self.namespace(Shard(None)).as_str())?;
+            return Some((Shard(None), key));
+        };
+        let (tag, _) = redis_key.strip_prefix('{')?.split_once("}:")?;
+        let number = match &self.prefix {
+            Some(prefix) => tag.strip_prefix(prefix.as_str())?.strip_prefix(':')?,
+            None => tag,
+        };
+        let shard = Shard(Some(number.parse().ok().filter(|&n| n < count)?));
+        let key = redis_key.strip_prefix(self.namespace(shard).as_str())?;
+        Some((shard, key))
+    }
+}
+
+/// Cluster slot of a Redis key, honouring `{hash tags}`
+fn key_slot(key: &[u8]) -> u16 {
+    let hashed = key
+        .iter()
+        .position(|&b| b == b'{')
+        .and_then(|open| {
+            let rest = &key[open + 1..];
+            let close = rest.iter().position(|&b| b == b'}')?;
+            // An empty tag hashes the whole key
+            (close > 0).then(|| &rest[..close])
+        })
+        .unwrap_or(key);
+    crc16(hashed) % CLUSTER_SLOTS
+}
+
+/// CRC-16/XMODEM, as used by Redis Cluster
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_slot() {
+        assert_eq!(crc16(b"123456789"), 0x31C3);
+        assert_eq!(key_slot(b"foo"), 12182);
+        assert_eq!(key_slot(b"{user1000}.following"), key_slot(b"user1000"));
+        // An empty tag hashes the whole key
+        assert_eq!(
+            key_slot(b"foo{}{bar}"),
+            crc16(b"foo{}{bar}") % CLUSTER_SLOTS
+        );
+    }
+
+    #[test]
+    fn test_single_node_layout() {
+        let layout = KeyLayout::single(Some("app".into()));
+        let shard = layout.shard_of("user:1");
+        assert_eq!(layout.shards(), vec![shard]);
+        assert_eq!(layout.slot(shard), None);
+        assert_eq!(layout.data_key("user:1"), "app:user:1");
+        assert_eq!(layout.tag_key(shard, "users"), "app:__tags__:users");
+        assert_eq!(layout.dep_key(shard, "org:1"), "app:__deps__:org:1");
+        assert_eq!(layout.lock_key("user:1"), "app:__locks__:user:1");
+        assert_eq!(
+            layout.index_sets("user:1", &["users".into()], &["org:1".into()]),
+            vec!["app:__tags__:users", "app:__deps__:org:1"]
+        );
+        assert_eq!(layout.cache_key("app:user:1"), Some("user:1"));
+        assert_eq!(layout.cache_key("app:__tags__:users"), None);
+        assert_eq!(layout.cache_key("other:user:1"), None);
+        assert_eq!(layout.scan_pattern("user:*"), "app:user:*");
+        let all = KeyPattern::new("*");
+        assert_eq!(layout.scanned_key("app:user:1", &all), Some("user:1"));
+        assert_eq!(layout.scanned_key("other:user:1", &all), None);
+
+        let bare = KeyLayout::single(None);
+        assert_eq!(bare.data_key("user:1"), "user:1");
+        assert_eq!(bare.tag_key(shard, "users"), "__tags__:users");
+        assert!(bare.owns_database());
+        assert_eq!(layout.unsharded_namespace().as_deref(), Some("app:"));
+        assert_eq!(bare.unsharded_namespace().as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_sharded_layout_is_slot_local() {
+        let layout = KeyLayout::sharded(Some("app".into()), 16);
+        assert_eq!(layout.shards().len(), 16);
+        assert!(!layout.owns_database());
+        assert_eq!(layout.unsharded_namespace(), None);
+
+        for key in ["user:1", "user:2", "post:42"] {
+            let shard = layout.shard_of(key);
+            let slot = layout.slot(shard).unwrap();
+            for redis_key in [
+                layout.data_key(key),
+                layout.tag_key(shard, "users"),
+                layout.dep_key(shard, "org:1"),
+                layout.lock_key(key),
+            ] {
+                assert_eq!(key_slot(redis_key.as_bytes()), slot, "{}", redis_key);
+            }
+        }
+
+        let shard = layout.shard_of("user:1");
+        let data_key = layout.data_key("user:1");
+        assert!(data_key.starts_with("{app:") && data_key.ends_with("}:user:1"));
+        assert_eq!(layout.cache_key(&layout.tag_key(shard, "users")), None);
+        assert_eq!(layout.cache_key(&data_key), Some("user:1"));
+    }
+
+    #[test]
+    fn test_sharded_scan_covers_every_shard() {
+        let layout = KeyLayout::sharded(Some("app".into()), 16);
+        let scan = KeyPattern::new(layout.scan_pattern("user:*"));
+        let pattern = KeyPattern::new("user:*");
+        for key in ["user:1", "user:2", "user:3"] {
+            let data_key = layout.data_key(key);
+            assert!(scan.matches(&data_key));
+            assert_eq!(layout.scanned_key(&data_key, &pattern), Some(key));
+        }
+
+        // Matched by the scan, but another layout's or not a `user:*` key
+        for redis_key in ["{app:16}:user:1", "{app:x:3}:user:1", "{app:03}:user:1", "{app:3}:a}:user:1"] {
+            assert!(scan.matches(redis_key), "{}", redis_key);
+            assert_eq!(layout.scanned_key(redis_key, &pattern), None, "{}", redis_key);
+        }
+    }
+}
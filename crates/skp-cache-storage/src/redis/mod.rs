@@ -2,12 +2,20 @@
 
 mod backend;
 mod config;
+mod connection;
+mod encoding;
+mod layout;
 mod pubsub;
+#[cfg(feature = "redis-sentinel")]
+mod sentinel;
+mod tracking;
 
 pub use backend::RedisBackend;
 pub use config::RedisConfig;
+pub use encoding::EntryEncoding;
 pub use pubsub::{
     InvalidationEvent, InvalidationPublisher, InvalidationSubscriber, PublishError,
     SubscribeError, INVALIDATION_CHANNEL,
 };
+pub use tracking::TrackingMode;
 
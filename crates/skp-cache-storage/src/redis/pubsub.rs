@@ -3,12 +3,35 @@
 //! Enables cache invalidation events to be broadcast across multiple
 //! application instances sharing the same Redis backend.
 
-use std::sync::Arc;
 use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 
 /// Channel name for cache invalidation events
 pub const INVALIDATION_CHANNEL: &str = "skp_cache:invalidate";
 
+/// Starts messages tagged with the ID of the sending instance
+const ORIGIN_PREFIX: char = '@';
+
+/// Handle to the task forwarding the invalidation channel to local subscribers
+///
+/// Shared by every clone of a `RedisBackend`; the task is stopped when the
+/// last handle is dropped.
+pub(crate) struct Forwarder {
+    task: JoinHandle<()>,
+}
+
+impl Forwarder {
+    pub(crate) fn new(task: JoinHandle<()>) -> Self {
+        Self { task }
+    }
+}
+
+impl Drop for Forwarder {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
 /// Event types for distributed invalidation
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InvalidationEvent {
@@ -49,6 +72,22 @@ impl InvalidationEvent {
             _ => None,
         }
     }
+
+    /// Serialize event to a Redis message tagged with the sender's instance ID
+    pub(crate) fn to_message_from(&self, origin: &str) -> String {
+        format!("{}{} {}", ORIGIN_PREFIX, origin, self.to_message())
+    }
+}
+
+/// Parse a Redis message into the sender's instance ID, if tagged, and the event
+pub(crate) fn parse_message(msg: &str) -> Option<(Option<&str>, InvalidationEvent)> {
+    match msg.strip_prefix(ORIGIN_PREFIX) {
+        Some(tagged) => {
+            let (origin, event) = tagged.split_once(' ')?;
+            Some((Some(origin), InvalidationEvent::from_message(event)?))
+        }
+        None => Some((None, InvalidationEvent::from_message(msg)?)),
+    }
 }
 
 /// Publisher for sending invalidation events
@@ -154,6 +193,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tagged_messages() {
+        let event = InvalidationEvent::Key("user:1 name".into());
+        let msg = event.to_message_from("a1b2");
+        assert_eq!(msg, "@a1b2 key:user:1 name");
+        assert_eq!(parse_message(&msg), Some((Some("a1b2"), event)));
+
+        // Untagged messages from older instances
+        assert_eq!(
+            parse_message("tag:users"),
+            Some((None, InvalidationEvent::Tag("users".into())))
+        );
+        assert_eq!(parse_message("@a1b2"), None);
+        assert_eq!(parse_message("@a1b2 bogus"), None);
+    }
+
     #[tokio::test]
     async fn test_pubsub() {
         let (publisher, mut subscriber) = InvalidationPublisher::new(16);
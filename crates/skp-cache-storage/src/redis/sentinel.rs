@@ -0,0 +1,154 @@
+//! Primary and replica discovery through Redis Sentinel
+
+use parking_lot::Mutex;
+use redis::aio::MultiplexedConnection;
+use redis::sentinel::{SentinelClient, SentinelNodeConnectionInfo, SentinelServerType};
+use redis::{ErrorKind, IntoConnectionInfo, RedisError, RedisResult, Value};
+use skp_cache_core::{CacheError, CacheMetrics, Result};
+use std::sync::Arc;
+use tokio::sync::Mutex as AsyncMutex;
+
+use super::config::RedisConfig;
+
+/// Node a `SentinelManager` connects to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    Primary,
+    Replica,
+}
+
+impl Role {
+    /// Role name reported by the `ROLE` command
+    fn reported_as(self) -> &'static str {
+        match self {
+            Role::Primary => "master",
+            Role::Replica => "slave",
+        }
+    }
+}
+
+/// Connection manager asking Sentinel which node to connect to
+///
+/// Pooled connections are checked with `ROLE` before use. After a failover
+/// the old primary is unreachable or reports itself a replica, so its
+/// connections are dropped and new ones go to the node Sentinel now reports.
+pub(crate) struct SentinelManager {
+    sentinel: AsyncMutex<SentinelClient>,
+    role: Role,
+    /// Address of the node last resolved
+    current: Mutex<Option<String>>,
+    metrics: Arc<dyn CacheMetrics>,
+}
+
+impl SentinelManager {
+    /// Manager for connections to the `role` node of the configured master
+    pub(crate) fn new(
+        config: &RedisConfig,
+        role: Role,
+        metrics: Arc<dyn CacheMetrics>,
+    ) -> Result<Self> {
+        // Database and credentials of the data nodes come from `url`
+        let node_settings = config
+            .url
+            .as_str()
+            .into_connection_info()
+            .map_err(|e| CacheError::Connection(e.to_string()))?
+            .redis_settings()
+            .clone();
+        let server_type = match role {
+            Role::Primary => SentinelServerType::Master,
+            Role::Replica => SentinelServerType::Replica,
+        };
+        let sentinel = SentinelClient::build(
+            config.sentinel_nodes.clone(),
+            config.sentinel_master.clone(),
+            Some(SentinelNodeConnectionInfo::default().set_redis_connection_info(node_settings)),
+            server_type,
+        )
+        .map_err(|e| CacheError::Connection(e.to_string()))?;
+
+        Ok(Self {
+            sentinel: AsyncMutex::new(sentinel),
+            role,
+            current: Mutex::new(None),
+            metrics,
+        })
+    }
+
+    /// Client for the node Sentinel currently reports
+    ///
+    /// A primary at a new address is reported to the metrics as a failover.
+    pub(crate) async fn client(&self) -> RedisResult<redis::Client> {
+        let client = self.sentinel.lock().await.async_get_client().await?;
+        if self.role == Role::Primary {
+            let address = client.get_connection_info().addr().to_string();
+            let previous = self
+                .current
+                .lock()
+                .replace(address.clone());
+            if previous.is_some_and(|previous| previous != address) {
+                self.metrics.record_failover(&address);
+            }
+        }
+        Ok(client)
+    }
+}
+
+impl bb8::ManageConnection for SentinelManager {
+    type Connection = MultiplexedConnection;
+    type Error = RedisError;
+
+    async fn connect(&self) -> RedisResult<MultiplexedConnection> {
+        self.client()
+            .await?
+            .get_multiplexed_async_connection()
+            .await
+    }
+
+    async fn is_valid(&self, conn: &mut MultiplexedConnection) -> RedisResult<()> {
+        let reply: Vec<Value> = redis::cmd("ROLE").query_async(conn).await?;
+        if reported_role(&reply) == Some(self.role.reported_as()) {
+            Ok(())
+        } else {
+            Err((ErrorKind::Extension, "node changed role").into())
+        }
+    }
+
+    fn has_broken(&self, _: &mut MultiplexedConnection) -> bool {
+        false
+    }
+}
+
+/// Role name from a `ROLE` reply
+fn reported_role(reply: &[Value]) -> Option<&str> {
+    match reply.first()? {
+        Value::BulkString(bytes) => std::str::from_utf8(bytes).ok(),
+        Value::SimpleString(role) => Some(role),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reported_role() {
+        let primary = [
+            Value::BulkString(b"master".to_vec()),
+            Value::Int(3129659),
+            Value::Array(Vec::new()),
+        ];
+        assert_eq!(reported_role(&primary), Some(Role::Primary.reported_as()));
+
+        let replica = [
+            Value::BulkString(b"slave".to_vec()),
+            Value::BulkString(b"127.0.0.1".to_vec()),
+            Value::Int(9538),
+        ];
+        assert_eq!(reported_role(&replica), Some(Role::Replica.reported_as()));
+
+        assert_eq!(reported_role(&[]), None);
+        assert_eq!(reported_role(&[Value::Int(1)]), None);
+    }
+}
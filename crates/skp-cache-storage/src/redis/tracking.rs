@@ -0,0 +1,159 @@
+//! Client-side caching through RESP3 `CLIENT TRACKING`
+//!
+//! A dedicated RESP3 connection enables tracking and receives `invalidate`
+//! pushes, which the backend turns into local `InvalidationEvent`s.
+
+use parking_lot::{Mutex, RwLock};
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncConnectionConfig, ProtocolVersion, PushInfo, PushKind, RedisResult, Value};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+
+/// Keys Redis sends invalidation pushes for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrackingMode {
+    /// Keys this backend has read
+    ///
+    /// `get`, `get_many` and `exists` go over the tracking connection so
+    /// Redis records what was read.
+    #[default]
+    Default,
+    /// Every key under the backend's key prefix, whoever read it (`BCAST PREFIX`)
+    Broadcast,
+}
+
+/// Invalidation carried by a push
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Invalidation {
+    /// These Redis keys changed
+    Keys(Vec<String>),
+    /// Everything may have changed (`FLUSHALL`, `FLUSHDB`)
+    All,
+}
+
+/// Connection slot shared with the task that keeps it connected
+pub(crate) type TrackingSlot = Arc<RwLock<Option<MultiplexedConnection>>>;
+
+/// Handle to the tracking connection and the task forwarding its pushes
+///
+/// Shared by every clone of a `RedisBackend`; the task is stopped when the
+/// last handle is dropped.
+pub(crate) struct Tracker {
+    mode: TrackingMode,
+    slot: TrackingSlot,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Tracker {
+    pub(crate) fn new(mode: TrackingMode, slot: TrackingSlot, task: JoinHandle<()>) -> Self {
+        Self {
+            mode,
+            slot,
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    /// Connection reads must use, if any
+    ///
+    /// Only in `TrackingMode::Default`, and only while connected.
+    pub(crate) fn read_connection(&self) -> Option<MultiplexedConnection> {
+        match self.mode {
+            TrackingMode::Default => self.slot.read().clone(),
+            TrackingMode::Broadcast => None,
+        }
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.lock().take() {
+            task.abort();
+        }
+    }
+}
+
+/// Open a RESP3 connection through `client` with tracking enabled
+///
+/// Pushes, including the `Disconnection` sent when the connection closes,
+/// are forwarded to `pushes`. In broadcast mode only keys starting with
+/// `namespace` are tracked.
+pub(crate) async fn connect(
+    client: &redis::Client,
+    mode: TrackingMode,
+    namespace: &str,
+    pushes: UnboundedSender<PushInfo>,
+) -> RedisResult<MultiplexedConnection> {
+    let info = client.get_connection_info().clone();
+    let settings = info
+        .redis_settings()
+        .clone()
+        .set_protocol(ProtocolVersion::RESP3);
+    let client = redis::Client::open(info.set_redis_settings(settings))?;
+    let mut conn = client
+        .get_multiplexed_async_connection_with_config(
+            &AsyncConnectionConfig::new().set_push_sender(pushes),
+        )
+        .await?;
+
+    let mut cmd = redis::cmd("CLIENT");
+    cmd.arg("TRACKING").arg("ON");
+    if mode == TrackingMode::Broadcast {
+        cmd.arg("BCAST");
+        if !namespace.is_empty() {
+            cmd.arg("PREFIX").arg(namespace);
+        }
+    }
+    cmd.query_async::<()>(&mut conn).await?;
+    Ok(conn)
+}
+
+/// Invalidation carried by `push`, if it is an `invalidate` push
+pub(crate) fn parse_push(push: PushInfo) -> Option<Invalidation> {
+    if push.kind != PushKind::Invalidate {
+        return None;
+    }
+    match push.data.into_iter().next()? {
+        Value::Nil => Some(Invalidation::All),
+        Value::Array(keys) => Some(Invalidation::Keys(
+            keys.into_iter()
+                .filter_map(|key| match key {
+                    Value::BulkString(bytes) => String::from_utf8(bytes).ok(),
+                    Value::SimpleString(key) => Some(key),
+                    _ => None,
+                })
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(kind: PushKind, data: Vec<Value>) -> PushInfo {
+        PushInfo { kind, data }
+    }
+
+    #[test]
+    fn test_parse_push() {
+        let keys = Value::Array(vec![
+            Value::BulkString(b"skp:user:1".to_vec()),
+            Value::BulkString(b"skp:user:2".to_vec()),
+        ]);
+        assert_eq!(
+            parse_push(push(PushKind::Invalidate, vec![keys])),
+            Some(Invalidation::Keys(vec![
+                "skp:user:1".to_string(),
+                "skp:user:2".to_string()
+            ]))
+        );
+        assert_eq!(
+            parse_push(push(PushKind::Invalidate, vec![Value::Nil])),
+            Some(Invalidation::All)
+        );
+        assert_eq!(parse_push(push(PushKind::Disconnection, Vec::new())), None);
+        assert_eq!(parse_push(push(PushKind::Message, vec![Value::Nil])), None);
+    }
+}
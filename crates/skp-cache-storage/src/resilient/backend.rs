@@ -0,0 +1,371 @@
+use async_trait::async_trait;
+use skp_cache_core::{
+    CacheBackend, CacheEntry, CacheError, CacheOptions, CacheStats, DependencyBackend,
+    DistributedBackend, PatternBackend, Result, TaggableBackend,
+};
+use std::future::Future;
+use std::time::Duration;
+
+use super::config::{OperationTimeouts, RetryPolicy};
+
+/// Backend wrapper bounding each operation in time and retrying transient
+/// failures
+///
+/// An attempt running past its `OperationTimeouts` limit is abandoned with
+/// `CacheError::Timeout`. Failing with a transient error, `get`, `exists`,
+/// `get_many`, `set`, `set_many` and `delete` are retried as the `RetryPolicy`
+/// says; the last error is returned once attempts run out. A delete that
+/// succeeded on an attempt that timed out may be reported as having removed
+/// nothing. Compare-and-set writes (`CacheOptions::if_version`) and every
+/// other operation, such as bulk invalidations and locks, run once.
+///
+/// Wrap the L2 of a `MultiTierBackend` to let timeouts trip its circuit breaker.
+#[derive(Debug, Clone)]
+pub struct ResilientBackend<B> {
+    inner: B,
+    timeouts: OperationTimeouts,
+    retry: RetryPolicy,
+}
+
+impl<B> ResilientBackend<B> {
+    /// Wrap `inner`
+    pub fn new(inner: B, timeouts: OperationTimeouts, retry: RetryPolicy) -> Self {
+        Self {
+            inner,
+            timeouts,
+            retry,
+        }
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Run `op` with `timeout` per attempt, retrying transient errors if `retryable`
+    async fn run<T, F, Fut>(&self, timeout: Option<Duration>, retryable: bool, op: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let attempts = if retryable {
+            self.retry.max_attempts.max(1)
+        } else {
+            1
+        };
+
+        let mut attempt = 1;
+        loop {
+            let result = match timeout {
+                Some(timeout) => tokio::time::timeout(timeout, op())
+                    .await
+                    .unwrap_or(Err(CacheError::Timeout)),
+                None => op().await,
+            };
+            match result {
+                Err(e) if attempt < attempts && e.is_transient() => {
+                    tokio::time::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<B: CacheBackend> CacheBackend for ResilientBackend<B> {
+    async fn get(&self, key: &str) -> Result<Option<CacheEntry<Vec<u8>>>> {
+        self.run(self.timeouts.get, true, || self.inner.get(key))
+            .await
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, options: &CacheOptions) -> Result<()> {
+        let idempotent = options.if_version.is_none();
+        self.run(self.timeouts.set, idempotent, || {
+            self.inner.set(key, value.clone(), options)
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        self.run(self.timeouts.delete, true, || self.inner.delete(key))
+            .await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        self.run(self.timeouts.get, true, || self.inner.exists(key))
+            .await
+    }
+
+    async fn delete_many(&self, keys: &[&str]) -> Result<u64> {
+        self.run(self.timeouts.bulk, false, || self.inner.delete_many(keys))
+            .await
+    }
+
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<CacheEntry<Vec<u8>>>>> {
+        self.run(self.timeouts.bulk, true, || self.inner.get_many(keys))
+            .await
+    }
+
+    async fn set_many(&self, entries: &[(&str, Vec<u8>, &CacheOptions)]) -> Result<()> {
+        let idempotent = entries
+            .iter()
+            .all(|(_, _, options)| options.if_version.is_none());
+        self.run(self.timeouts.bulk, idempotent, || {
+            self.inner.set_many(entries)
+        })
+        .await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.run(self.timeouts.bulk, false, || self.inner.clear())
+            .await
+    }
+
+    async fn stats(&self) -> Result<CacheStats> {
+        self.run(self.timeouts.bulk, false, || self.inner.stats())
+            .await
+    }
+
+    async fn len(&self) -> Result<usize> {
+        self.run(self.timeouts.bulk, false, || self.inner.len())
+            .await
+    }
+}
+
+#[async_trait]
+impl<B: TaggableBackend> TaggableBackend for ResilientBackend<B> {
+    async fn get_by_tag(&self, tag: &str) -> Result<Vec<String>> {
+        self.run(self.timeouts.bulk, false, || self.inner.get_by_tag(tag))
+            .await
+    }
+
+    async fn delete_by_tag(&self, tag: &str) -> Result<u64> {
+        self.run(self.timeouts.bulk, false, || self.inner.delete_by_tag(tag))
+            .await
+    }
+}
+
+#[async_trait]
+impl<B: PatternBackend> PatternBackend for ResilientBackend<B> {
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<u64> {
+        self.run(self.timeouts.bulk, false, || {
+            self.inner.delete_by_pattern(pattern)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl<B: DependencyBackend> DependencyBackend for ResilientBackend<B> {
+    async fn get_dependents(&self, key: &str) -> Result<Vec<String>> {
+        self.run(self.timeouts.bulk, false, || self.inner.get_dependents(key))
+            .await
+    }
+
+    async fn delete_cascade(&self, key: &str) -> Result<Vec<String>> {
+        self.run(self.timeouts.bulk, false, || self.inner.delete_cascade(key))
+            .await
+    }
+}
+
+#[async_trait]
+impl<B: DistributedBackend> DistributedBackend for ResilientBackend<B> {
+    async fn acquire_lock(&self, key: &str, ttl: Duration) -> Result<String> {
+        // A timed-out attempt may still have taken the lock
+        self.run(self.timeouts.set, false, || {
+            self.inner.acquire_lock(key, ttl)
+        })
+        .await
+    }
+
+    async fn release_lock(&self, key: &str, token: &str) -> Result<bool> {
+        self.run(self.timeouts.delete, false, || {
+            self.inner.release_lock(key, token)
+        })
+        .await
+    }
+
+    async fn publish_invalidation(&self, keys: &[&str]) -> Result<()> {
+        self.run(self.timeouts.bulk, false, || {
+            self.inner.publish_invalidation(keys)
+        })
+        .await
+    }
+
+    async fn subscribe_invalidations(&self) -> Result<()> {
+        self.run(self.timeouts.bulk, false, || {
+            self.inner.subscribe_invalidations()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Backend whose `get` and `clear` fail `failures` times, then succeed; `set` hangs
+    #[derive(Default)]
+    struct Flaky {
+        failures: u32,
+        error: Option<CacheError>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl Flaky {
+        fn attempt(&self) -> Result<()> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                Err(self
+                    .error
+                    .clone()
+                    .unwrap_or(CacheError::Connection("reset".into())))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[async_trait]
+    impl CacheBackend for Flaky {
+        async fn get(&self, _key: &str) -> Result<Option<CacheEntry<Vec<u8>>>> {
+            self.attempt().map(|()| None)
+        }
+
+        async fn set(&self, _key: &str, _value: Vec<u8>, _options: &CacheOptions) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            std::future::pending().await
+        }
+
+        async fn delete(&self, _key: &str) -> Result<bool> {
+            Ok(false)
+        }
+
+        async fn exists(&self, _key: &str) -> Result<bool> {
+            Ok(false)
+        }
+
+        async fn delete_many(&self, _keys: &[&str]) -> Result<u64> {
+            Ok(0)
+        }
+
+        async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<CacheEntry<Vec<u8>>>>> {
+            Ok(vec![None; keys.len()])
+        }
+
+        async fn set_many(&self, _entries: &[(&str, Vec<u8>, &CacheOptions)]) -> Result<()> {
+            Ok(())
+        }
+
+        async fn clear(&self) -> Result<()> {
+            self.attempt()
+        }
+
+        async fn stats(&self) -> Result<CacheStats> {
+            Ok(CacheStats::default())
+        }
+
+        async fn len(&self) -> Result<usize> {
+            Ok(0)
+        }
+    }
+
+    fn fast_retries(attempts: u32) -> RetryPolicy {
+        RetryPolicy::default()
+            .max_attempts(attempts)
+            .backoff(Duration::from_millis(1), Duration::from_millis(2))
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let flaky = Flaky {
+            failures: 2,
+            calls: calls.clone(),
+            ..Default::default()
+        };
+        let backend = ResilientBackend::new(flaky, OperationTimeouts::default(), fast_retries(3));
+
+        assert!(backend.get("k").await.unwrap().is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retries_run_out() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let flaky = Flaky {
+            failures: 5,
+            calls: calls.clone(),
+            ..Default::default()
+        };
+        let backend = ResilientBackend::new(flaky, OperationTimeouts::default(), fast_retries(2));
+
+        assert!(matches!(
+            backend.get("k").await,
+            Err(CacheError::Connection(_))
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_invalidations_are_not_retried() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let flaky = Flaky {
+            failures: 1,
+            calls: calls.clone(),
+            ..Default::default()
+        };
+        let backend = ResilientBackend::new(flaky, OperationTimeouts::default(), fast_retries(3));
+
+        assert!(matches!(backend.clear().await, Err(CacheError::Connection(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_not_retried() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let flaky = Flaky {
+            failures: 1,
+            error: Some(CacheError::Deserialization("bad".into())),
+            calls: calls.clone(),
+        };
+        let backend = ResilientBackend::new(flaky, OperationTimeouts::default(), fast_retries(3));
+
+        assert!(matches!(
+            backend.get("k").await,
+            Err(CacheError::Deserialization(_))
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_hung_operation_times_out() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let flaky = Flaky {
+            calls: calls.clone(),
+            ..Default::default()
+        };
+        let timeouts = OperationTimeouts::default().set(Duration::from_millis(10));
+        let backend = ResilientBackend::new(flaky, timeouts, fast_retries(2));
+
+        let result = backend
+            .set("k", b"v".to_vec(), &CacheOptions::default())
+            .await;
+        assert!(matches!(result, Err(CacheError::Timeout)));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Compare-and-set writes are not retried
+        calls.store(0, Ordering::SeqCst);
+        let options = CacheOptions {
+            if_version: Some(1),
+            ..Default::default()
+        };
+        let result = backend.set("k", b"v".to_vec(), &options).await;
+        assert!(matches!(result, Err(CacheError::Timeout)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
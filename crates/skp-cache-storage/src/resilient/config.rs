@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+/// Time limit of each attempt, by operation (`None` = no limit)
+#[derive(Debug, Clone, Default)]
+pub struct OperationTimeouts {
+    /// `get` and `exists`
+    pub get: Option<Duration>,
+    /// `set` and `acquire_lock`
+    pub set: Option<Duration>,
+    /// `delete` and `release_lock`
+    pub delete: Option<Duration>,
+    /// Everything else: `get_many`, `set_many`, `delete_many`, tag, pattern
+    /// and dependency operations, `clear`, `len`, `stats` and invalidation
+    pub bulk: Option<Duration>,
+}
+
+impl OperationTimeouts {
+    /// Same limit for every operation
+    pub fn uniform(timeout: Duration) -> Self {
+        Self {
+            get: Some(timeout),
+            set: Some(timeout),
+            delete: Some(timeout),
+            bulk: Some(timeout),
+        }
+    }
+
+    /// Set the limit of `get` and `exists`
+    pub fn get(mut self, timeout: Duration) -> Self {
+        self.get = Some(timeout);
+        self
+    }
+
+    /// Set the limit of `set` and `acquire_lock`
+    pub fn set(mut self, timeout: Duration) -> Self {
+        self.set = Some(timeout);
+        self
+    }
+
+    /// Set the limit of `delete` and `release_lock`
+    pub fn delete(mut self, timeout: Duration) -> Self {
+        self.delete = Some(timeout);
+        self
+    }
+
+    /// Set the limit of bulk and other operations
+    pub fn bulk(mut self, timeout: Duration) -> Self {
+        self.bulk = Some(timeout);
+        self
+    }
+}
+
+/// Retries of idempotent operations with exponential backoff and jitter
+///
+/// Only errors `CacheError::is_transient` accepts (those that trip a
+/// `CircuitBreaker`) are retried. The wait before retry `n` is
+/// `initial_backoff * multiplier^(n - 1)`, capped at `max_backoff`, then
+/// shortened by a random fraction of up to `jitter` so clients that failed
+/// together do not retry together.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts per operation, including the first (1 = no retries)
+    pub max_attempts: u32,
+    /// Wait before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound of the wait between attempts
+    pub max_backoff: Duration,
+    /// Growth of the wait after each retry
+    pub multiplier: f64,
+    /// Largest fraction (0.0 to 1.0) randomly taken off each wait
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(500),
+            multiplier: 2.0,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Set the number of attempts, including the first
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Set the initial and maximum wait between attempts
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the growth of the wait after each retry
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set the largest fraction randomly taken off each wait
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Wait before retry `retry` (1-based), before jitter
+    fn base_delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        Duration::try_from_secs_f64(secs)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Wait before retry `retry` (1-based)
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::random::<f64>();
+        self.base_delay(retry).mul_f64(1.0 - jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy::default()
+            .backoff(Duration::from_millis(10), Duration::from_millis(50))
+            .jitter(0.0);
+        assert_eq!(policy.delay(1), Duration::from_millis(10));
+        assert_eq!(policy.delay(2), Duration::from_millis(20));
+        assert_eq!(policy.delay(3), Duration::from_millis(40));
+        assert_eq!(policy.delay(4), Duration::from_millis(50));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_jitter_shortens_delay() {
+        let policy = RetryPolicy::default()
+            .backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .jitter(0.5);
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Timeouts and retries around another backend
+
+mod backend;
+mod config;
+
+pub use backend::ResilientBackend;
+pub use config::{OperationTimeouts, RetryPolicy};
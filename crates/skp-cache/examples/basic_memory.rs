@@ -114,12 +114,9 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         )
         .await?;
 
-    match cache.get::<String>("temp_data").await? {
-        CacheResult::Hit(entry) => {
-            println!("✅ Temp data cached with TTL: {:?}", entry.ttl);
-            println!("   Stale-while-revalidate: {:?}", entry.stale_while_revalidate);
-        }
-        _ => {}
+    if let CacheResult::Hit(entry) = cache.get::<String>("temp_data").await? {
+        println!("✅ Temp data cached with TTL: {:?}", entry.ttl);
+        println!("   Stale-while-revalidate: {:?}", entry.stale_while_revalidate);
     }
 
     println!("\n=== Example Complete ===");
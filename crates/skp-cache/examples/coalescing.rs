@@ -36,7 +36,7 @@ async fn main() -> Result<()> {
                             count.fetch_add(1, Ordering::SeqCst);
                             println!("  → Computing expensive report...");
                             tokio::time::sleep(Duration::from_millis(100)).await;
-                            Ok::<_, CacheError>(format!("Report data"))
+                            Ok::<_, CacheError>("Report data".to_string())
                         }
                     },
                     Some(CacheOpts::new().ttl_secs(60).into()),
@@ -37,13 +37,15 @@ pub use skp_cache_core::*;
 
 // Re-export storage
 #[cfg(feature = "memory")]
-pub use skp_cache_storage::{MemoryBackend, MemoryConfig};
+pub use skp_cache_storage::{EvictionPolicy, KeyFilter, MemoryBackend, MemoryConfig, Weigher};
 
 #[cfg(feature = "redis")]
-pub use skp_cache_storage::{RedisBackend, RedisConfig};
+pub use skp_cache_storage::{EntryEncoding, RedisBackend, RedisConfig, TrackingMode};
 
 #[cfg(feature = "multitier")]
-pub use skp_cache_storage::{MultiTierBackend, CircuitBreaker};
+pub use skp_cache_storage::{MultiTierBackend, CircuitBreaker, DegradationPolicy, DegradedMode, WriteBehindConfig};
+
+pub use skp_cache_storage::{OperationTimeouts, ResilientBackend, RetryPolicy};
 
 #[cfg(feature = "derive")]
 pub use skp_cache_derive::CacheKey;
@@ -62,7 +64,7 @@ pub mod prelude {
     };
 
     #[cfg(feature = "memory")]
-    pub use crate::{MemoryBackend, MemoryConfig};
+    pub use crate::{EvictionPolicy, MemoryBackend, MemoryConfig};
 
     #[cfg(feature = "redis")]
     pub use crate::{RedisBackend, RedisConfig};
@@ -76,6 +78,15 @@ pub mod prelude {
     #[cfg(feature = "bincode")]
     pub use crate::BincodeSerializer;
 
+    #[cfg(feature = "postcard")]
+    pub use crate::PostcardSerializer;
+
+    #[cfg(feature = "cbor")]
+    pub use crate::CborSerializer;
+
+    #[cfg(feature = "rkyv")]
+    pub use crate::{ArchivedValue, RkyvSerializer};
+
     #[cfg(feature = "derive")]
     pub use crate::CacheKey as DeriveCacheKey;
 }
@@ -0,0 +1,125 @@
+//! In-place reads of rkyv-archived values
+
+use std::time::Instant;
+
+use skp_cache_core::rkyv::api::high::{HighSerializer, HighValidator};
+use skp_cache_core::rkyv::bytecheck::CheckBytes;
+use skp_cache_core::rkyv::rancor;
+use skp_cache_core::rkyv::ser::allocator::ArenaHandle;
+use skp_cache_core::rkyv::util::AlignedVec;
+use skp_cache_core::rkyv::{Archive, Serialize};
+use skp_cache_core::{
+    ArchivedValue, CacheBackend, CacheKey, CacheMetrics, CacheOperation, CacheOptions, CacheResult,
+    CacheTier, DependencyBackend, Result, RkyvSerializer, Serializer,
+};
+
+use super::{CacheManager, Envelope, with_value};
+
+impl<B, S, M> CacheManager<B, S, M>
+where
+    B: CacheBackend + DependencyBackend,
+    S: Serializer,
+    M: CacheMetrics,
+{
+    /// Set a value in cache as an rkyv archive
+    ///
+    /// Read it back with `get_archived`; `get` treats it as a format mismatch.
+    pub async fn set_archived<T>(
+        &self,
+        key: impl CacheKey,
+        value: &T,
+        options: impl Into<CacheOptions>,
+    ) -> Result<()>
+    where
+        T: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, rancor::Error>>,
+    {
+        let full_key = self.full_key(&key.full_key());
+
+        let serialize_start = Instant::now();
+        let bytes = RkyvSerializer.serialize(value)?;
+        let encoded = self.codec.encode(bytes, self.archived_envelope::<T>(&full_key))?;
+        self.metrics
+            .record_latency(CacheOperation::Serialize, serialize_start.elapsed());
+
+        self.set_raw(&full_key, encoded, options.into()).await
+    }
+
+    /// Get an archived value without deserializing it
+    ///
+    /// The archive is validated once; fields are then read in place through
+    /// `ArchivedValue`, which dereferences to `T::Archived`.
+    pub async fn get_archived<T>(&self, key: impl CacheKey) -> Result<CacheResult<ArchivedValue<T>>>
+    where
+        T: Archive,
+        T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    {
+        let full_key = self.full_key(&key.full_key());
+        let start = Instant::now();
+
+        let backend = self.backend.clone();
+        let key_clone = full_key.clone();
+        let req_result = self
+            .coalescer
+            .do_request(
+                &full_key,
+                move || async move { backend.get(&key_clone).await },
+            )
+            .await?;
+
+        let result = match req_result {
+            Some(entry) if entry.is_expired() && !entry.is_stale() => {
+                self.metrics.record_miss(&full_key);
+                CacheResult::Miss
+            }
+            Some(entry) if entry.negative => {
+                self.metrics.record_negative_hit(&full_key);
+                CacheResult::NegativeHit
+            }
+            Some(entry) => {
+                let access_start = Instant::now();
+                let archived = match self
+                    .codec
+                    .decode(&entry.value, self.archived_envelope::<T>(&full_key))?
+                {
+                    Some(bytes) => Some(ArchivedValue::<T>::new(&bytes)?),
+                    None => None,
+                };
+                self.metrics
+                    .record_latency(CacheOperation::Deserialize, access_start.elapsed());
+
+                match archived {
+                    Some(value) if entry.is_stale() => {
+                        self.metrics.record_stale_hit(&full_key);
+                        CacheResult::Stale(with_value(entry, value))
+                    }
+                    Some(value) => {
+                        self.metrics.record_hit(&full_key, CacheTier::L1Memory);
+                        CacheResult::Hit(with_value(entry, value))
+                    }
+                    None => {
+                        self.evict_mismatched(&full_key).await;
+                        self.metrics.record_miss(&full_key);
+                        CacheResult::Miss
+                    }
+                }
+            }
+            None => {
+                self.metrics.record_miss(&full_key);
+                CacheResult::Miss
+            }
+        };
+
+        self.metrics
+            .record_latency(CacheOperation::Get, start.elapsed());
+        Ok(result)
+    }
+
+    /// Envelope of rkyv-archived values of type `T` stored under `full_key`
+    fn archived_envelope<'a, T>(&self, full_key: &'a str) -> Envelope<'a> {
+        Envelope {
+            key: full_key,
+            serializer: RkyvSerializer.name(),
+            schema_version: self.schema_version::<T>(),
+        }
+    }
+}
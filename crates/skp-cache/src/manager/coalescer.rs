@@ -3,11 +3,14 @@ use tokio::sync::broadcast;
 use dashmap::DashMap;
 use skp_cache_core::{CacheEntry, Result, CacheError};
 
+/// Result shared with followers of an in-flight request
+type SharedResult = Result<Option<CacheEntry<Vec<u8>>>>;
+
 #[derive(Clone, Default)]
 pub struct Coalescer {
     // Map key -> Broadcast channel sender
     // The sender transmits the result of the cache fetch
-    inflight: Arc<DashMap<String, broadcast::Sender<Result<Option<CacheEntry<Vec<u8>>>>>>>,
+    inflight: Arc<DashMap<String, broadcast::Sender<SharedResult>>>,
     // Set of keys currently being refreshed in background (SWR)
     refreshing: Arc<DashMap<String, ()>>,
 }
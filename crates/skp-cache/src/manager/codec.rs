@@ -0,0 +1,384 @@
+//! Value envelope: the header stored in front of serialized bytes
+//!
+//! Layout (integers big endian):
+//!
+//! ```text
+//! magic (2) | format version (1) | flags (1) | schema version (4)
+//! | serializer name length (1) | serializer name
+//! | compressor name length (1) | compressor name (empty if not compressed)
+//! | key ID (4, encrypted values only) | header checksum (4) | payload
+//! ```
+//!
+//! The checksum (FNV-1a of the header bytes before it) tells envelopes apart
+//! from serializer output that happens to start with the magic bytes.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use skp_cache_core::{CacheError, Compressor, Encryptor, Result};
+
+/// Marks a value written with an envelope
+///
+/// `0xFF` never starts JSON and is a lone `-1` in MessagePack. Other formats
+/// (e.g. bincode) can start with these bytes, so a value only counts as
+/// enveloped if the header checksum matches too.
+const MAGIC: [u8; 2] = [0xFF, 0x53];
+
+/// Layout of the header that follows the magic bytes
+const FORMAT_VERSION: u8 = 1;
+
+/// Payload is compressed
+const FLAG_COMPRESSED: u8 = 0b0000_0001;
+
+/// Payload is encrypted; the header ends with the key ID
+const FLAG_ENCRYPTED: u8 = 0b0000_0010;
+
+/// Flags this version understands
+const KNOWN_FLAGS: u8 = FLAG_COMPRESSED | FLAG_ENCRYPTED;
+
+/// What the reader or writer of a value expects the envelope to say
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Envelope<'a> {
+    /// Full key the value is stored under, authenticated with encrypted values
+    pub key: &'a str,
+    /// `Serializer::name` of the manager
+    pub serializer: &'a str,
+    /// Schema version registered for the value type
+    pub schema_version: u32,
+}
+
+/// Parsed envelope header
+struct Header<'a> {
+    flags: u8,
+    schema_version: u32,
+    serializer: &'a [u8],
+    compressor: &'a [u8],
+    key_id: Option<u32>,
+    /// Header length, including the key ID and checksum
+    len: usize,
+}
+
+/// How stored bytes are framed
+enum Format<'a> {
+    /// Plain serializer output
+    Raw,
+    /// Envelope written by another format version or with unknown flags
+    Unknown,
+    /// Envelope this version can read
+    Enveloped(Header<'a>),
+}
+
+/// Object-safe view of a `Compressor`
+trait DynCompressor: Send + Sync {
+    fn name(&self) -> &str;
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>>;
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>>;
+    fn should_compress(&self, data: &[u8]) -> bool;
+}
+
+impl<C: Compressor> DynCompressor for C {
+    fn name(&self) -> &str {
+        Compressor::name(self)
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Compressor::compress(self, data)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Compressor::decompress(self, data)
+    }
+
+    fn should_compress(&self, data: &[u8]) -> bool {
+        Compressor::should_compress(self, data)
+    }
+}
+
+/// Object-safe view of an `Encryptor`
+trait DynEncryptor: Send + Sync {
+    fn key_id(&self) -> u32;
+    fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>>;
+    fn decrypt(&self, key_id: u32, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl<E: Encryptor> DynEncryptor for E {
+    fn key_id(&self) -> u32 {
+        Encryptor::key_id(self)
+    }
+
+    fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        Encryptor::encrypt(self, plaintext, aad)
+    }
+
+    fn decrypt(&self, key_id: u32, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        Encryptor::decrypt(self, key_id, ciphertext, aad)
+    }
+}
+
+/// Turns serializer output into stored bytes and back
+///
+/// Values are wrapped in an envelope recording the serializer, compressor and
+/// schema version they were written with, and whether the payload is
+/// compressed and/or encrypted. A value whose envelope does not match what the
+/// reader expects is reported as a mismatch rather than an error. Without an
+/// envelope, compressor or encryptor values are stored exactly as serialized;
+/// headerless values are read as is unless encryption is required, and codecs
+/// writing no envelope also read values of unknown envelope formats as is.
+/// Encrypted values record the key ID and authenticate the whole header along
+/// with the key they are stored under, so they cannot be moved to another key.
+#[derive(Clone, Default)]
+pub(crate) struct ValueCodec {
+    envelope: bool,
+    compressor: Option<Arc<dyn DynCompressor>>,
+    encryptor: Option<Arc<dyn DynEncryptor>>,
+    /// Reject values that are not encrypted
+    require_encryption: bool,
+}
+
+impl ValueCodec {
+    /// Create a codec, writing envelopes if `envelope` is set
+    pub(crate) fn new(envelope: bool) -> Self {
+        Self {
+            envelope,
+            ..Default::default()
+        }
+    }
+
+    /// Compress values written from now on
+    pub(crate) fn set_compressor<C: Compressor>(&mut self, compressor: C) {
+        self.compressor = Some(Arc::new(compressor));
+    }
+
+    /// Encrypt values written from now on
+    ///
+    /// With `require_encryption`, plain and unencrypted enveloped values fail
+    /// to decode instead of being read as is.
+    pub(crate) fn set_encryptor<E: Encryptor>(&mut self, encryptor: E, require_encryption: bool) {
+        self.encryptor = Some(Arc::new(encryptor));
+        self.require_encryption = require_encryption;
+    }
+
+    /// Check whether values are stored exactly as serialized
+    ///
+    /// Such a codec never wrote envelopes, so it reads what looks like an
+    /// envelope of an unknown format as plain bytes.
+    fn is_plain(&self) -> bool {
+        !self.envelope && self.compressor.is_none() && self.encryptor.is_none()
+    }
+
+    /// Encode serialized bytes for storage
+    pub(crate) fn encode(&self, bytes: Vec<u8>, envelope: Envelope<'_>) -> Result<Vec<u8>> {
+        if self.is_plain() {
+            return Ok(bytes);
+        }
+
+        let mut flags = 0;
+        let mut compressor_name = "";
+        let mut payload = bytes;
+        if let Some(compressor) = &self.compressor {
+            if compressor.should_compress(&payload) {
+                let compressed = compressor.compress(&payload)?;
+                // Keep incompressible data as is
+                if compressed.len() < payload.len() {
+                    flags |= FLAG_COMPRESSED;
+                    compressor_name = compressor.name();
+                    payload = compressed;
+                }
+            }
+        }
+        if self.encryptor.is_some() {
+            flags |= FLAG_ENCRYPTED;
+        }
+
+        let mut header = Vec::with_capacity(64);
+        header.extend_from_slice(&MAGIC);
+        header.push(FORMAT_VERSION);
+        header.push(flags);
+        header.extend_from_slice(&envelope.schema_version.to_be_bytes());
+        push_name(&mut header, envelope.serializer)?;
+        push_name(&mut header, compressor_name)?;
+
+        if let Some(encryptor) = &self.encryptor {
+            header.extend_from_slice(&encryptor.key_id().to_be_bytes());
+        }
+        let sum = checksum(&header);
+        header.extend_from_slice(&sum);
+
+        if let Some(encryptor) = &self.encryptor {
+            // Compress first: ciphertext does not compress
+            payload = encryptor.encrypt(&payload, &aad(&header, envelope.key))?;
+        }
+
+        let mut out = header;
+        out.extend_from_slice(&payload);
+        Ok(out)
+    }
+
+    /// Check whether stored bytes can be decoded as `envelope` without decoding them
+    pub(crate) fn matches(&self, bytes: &[u8], envelope: Envelope<'_>) -> bool {
+        match parse(bytes) {
+            Format::Raw => true,
+            Format::Unknown => self.is_plain(),
+            Format::Enveloped(header) => self.header_matches(&header, envelope),
+        }
+    }
+
+    /// Decode stored bytes into serializer input
+    ///
+    /// Returns `None` if the value was written in a different format.
+    pub(crate) fn decode<'a>(
+        &self,
+        bytes: &'a [u8],
+        envelope: Envelope<'_>,
+    ) -> Result<Option<Cow<'a, [u8]>>> {
+        let header = match parse(bytes) {
+            Format::Raw if self.require_encryption => return Err(unencrypted()),
+            Format::Raw => return Ok(Some(Cow::Borrowed(bytes))),
+            Format::Unknown if self.is_plain() => return Ok(Some(Cow::Borrowed(bytes))),
+            Format::Unknown => return Ok(None),
+            Format::Enveloped(header) => header,
+        };
+        if !self.header_matches(&header, envelope) {
+            return Ok(None);
+        }
+        if header.key_id.is_none() && self.require_encryption {
+            return Err(unencrypted());
+        }
+
+        let (header_bytes, payload) = bytes.split_at(header.len);
+        let payload = match header.key_id {
+            Some(key_id) => {
+                let Some(encryptor) = &self.encryptor else {
+                    return Err(CacheError::Decryption(
+                        "value is encrypted but no encryptor is configured".into(),
+                    ));
+                };
+                Cow::Owned(encryptor.decrypt(key_id, payload, &aad(header_bytes, envelope.key))?)
+            }
+            None => Cow::Borrowed(payload),
+        };
+
+        match &self.compressor {
+            Some(compressor) if header.flags & FLAG_COMPRESSED != 0 => compressor
+                .decompress(&payload)
+                .map(|bytes| Some(Cow::Owned(bytes))),
+            _ => Ok(Some(payload)),
+        }
+    }
+
+    /// Serializer, schema version and compressor agree with this reader
+    fn header_matches(&self, header: &Header<'_>, envelope: Envelope<'_>) -> bool {
+        if header.serializer != envelope.serializer.as_bytes()
+            || header.schema_version != envelope.schema_version
+        {
+            return false;
+        }
+        if header.flags & FLAG_COMPRESSED == 0 {
+            return true;
+        }
+        self.compressor
+            .as_ref()
+            .is_some_and(|compressor| header.compressor == compressor.name().as_bytes())
+    }
+}
+
+/// Associated data of an encrypted value: its header, then the key it is stored under
+fn aad(header: &[u8], key: &str) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(header.len() + key.len());
+    aad.extend_from_slice(header);
+    aad.extend_from_slice(key.as_bytes());
+    aad
+}
+
+/// Error for a value read without encryption while encryption is required
+fn unencrypted() -> CacheError {
+    CacheError::Decryption("value is not encrypted".into())
+}
+
+/// Append a length-prefixed name
+fn push_name(out: &mut Vec<u8>, name: &str) -> Result<()> {
+    let len = u8::try_from(name.len())
+        .map_err(|_| CacheError::Serialization(format!("name too long for envelope: {}", name)))?;
+    out.push(len);
+    out.extend_from_slice(name.as_bytes());
+    Ok(())
+}
+
+/// FNV-1a hash of header bytes
+fn checksum(header: &[u8]) -> [u8; 4] {
+    let hash = header.iter().fold(0x811C_9DC5u32, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    hash.to_be_bytes()
+}
+
+/// Split stored bytes into envelope header and payload
+///
+/// Bytes starting with the magic but without a valid header are raw.
+fn parse(bytes: &[u8]) -> Format<'_> {
+    if !bytes.starts_with(&MAGIC) {
+        return Format::Raw;
+    }
+    if bytes.get(MAGIC.len()) != Some(&FORMAT_VERSION) {
+        return Format::Unknown;
+    }
+    let mut reader = Reader {
+        bytes,
+        pos: MAGIC.len(),
+    };
+    match reader.header() {
+        Some(header) if header.flags & !KNOWN_FLAGS != 0 => Format::Unknown,
+        Some(header) => Format::Enveloped(header),
+        None => Format::Raw,
+    }
+}
+
+/// Cursor over header bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn header(&mut self) -> Option<Header<'a>> {
+        if self.take(1)?[0] != FORMAT_VERSION {
+            return None;
+        }
+        let flags = self.take(1)?[0];
+        let schema_version = self.u32()?;
+        let serializer = self.name()?;
+        let compressor = self.name()?;
+        let key_id = if flags & FLAG_ENCRYPTED != 0 {
+            Some(self.u32()?)
+        } else {
+            None
+        };
+        let end = self.pos;
+        if self.take(4)? != checksum(&self.bytes[..end]) {
+            return None;
+        }
+        Some(Header {
+            flags,
+            schema_version,
+            serializer,
+            compressor,
+            key_id,
+            len: self.pos,
+        })
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)?.try_into().ok().map(u32::from_be_bytes)
+    }
+
+    fn name(&mut self) -> Option<&'a [u8]> {
+        let len = self.take(1)?[0] as usize;
+        self.take(len)
+    }
+}
@@ -2,14 +2,17 @@
 
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashMap;
 
 use skp_cache_core::{
     CacheBackend, CacheEntry, CacheKey, CacheMetrics, CacheOperation, CacheOptions,
-    CacheResult, CacheTier, DependencyBackend, JsonSerializer, NoopMetrics, Result, Serializer,
-    TaggableBackend,
+    CacheResult, CacheTier, Compressor, DependencyBackend, Encryptor, EvictionReason,
+    JsonSerializer, KeyPattern, NoopMetrics, PatternBackend, Result, Serializer, TaggableBackend,
 };
 
+mod codec;
+use codec::{Envelope, ValueCodec};
+
 mod coalescer;
 use coalescer::Coalescer;
 
@@ -19,23 +22,47 @@ pub use read_through::{Loader, ReadThroughCache, CacheManagerReadThroughExt};
 mod groups;
 pub use groups::CacheGroup;
 
+#[cfg(feature = "rkyv")]
+mod archived;
+
 /// Configuration for CacheManager
 #[derive(Debug, Clone)]
 pub struct CacheManagerConfig {
     /// Default TTL for entries without explicit TTL
     pub default_ttl: Option<Duration>,
+    /// TTL for negative entries without explicit TTL
+    pub negative_ttl: Duration,
     /// Namespace prefix for all keys
     pub namespace: Option<String>,
     /// TTL jitter percentage (0.0 - 1.0) to prevent thundering herd
     pub ttl_jitter: f64,
+    /// Record the measured compute duration (in microseconds) as the entry cost
+    /// in `get_or_compute` when no explicit cost is given
+    pub measure_compute_cost: bool,
+    /// XFetch `beta` for early refresh (> 1.0 favours earlier recomputes)
+    pub early_refresh_beta: f64,
+    /// Store values in an envelope recording serializer, compressor and schema version
+    ///
+    /// Off by default: releases without envelope support read enveloped values
+    /// as garbage, so only enable it once every instance sharing the backend
+    /// reads envelopes. Values are enveloped regardless once a compressor or
+    /// encryptor is set.
+    pub envelope: bool,
+    /// Schema version per value type (`std::any::type_name`), 0 if not listed
+    pub schema_versions: HashMap<String, u32>,
 }
 
 impl Default for CacheManagerConfig {
     fn default() -> Self {
         Self {
             default_ttl: Some(Duration::from_secs(300)),
+            negative_ttl: Duration::from_secs(60),
             namespace: None,
             ttl_jitter: 0.1, // 10% jitter
+            measure_compute_cost: false,
+            early_refresh_beta: 1.0,
+            envelope: false,
+            schema_versions: HashMap::new(),
         }
     }
 }
@@ -62,6 +89,48 @@ impl CacheManagerConfig {
         self.ttl_jitter = 0.0;
         self
     }
+
+    /// Set the TTL for negative entries
+    pub fn negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Use measured compute time as the entry cost for cost-aware eviction
+    pub fn measure_compute_cost(mut self) -> Self {
+        self.measure_compute_cost = true;
+        self
+    }
+
+    /// Set the XFetch `beta` used by early refresh
+    pub fn early_refresh_beta(mut self, beta: f64) -> Self {
+        self.early_refresh_beta = beta;
+        self
+    }
+
+    /// Store values in an envelope, so serializer, compressor or schema
+    /// version changes are detected instead of failing to deserialize
+    pub fn envelope(mut self) -> Self {
+        self.envelope = true;
+        self
+    }
+
+    /// Store plain serializer output (unless compression or encryption is enabled)
+    pub fn no_envelope(mut self) -> Self {
+        self.envelope = false;
+        self
+    }
+
+    /// Set the schema version of `T`
+    ///
+    /// Bump it when the shape of `T` changes: entries written with another
+    /// version are then treated as misses and evicted instead of failing to
+    /// deserialize.
+    pub fn schema_version<T: ?Sized>(mut self, version: u32) -> Self {
+        self.schema_versions
+            .insert(std::any::type_name::<T>().to_string(), version);
+        self
+    }
 }
 
 /// High-level cache manager with pluggable serialization and metrics
@@ -81,6 +150,7 @@ where
     metrics: Arc<M>,
     config: CacheManagerConfig,
     coalescer: Coalescer,
+    codec: ValueCodec,
 }
 
 // Constructors for default serializer/metrics
@@ -96,6 +166,7 @@ impl<B: CacheBackend + DependencyBackend> CacheManager<B, JsonSerializer, NoopMe
             backend: Arc::new(backend),
             serializer: Arc::new(JsonSerializer),
             metrics: Arc::new(NoopMetrics),
+            codec: ValueCodec::new(config.envelope),
             config,
             coalescer: Coalescer::new(),
         }
@@ -120,11 +191,44 @@ where
             backend: Arc::new(backend),
             serializer: Arc::new(serializer),
             metrics: Arc::new(metrics),
+            codec: ValueCodec::new(config.envelope),
             config,
             coalescer: Coalescer::new(),
         }
     }
 
+    /// Compress stored values with `compressor`
+    ///
+    /// Values are compressed after serialization and carry a header flag, so
+    /// entries written before compression was enabled (or below the
+    /// compressor's size threshold) are still read back as is.
+    pub fn with_compressor<C: Compressor>(mut self, compressor: C) -> Self {
+        self.codec.set_compressor(compressor);
+        self
+    }
+
+    /// Encrypt stored values with `encryptor`
+    ///
+    /// Values are encrypted after serialization (and compression) and tagged
+    /// with the encryptor's key ID; the key they are stored under is
+    /// authenticated with them. Values that fail to decrypt are reported as
+    /// `CacheError::Decryption`; unencrypted values written before encryption
+    /// was enabled are still read.
+    pub fn with_encryptor<E: Encryptor>(mut self, encryptor: E) -> Self {
+        self.codec.set_encryptor(encryptor, false);
+        self
+    }
+
+    /// Encrypt stored values with `encryptor`, rejecting unencrypted ones
+    ///
+    /// Like `with_encryptor`, but values that were not encrypted, such as
+    /// those written before encryption was enabled or by a writer of the
+    /// backend without the key, fail with `CacheError::Decryption`.
+    pub fn with_required_encryptor<E: Encryptor>(mut self, encryptor: E) -> Self {
+        self.codec.set_encryptor(encryptor, true);
+        self
+    }
+
     /// Create a namespaced cache group
     pub fn group(&self, namespace: impl Into<String>) -> CacheGroup<'_, B, S, M> {
         CacheGroup::new(self, namespace.into())
@@ -150,6 +254,56 @@ where
         ttl
     }
 
+    /// Record the compute duration in the options
+    ///
+    /// Fills in the entry cost if configured, and the compute time of
+    /// early-refresh entries, which XFetch needs.
+    fn apply_compute_cost(&self, options: &mut CacheOptions, elapsed: Duration) {
+        if self.config.measure_compute_cost && options.cost.is_none() {
+            options.cost = Some((elapsed.as_micros() as u64).max(1));
+        }
+        if options.early_refresh && options.compute_time.is_none() {
+            options.compute_time = Some(elapsed);
+        }
+    }
+
+    /// XFetch: decide whether to recompute a fresh entry before it expires
+    ///
+    /// Refreshes when `delta * beta * -ln(rand) >= ttl_remaining`, where `delta`
+    /// is the entry's recorded compute time. The probability rises as expiry
+    /// nears and is higher for slow computations; entries without a compute
+    /// time are never refreshed early.
+    fn should_refresh_early<T>(&self, entry: &CacheEntry<T>) -> bool {
+        let (Some(remaining), Some(compute_time)) = (entry.ttl_remaining(), entry.compute_time) else {
+            return false;
+        };
+        let delta = compute_time.as_secs_f64();
+        // 1 - [0, 1) keeps the logarithm finite
+        let rand = 1.0 - rand::random::<f64>();
+        delta * self.config.early_refresh_beta * -rand.ln() >= remaining.as_secs_f64()
+    }
+
+    /// Recompute a key in the background, at most once at a time per key
+    fn spawn_refresh<T, F, Fut>(&self, full_key: &str, computer: F, mut options: CacheOptions)
+    where
+        T: serde::Serialize + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<T>> + Send + 'static,
+    {
+        let manager = self.clone();
+        let key = full_key.to_string();
+
+        self.coalescer.try_spawn_refresh(full_key, move || async move {
+            let compute_start = Instant::now();
+            if let Ok(val) = computer().await {
+                manager.apply_compute_cost(&mut options, compute_start.elapsed());
+                if let Ok(encoded) = manager.encode(&key, &val) {
+                    let _ = manager.set_raw(&key, encoded, options).await;
+                }
+            }
+        });
+    }
+
     /// Get a value from cache
     pub async fn get<T>(&self, key: impl CacheKey) -> Result<CacheResult<T>>
     where
@@ -167,18 +321,29 @@ where
         }).await?;
 
         let result = match req_result {
-            Some(entry) => {
-                if entry.is_expired() && !entry.is_stale() {
-                    self.metrics.record_miss(&full_key);
-                    CacheResult::Miss
-                } else if entry.is_stale() {
+            Some(entry) if entry.is_expired() && !entry.is_stale() => {
+                self.metrics.record_miss(&full_key);
+                CacheResult::Miss
+            }
+            Some(entry) if entry.negative => {
+                self.metrics.record_negative_hit(&full_key);
+                CacheResult::NegativeHit
+            }
+            Some(entry) => match self.deserialize_entry(&full_key, entry)? {
+                Some(entry) if entry.is_stale() => {
                     self.metrics.record_stale_hit(&full_key);
-                    CacheResult::Stale(self.deserialize_entry(entry)?)
-                } else {
+                    CacheResult::Stale(entry)
+                }
+                Some(entry) => {
                     self.metrics.record_hit(&full_key, CacheTier::L1Memory);
-                    CacheResult::Hit(self.deserialize_entry(entry)?)
+                    CacheResult::Hit(entry)
                 }
-            }
+                None => {
+                    self.evict_mismatched(&full_key).await;
+                    self.metrics.record_miss(&full_key);
+                    CacheResult::Miss
+                }
+            },
             None => {
                 self.metrics.record_miss(&full_key);
                 CacheResult::Miss
@@ -203,20 +368,55 @@ where
         let full_key = self.full_key(&key.full_key());
         let options = options.into();
 
-        // Serialize
-        let serialize_start = Instant::now();
-        let serialized = self.serializer.serialize(&value)?;
-        self.metrics
-            .record_latency(CacheOperation::Serialize, serialize_start.elapsed());
+        let encoded = self.encode(&full_key, &value)?;
+        self.set_raw(&full_key, encoded, options).await
+    }
 
-        self.set_raw(&full_key, serialized, options).await
+    /// Set a value only if the stored entry still has `expected_version`
+    ///
+    /// Versions start at 1 for a new key and grow by one on every write; pass 0
+    /// to insert only if the key is absent. Fails with `CacheError::VersionMismatch`
+    /// if another writer updated the key in the meantime.
+    pub async fn compare_and_set<T>(
+        &self,
+        key: impl CacheKey,
+        value: T,
+        expected_version: u64,
+        options: impl Into<CacheOptions>,
+    ) -> Result<()>
+    where
+        T: serde::Serialize,
+    {
+        let mut options = options.into();
+        options.if_version = Some(expected_version);
+        self.set(key, value, options).await
+    }
+
+    /// Record that a key is known to be missing
+    ///
+    /// Subsequent `get` calls return `CacheResult::NegativeHit` until the entry
+    /// expires, after `CacheManagerConfig::negative_ttl` unless `options` sets a TTL.
+    pub async fn set_negative(
+        &self,
+        key: impl CacheKey,
+        options: impl Into<CacheOptions>,
+    ) -> Result<()> {
+        let full_key = self.full_key(&key.full_key());
+        let mut options = options.into();
+        options.negative = true;
+
+        self.set_raw(&full_key, Vec::new(), options).await
     }
 
     /// Internal set with full logic (jitter, cascade, metrics)
     async fn set_raw(&self, full_key: &str, value: Vec<u8>, mut options: CacheOptions) -> Result<()> {
         // Apply default TTL if not specified
         if options.ttl.is_none() {
-            options.ttl = self.config.default_ttl;
+            options.ttl = if options.negative {
+                Some(self.config.negative_ttl)
+            } else {
+                self.config.default_ttl
+            };
         }
 
         // Apply jitter
@@ -245,6 +445,10 @@ where
     }
 
     /// Get a value from cache, or compute it if missing (coalesced)
+    ///
+    /// A cached negative entry is returned as `CacheResult::NegativeHit` without computing.
+    /// With `CacheOpts::early_refresh`, fresh hits are recomputed in the background with
+    /// a probability that rises as the entry nears expiry (XFetch).
     pub async fn get_or_compute<T, F, Fut>(
         &self,
         key: impl CacheKey,
@@ -265,54 +469,53 @@ where
         // Coalesce the request
         let req_result = self.coalescer.do_request(&full_key, move || async move {
              // 1. Check Backend
-             if let Some(entry) = backend.get(&key_str).await? {
+             let cached = match backend.get(&key_str).await? {
+                 // Written in another format: evict and recompute
+                 Some(entry) if !entry.negative && !manager.codec.matches(&entry.value, manager.envelope::<T>(&key_str)) => {
+                      manager.evict_mismatched(&key_str).await;
+                      None
+                 }
+                 cached => cached,
+             };
+             if let Some(entry) = cached {
                  if !entry.is_expired() {
+                      // XFetch: occasionally recompute before expiry
+                      if opts.early_refresh && !entry.negative && manager.should_refresh_early(&entry) {
+                           manager.spawn_refresh(&key_str, computer, opts);
+                      }
                       return Ok(Some(entry));
                  }
                  
                  // SWR Logic: If stale, trigger background refresh
                  if entry.is_stale() {
-                      let manager_bg = manager.clone();
-                      let key_bg = key_str.clone();
-                      let opts_bg = opts.clone();
-                      
-                      manager.coalescer.try_spawn_refresh(&key_str, move || async move {
-                           if let Ok(val) = computer().await {
-                                // Serialize depends on T. We need T to serialize!
-                                // Manager has serializer.
-                                // We call set_internal (set_raw).
-                                // But set_raw expects Vec<u8>.
-                                // CacheManager has serializer.
-                                // But `computer` returns T.
-                                // We need to serialize T.
-                                // `manager_bg.serializer.serialize(&val)`.
-                                if let Ok(serialized) = manager_bg.serializer.serialize(&val) {
-                                     let _ = manager_bg.set_raw(&key_bg, serialized, opts_bg).await;
-                                }
-                           }
-                      });
-                      
+                      manager.spawn_refresh(&key_str, computer, opts);
                       return Ok(Some(entry));
                  }
              }
              
              // 2. Compute (Miss case)
+             let compute_start = Instant::now();
              let val = computer().await?;
-             let serialized = manager.serializer.serialize(&val)?;
-             let size = serialized.len();
+             let mut opts = opts;
+             manager.apply_compute_cost(&mut opts, compute_start.elapsed());
+             let encoded = manager.encode(&key_str, &val)?;
+             let size = encoded.len();
              
              // 3. Set (using set_raw for full logic)
-             manager.set_raw(&key_str, serialized.clone(), opts).await?;
+             manager.set_raw(&key_str, encoded.clone(), opts).await?;
              
-             Ok(Some(CacheEntry::new(serialized, size)))
+             Ok(Some(CacheEntry::new(encoded, size)))
         }).await?;
 
         match req_result {
-            Some(entry) => {
-                if entry.is_stale() {
-                    Ok(CacheResult::Stale(self.deserialize_entry(entry)?))
-                } else {
-                    Ok(CacheResult::Hit(self.deserialize_entry(entry)?))
+            Some(entry) if entry.negative => Ok(CacheResult::NegativeHit),
+            Some(entry) => match self.deserialize_entry(&full_key, entry)? {
+                Some(entry) if entry.is_stale() => Ok(CacheResult::Stale(entry)),
+                Some(entry) => Ok(CacheResult::Hit(entry)),
+                // A concurrent caller shared an entry of another type
+                None => {
+                    self.evict_mismatched(&full_key).await;
+                    Ok(CacheResult::Miss)
                 }
             },
             None => Err(skp_cache_core::CacheError::Internal("Compute returned None".into()))
@@ -347,37 +550,14 @@ where
     }
 
     /// Recursive invalidation of dependents
+    ///
+    /// Delegates to `DependencyBackend::delete_cascade`, which backends such
+    /// as Redis run server-side in one atomic step.
     /// Returns (initial_key_deleted, total_count)
     async fn invalidate_recursive(&self, key: &str) -> Result<(bool, u64)> {
-        let mut queue = VecDeque::new();
-        queue.push_back(key.to_string());
-        let mut visited = HashSet::new();
-        visited.insert(key.to_string());
-        
-        let mut initial_deleted = false;
-        let mut first = true;
-        let mut count = 0u64;
-        
-        while let Some(k) = queue.pop_front() {
-             // Get dependents first
-             if let Ok(deps) = self.backend.get_dependents(&k).await {
-                  for dep in deps {
-                      if visited.insert(dep.clone()) {
-                          queue.push_back(dep);
-                      }
-                  }
-             }
-             // Delete
-             let deleted = self.backend.delete(&k).await?;
-             if deleted {
-                 count += 1;
-             }
-             if first {
-                 initial_deleted = deleted;
-                 first = false;
-             }
-        }
-        Ok((initial_deleted, count))
+        let deleted = self.backend.delete_cascade(key).await?;
+        let initial_deleted = deleted.first().is_some_and(|k| k == key);
+        Ok((initial_deleted, deleted.len() as u64))
     }
 
     /// Check if key exists in cache
@@ -406,30 +586,81 @@ where
         self.backend.is_empty().await
     }
 
+    /// Envelope expected for values of type `T` stored under `full_key`
+    fn envelope<'a, T: ?Sized>(&'a self, full_key: &'a str) -> Envelope<'a> {
+        Envelope {
+            key: full_key,
+            serializer: self.serializer.name(),
+            schema_version: self.schema_version::<T>(),
+        }
+    }
+
+    /// Schema version registered for `T`
+    fn schema_version<T: ?Sized>(&self) -> u32 {
+        self.config
+            .schema_versions
+            .get(std::any::type_name::<T>())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Remove an entry written in another format, so the next read is a plain miss
+    async fn evict_mismatched(&self, full_key: &str) {
+        let _ = self.backend.delete(full_key).await;
+        self.metrics.record_eviction(EvictionReason::FormatMismatch);
+    }
+
+    /// Serialize a value into the bytes handed to the backend
+    fn encode<T: serde::Serialize>(&self, full_key: &str, value: &T) -> Result<Vec<u8>> {
+        let serialize_start = Instant::now();
+        let serialized = self.serializer.serialize(value)?;
+        let encoded = self.codec.encode(serialized, self.envelope::<T>(full_key))?;
+        self.metrics
+            .record_latency(CacheOperation::Serialize, serialize_start.elapsed());
+        Ok(encoded)
+    }
+
     /// Deserialize a cache entry
-    fn deserialize_entry<T>(&self, entry: CacheEntry<Vec<u8>>) -> Result<CacheEntry<T>>
+    ///
+    /// Returns `None` if the entry was written with another serializer,
+    /// compressor or schema version.
+    fn deserialize_entry<T>(
+        &self,
+        full_key: &str,
+        entry: CacheEntry<Vec<u8>>,
+    ) -> Result<Option<CacheEntry<T>>>
     where
         T: serde::de::DeserializeOwned,
     {
         let deserialize_start = Instant::now();
-        let value: T = self.serializer.deserialize(&entry.value)?;
+        let Some(bytes) = self.codec.decode(&entry.value, self.envelope::<T>(full_key))? else {
+            return Ok(None);
+        };
+        let value: T = self.serializer.deserialize(&bytes)?;
         self.metrics
             .record_latency(CacheOperation::Deserialize, deserialize_start.elapsed());
 
-        Ok(CacheEntry {
-            value,
-            created_at: entry.created_at,
-            last_accessed: entry.last_accessed,
-            access_count: entry.access_count,
-            ttl: entry.ttl,
-            stale_while_revalidate: entry.stale_while_revalidate,
-            tags: entry.tags,
-            dependencies: entry.dependencies,
-            cost: entry.cost,
-            size: entry.size,
-            etag: entry.etag,
-            version: entry.version,
-        })
+        Ok(Some(with_value(entry, value)))
+    }
+}
+
+/// Replace the value of an entry, keeping its metadata
+fn with_value<T, U>(entry: CacheEntry<U>, value: T) -> CacheEntry<T> {
+    CacheEntry {
+        value,
+        created_at: entry.created_at,
+        last_accessed: entry.last_accessed,
+        access_count: entry.access_count,
+        ttl: entry.ttl,
+        stale_while_revalidate: entry.stale_while_revalidate,
+        tags: entry.tags,
+        dependencies: entry.dependencies,
+        cost: entry.cost,
+        compute_time: entry.compute_time,
+        size: entry.size,
+        etag: entry.etag,
+        version: entry.version,
+        negative: entry.negative,
     }
 }
 
@@ -446,6 +677,7 @@ where
             metrics: self.metrics.clone(),
             config: self.config.clone(),
             coalescer: self.coalescer.clone(),
+            codec: self.codec.clone(),
         }
     }
 }
@@ -471,3 +703,28 @@ where
         self.backend.get_by_tag(tag).await
     }
 }
+
+// Pattern operations
+impl<B, S, M> CacheManager<B, S, M>
+where
+    B: CacheBackend + DependencyBackend + PatternBackend,
+    S: Serializer,
+    M: CacheMetrics,
+{
+    /// Delete all entries whose key matches a glob pattern
+    ///
+    /// The pattern is matched within the manager's namespace, e.g.
+    /// `"user:*:posts"`; see `KeyPattern` for the syntax. Dependents of the
+    /// deleted entries are not invalidated.
+    pub async fn invalidate_pattern(&self, pattern: &str) -> Result<u64> {
+        let start = Instant::now();
+        let pattern = match &self.config.namespace {
+            Some(ns) => format!("{}:{}", KeyPattern::escape(ns), pattern),
+            None => pattern.to_string(),
+        };
+        let count = self.backend.delete_by_pattern(&pattern).await?;
+        self.metrics
+            .record_latency(CacheOperation::Invalidate, start.elapsed());
+        Ok(count)
+    }
+}
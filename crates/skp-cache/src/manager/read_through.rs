@@ -3,6 +3,7 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::time::Instant;
 
 use skp_cache_core::{
     CacheBackend, CacheError, CacheKey, CacheMetrics, CacheOptions, CacheResult, DependencyBackend,
@@ -51,25 +52,43 @@ where
     }
 
     /// Get value from cache, or load it automatically if missing
+    ///
+    /// A loader result of `Ok(None)` is cached as a negative entry, so repeated
+    /// lookups for a missing key skip the loader until the negative TTL expires.
     pub async fn get(&self, key: K) -> Result<Option<V>> {
         // 1. Try to get from cache
         match self.manager.get::<V>(key.clone()).await? {
-            CacheResult::Hit(entry) => Ok(Some(entry.value)),
+            CacheResult::Hit(entry) => {
+                // XFetch: occasionally reload before expiry
+                if self.options.early_refresh && self.manager.should_refresh_early(&entry) {
+                    self.refresh_background(key);
+                }
+                Ok(Some(entry.value))
+            }
             CacheResult::Stale(entry) => {
                 // If stale, serve it but trigger background refresh
                 self.refresh_background(key.clone());
                 Ok(Some(entry.value))
             }
-            CacheResult::Miss | CacheResult::NegativeHit => {
+            CacheResult::NegativeHit => Ok(None),
+            CacheResult::Miss => {
                 // 2. Load from source (coalesced via get_or_compute)
                 let loader = self.loader.clone();
+                let manager = self.manager.clone();
+                let negative_options = self.negative_options();
                 let key_clone = key.clone();
                 
                 let result = self.manager.get_or_compute(
                     key,
                     move || async move {
-                        loader.load(&key_clone).await?
-                             .ok_or_else(|| CacheError::NotFound("Loader returned None".into()))
+                        match loader.load(&key_clone).await? {
+                            Some(val) => Ok(val),
+                            None => {
+                                // Remember the miss so the source isn't asked again
+                                manager.set_negative(key_clone, negative_options).await?;
+                                Err(CacheError::NotFound("Loader returned None".into()))
+                            }
+                        }
                     },
                     Some(self.options.clone())
                 ).await;
@@ -87,21 +106,40 @@ where
 
     /// Force refresh a key using the loader
     pub async fn refresh(&self, key: K) -> Result<()> {
-        if let Some(val) = self.loader.load(&key).await? {
-            self.manager.set(key, val, self.options.clone()).await?;
+        match self.loader.load(&key).await? {
+            Some(val) => self.manager.set(key, val, self.options.clone()).await,
+            None => self.manager.set_negative(key, self.negative_options()).await,
+        }
+    }
+
+    /// Options for negative entries: keep tags and dependencies, use the negative TTL
+    fn negative_options(&self) -> CacheOptions {
+        CacheOptions {
+            tags: self.options.tags.clone(),
+            dependencies: self.options.dependencies.clone(),
+            ..Default::default()
         }
-        Ok(())
     }
 
-    /// Trigger background refresh
+    /// Trigger background refresh (at most one per key at a time)
     fn refresh_background(&self, key: K) {
         let loader = self.loader.clone();
         let manager = self.manager.clone();
-        let options = self.options.clone();
+        let mut options = self.options.clone();
+        let negative_options = self.negative_options();
+        let full_key = self.manager.full_key(&key.full_key());
 
-        tokio::spawn(async move {
-            if let Ok(Some(val)) = loader.load(&key).await {
-                let _ = manager.set(key, val, options).await;
+        self.manager.coalescer.try_spawn_refresh(&full_key, move || async move {
+            let load_start = Instant::now();
+            match loader.load(&key).await {
+                Ok(Some(val)) => {
+                    manager.apply_compute_cost(&mut options, load_start.elapsed());
+                    let _ = manager.set(key, val, options).await;
+                }
+                Ok(None) => {
+                    let _ = manager.set_negative(key, negative_options).await;
+                }
+                Err(_) => {}
             }
         });
     }
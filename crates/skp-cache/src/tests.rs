@@ -1,8 +1,12 @@
 //! Integration tests for CacheManager
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::prelude::*;
+    use crate::{CacheMetrics, NoopMetrics};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::time::Duration;
 
     #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -81,6 +85,28 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_invalidate_pattern() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let config = CacheManagerConfig {
+            namespace: Some("app[1]".to_string()),
+            ..Default::default()
+        };
+        let cache = CacheManager::with_config(backend.clone(), config);
+        let other = CacheManager::new(backend);
+
+        for key in ["user:1:posts", "user:2:posts", "user:1:profile"] {
+            cache.set(key, &1i32, CacheOpts::new()).await.unwrap();
+        }
+        other.set("user:3:posts", &1i32, CacheOpts::new()).await.unwrap();
+
+        assert_eq!(cache.invalidate_pattern("user:*:posts").await.unwrap(), 2);
+        assert!(!cache.exists("user:1:posts").await.unwrap());
+        assert!(cache.exists("user:1:profile").await.unwrap());
+        // Outside the namespace
+        assert!(other.exists("user:3:posts").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_with_ttl() {
         let backend = MemoryBackend::new(MemoryConfig::default());
@@ -174,6 +200,571 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_get_or_compute_records_compute_cost() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let cache =
+            CacheManager::with_config(backend, CacheManagerConfig::default().measure_compute_cost());
+
+        cache
+            .get_or_compute(
+                "slow",
+                || async {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok(1i32)
+                },
+                None,
+            )
+            .await
+            .unwrap();
+        cache
+            .get_or_compute("fixed", || async { Ok(2i32) }, Some(CacheOpts::new().cost(7).build()))
+            .await
+            .unwrap();
+
+        let slow = cache.get::<i32>("slow").await.unwrap().entry().unwrap();
+        assert!(slow.cost >= 20_000, "cost was {}", slow.cost);
+
+        // An explicit cost is never overridden
+        let fixed = cache.get::<i32>("fixed").await.unwrap().entry().unwrap();
+        assert_eq!(fixed.cost, 7);
+    }
+
+    #[tokio::test]
+    async fn test_set_negative() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let config = CacheManagerConfig::default().negative_ttl(Duration::from_millis(100));
+        let cache = CacheManager::with_config(backend, config);
+
+        cache.set_negative("user:404", CacheOpts::new()).await.unwrap();
+        assert!(cache.get::<TestData>("user:404").await.unwrap().is_negative());
+
+        // get_or_compute trusts the negative entry
+        let result = cache
+            .get_or_compute("user:404", || async { Ok(1i32) }, None)
+            .await
+            .unwrap();
+        assert!(result.is_negative());
+
+        // Negative TTL applies, not the default TTL
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(cache.get::<TestData>("user:404").await.unwrap().is_miss());
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let cache = CacheManager::new(backend);
+
+        // 0 = only if absent
+        cache.compare_and_set("counter", &1i32, 0, CacheOpts::new()).await.unwrap();
+        let entry = cache.get::<i32>("counter").await.unwrap().entry().unwrap();
+        assert_eq!(entry.version, 1);
+
+        cache.set("counter", &2i32, CacheOpts::new()).await.unwrap();
+        let err = cache
+            .compare_and_set("counter", &3i32, entry.version, CacheOpts::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CacheError::VersionMismatch {
+                expected: 1,
+                actual: 2
+            }
+        ));
+        assert_eq!(cache.get::<i32>("counter").await.unwrap().value(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set_concurrent_increments() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let cache = CacheManager::new(backend);
+        cache.set("counter", &0i32, CacheOpts::new()).await.unwrap();
+
+        let mut tasks = Vec::new();
+        for _ in 0..8 {
+            let cache = cache.clone();
+            tasks.push(tokio::spawn(async move {
+                for _ in 0..25 {
+                    loop {
+                        let entry = cache.get::<i32>("counter").await.unwrap().entry().unwrap();
+                        match cache
+                            .compare_and_set("counter", &(entry.value + 1), entry.version, CacheOpts::new())
+                            .await
+                        {
+                            Ok(()) => break,
+                            Err(CacheError::VersionMismatch { .. }) => tokio::task::yield_now().await,
+                            Err(e) => panic!("{}", e),
+                        }
+                    }
+                }
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(cache.get::<i32>("counter").await.unwrap().value(), Some(200));
+    }
+
+    struct CountingLoader {
+        calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Loader<String, TestData> for CountingLoader {
+        async fn load(&self, key: &String) -> Result<Option<TestData>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if key == "found" {
+                Ok(Some(TestData {
+                    id: 1,
+                    name: key.clone(),
+                    value: 1,
+                }))
+            } else {
+                Ok(None)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_through_early_refresh() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let config = CacheManagerConfig::default().early_refresh_beta(1e12);
+        let cache = CacheManager::with_config(backend, config).read_through(
+            CountingLoader {
+                calls: calls.clone(),
+            },
+            CacheOpts::new().ttl_secs(60).early_refresh().build(),
+        );
+
+        assert!(cache.get("found".to_string()).await.unwrap().is_some());
+        assert!(cache.get("found".to_string()).await.unwrap().is_some());
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_through_caches_missing_rows() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let cache = CacheManager::new(backend).read_through(
+            CountingLoader {
+                calls: calls.clone(),
+            },
+            CacheOpts::new().ttl_secs(60).build(),
+        );
+
+        for _ in 0..3 {
+            assert!(cache.get("missing".to_string()).await.unwrap().is_none());
+            assert!(cache.get("found".to_string()).await.unwrap().is_some());
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_compute_early_refresh() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        // A huge beta makes the XFetch check fire on every fresh hit
+        let config = CacheManagerConfig::default().early_refresh_beta(1e12);
+        let cache = CacheManager::with_config(backend.clone(), config);
+        let opts = CacheOpts::new().ttl_secs(60).early_refresh().build();
+
+        cache
+            .get_or_compute("plain", || async { Ok(1i32) }, Some(CacheOpts::new().ttl_secs(60).build()))
+            .await
+            .unwrap();
+        cache
+            .get_or_compute("early", || async { Ok(1i32) }, Some(opts.clone()))
+            .await
+            .unwrap();
+
+        // The compute time is recorded apart from the eviction cost
+        let stored = crate::CacheBackend::get(&backend, "early").await.unwrap().unwrap();
+        assert!(stored.compute_time.is_some());
+        assert_eq!(stored.cost, 1);
+
+        // Hits are served immediately while the recompute runs in the background
+        let plain = cache
+            .get_or_compute("plain", || async { Ok(2i32) }, Some(CacheOpts::new().ttl_secs(60).build()))
+            .await
+            .unwrap();
+        let early = cache
+            .get_or_compute("early", || async { Ok(2i32) }, Some(opts))
+            .await
+            .unwrap();
+        assert_eq!(plain.value(), Some(1));
+        assert_eq!(early.value(), Some(1));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(cache.get::<i32>("plain").await.unwrap().value(), Some(1));
+        assert_eq!(cache.get::<i32>("early").await.unwrap().value(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_compressor_reads_uncompressed_values() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let plain = CacheManager::new(backend.clone());
+        let with_header = CacheManager::new(backend.clone()).with_compressor(crate::NoopCompressor);
+
+        plain.set("legacy", &1i32, CacheOpts::new()).await.unwrap();
+        with_header.set("new", &2i32, CacheOpts::new()).await.unwrap();
+
+        assert_eq!(with_header.get::<i32>("legacy").await.unwrap().value(), Some(1));
+        assert_eq!(with_header.get::<i32>("new").await.unwrap().value(), Some(2));
+        // Uncompressed values stay readable without a compressor
+        assert_eq!(plain.get::<i32>("new").await.unwrap().value(), Some(2));
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_zstd_compression() {
+        use crate::{CacheBackend, ZstdCompressor};
+
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let plain = CacheManager::new(backend.clone());
+        let zstd = CacheManager::new(backend.clone()).with_compressor(ZstdCompressor::default());
+
+        let big = "payload ".repeat(200);
+        plain.set("legacy", &big, CacheOpts::new()).await.unwrap();
+        zstd.set("big", &big, CacheOpts::new()).await.unwrap();
+        zstd.set("small", &"tiny", CacheOpts::new()).await.unwrap();
+
+        let stored = backend.get("big").await.unwrap().unwrap();
+        assert!(stored.value.len() < big.len() / 4);
+
+        assert_eq!(zstd.get::<String>("big").await.unwrap().value(), Some(big.clone()));
+        assert_eq!(zstd.get::<String>("legacy").await.unwrap().value(), Some(big));
+        assert_eq!(zstd.get::<String>("small").await.unwrap().value(), Some("tiny".to_string()));
+
+        // Compressed values need the compressor; without it they are misses
+        assert!(plain.get::<String>("big").await.unwrap().is_miss());
+        assert!(!backend.exists("big").await.unwrap());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn test_encryption_and_key_rotation() {
+        use crate::{AeadAlgorithm, AeadEncryptor, CacheBackend};
+
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let old_key = AeadEncryptor::new(AeadAlgorithm::Aes256Gcm, 1, [1; 32]);
+        let old = CacheManager::new(backend.clone()).with_encryptor(old_key.clone());
+        let rotated = CacheManager::new(backend.clone())
+            .with_encryptor(old_key.with_active_key(2, [2; 32]));
+
+        old.set("a", &"secret-a", CacheOpts::new()).await.unwrap();
+        rotated.set("b", &"secret-b", CacheOpts::new()).await.unwrap();
+
+        let stored = backend.get("a").await.unwrap().unwrap();
+        assert!(!stored.value.windows(8).any(|w| w == b"secret-a"));
+
+        // The rotated manager still reads values under the old key
+        assert_eq!(rotated.get::<String>("a").await.unwrap().value(), Some("secret-a".to_string()));
+        assert_eq!(rotated.get::<String>("b").await.unwrap().value(), Some("secret-b".to_string()));
+
+        // Unknown key and missing encryptor are decryption errors
+        assert!(matches!(old.get::<String>("b").await, Err(CacheError::Decryption(_))));
+        let plain = CacheManager::new(backend.clone());
+        assert!(matches!(plain.get::<String>("a").await, Err(CacheError::Decryption(_))));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn test_tampered_value_fails_to_decrypt() {
+        use crate::{AeadAlgorithm, AeadEncryptor, CacheBackend, CacheOptions};
+
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let cache = CacheManager::new(backend.clone())
+            .with_encryptor(AeadEncryptor::new(AeadAlgorithm::ChaCha20Poly1305, 7, [9; 32]));
+        cache.set("key", &"secret", CacheOpts::new()).await.unwrap();
+
+        let mut stored = backend.get("key").await.unwrap().unwrap().value;
+        let last = stored.len() - 1;
+        stored[last] ^= 1;
+        backend.set("key", stored, &CacheOptions::default()).await.unwrap();
+
+        assert!(matches!(cache.get::<String>("key").await, Err(CacheError::Decryption(_))));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn test_encrypted_value_is_bound_to_its_key() {
+        use crate::{AeadAlgorithm, AeadEncryptor, CacheBackend};
+
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let cache = CacheManager::new(backend.clone())
+            .with_encryptor(AeadEncryptor::new(AeadAlgorithm::Aes256Gcm, 1, [1; 32]));
+        cache.set("alice", &"alice-secret", CacheOpts::new()).await.unwrap();
+
+        // Copying the ciphertext under another key does not decrypt
+        let stored = backend.get("alice").await.unwrap().unwrap();
+        backend.set("mallory", stored.value, &Default::default()).await.unwrap();
+        assert!(matches!(cache.get::<String>("mallory").await, Err(CacheError::Decryption(_))));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn test_required_encryption_rejects_unencrypted_values() {
+        use crate::{AeadAlgorithm, AeadEncryptor};
+
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let key = AeadEncryptor::new(AeadAlgorithm::Aes256Gcm, 1, [1; 32]);
+        let plain = CacheManager::new(backend.clone());
+        let enveloped = CacheManager::with_config(backend.clone(), CacheManagerConfig::default().envelope());
+        let lenient = CacheManager::new(backend.clone()).with_encryptor(key.clone());
+        let strict = CacheManager::new(backend.clone()).with_required_encryptor(key);
+
+        plain.set("raw", &"raw-value", CacheOpts::new()).await.unwrap();
+        enveloped.set("enveloped", &"enveloped-value", CacheOpts::new()).await.unwrap();
+        strict.set("encrypted", &"secret", CacheOpts::new()).await.unwrap();
+
+        assert_eq!(lenient.get::<String>("raw").await.unwrap().value(), Some("raw-value".to_string()));
+        assert!(matches!(strict.get::<String>("raw").await, Err(CacheError::Decryption(_))));
+        assert!(matches!(strict.get::<String>("enveloped").await, Err(CacheError::Decryption(_))));
+        assert_eq!(strict.get::<String>("encrypted").await.unwrap().value(), Some("secret".to_string()));
+    }
+
+    #[derive(Clone, Default)]
+    struct MismatchCounter {
+        evictions: Arc<AtomicUsize>,
+    }
+
+    impl CacheMetrics for MismatchCounter {
+        fn record_hit(&self, _key: &str, _tier: crate::CacheTier) {}
+        fn record_miss(&self, _key: &str) {}
+        fn record_stale_hit(&self, _key: &str) {}
+        fn record_latency(&self, _op: crate::CacheOperation, _duration: Duration) {}
+        fn record_eviction(&self, reason: crate::EvictionReason) {
+            if reason == crate::EvictionReason::FormatMismatch {
+                self.evictions.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        fn record_size(&self, _size: usize, _memory_bytes: usize) {}
+    }
+
+    #[derive(Clone, Default)]
+    struct HitCounter {
+        hits: Arc<AtomicUsize>,
+        negative_hits: Arc<AtomicUsize>,
+    }
+
+    impl CacheMetrics for HitCounter {
+        fn record_hit(&self, _key: &str, _tier: crate::CacheTier) {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+        }
+        fn record_miss(&self, _key: &str) {}
+        fn record_stale_hit(&self, _key: &str) {}
+        fn record_negative_hit(&self, _key: &str) {
+            self.negative_hits.fetch_add(1, Ordering::SeqCst);
+        }
+        fn record_latency(&self, _op: crate::CacheOperation, _duration: Duration) {}
+        fn record_eviction(&self, _reason: crate::EvictionReason) {}
+        fn record_size(&self, _size: usize, _memory_bytes: usize) {}
+    }
+
+    #[tokio::test]
+    async fn test_negative_hits_are_not_counted_as_hits() {
+        let metrics = HitCounter::default();
+        let cache = CacheManager::with_serializer_and_metrics(
+            MemoryBackend::new(MemoryConfig::default()),
+            JsonSerializer,
+            metrics.clone(),
+            CacheManagerConfig::default(),
+        );
+
+        cache.set_negative("missing", CacheOpts::new()).await.unwrap();
+        cache.set("present", &1i32, CacheOpts::new()).await.unwrap();
+        assert!(cache.get::<i32>("missing").await.unwrap().is_negative());
+        assert!(cache.get::<i32>("present").await.unwrap().is_hit());
+
+        assert_eq!(metrics.negative_hits.load(Ordering::SeqCst), 1);
+        assert_eq!(metrics.hits.load(Ordering::SeqCst), 1);
+    }
+
+    /// JSON under another name, standing in for a different serializer
+    #[derive(Clone)]
+    struct OtherSerializer;
+
+    impl Serializer for OtherSerializer {
+        fn name(&self) -> &str {
+            "other"
+        }
+
+        fn serialize<T: serde::Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+            JsonSerializer.serialize(value)
+        }
+
+        fn deserialize<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+            JsonSerializer.deserialize(bytes)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schema_version_mismatch_is_miss() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let v1 = CacheManager::with_config(backend.clone(), CacheManagerConfig::default().envelope());
+        let metrics = MismatchCounter::default();
+        let v2 = CacheManager::with_serializer_and_metrics(
+            backend.clone(),
+            JsonSerializer,
+            metrics.clone(),
+            CacheManagerConfig::default()
+                .envelope()
+                .schema_version::<TestData>(2),
+        );
+
+        let data = TestData {
+            id: 1,
+            name: "test".to_string(),
+            value: 42,
+        };
+        v1.set("key", &data, CacheOpts::new()).await.unwrap();
+
+        assert!(v2.get::<TestData>("key").await.unwrap().is_miss());
+        assert_eq!(metrics.evictions.load(Ordering::SeqCst), 1);
+        assert!(!v1.exists("key").await.unwrap());
+
+        // Other types keep version 0
+        v1.set("other", &1i32, CacheOpts::new()).await.unwrap();
+        assert_eq!(v2.get::<i32>("other").await.unwrap().value(), Some(1));
+
+        // get_or_compute recomputes instead of failing
+        v1.set("key", &data, CacheOpts::new()).await.unwrap();
+        let fresh = TestData { value: 43, ..data.clone() };
+        let result = v2
+            .get_or_compute("key", move || async move { Ok(fresh) }, None)
+            .await
+            .unwrap();
+        assert_eq!(result.value().map(|d| d.value), Some(43));
+        assert_eq!(metrics.evictions.load(Ordering::SeqCst), 2);
+        assert_eq!(v2.get::<TestData>("key").await.unwrap().value().map(|d| d.value), Some(43));
+    }
+
+    #[tokio::test]
+    async fn test_serializer_mismatch_is_miss() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let json = CacheManager::with_config(backend.clone(), CacheManagerConfig::default().envelope());
+        let other = CacheManager::with_serializer_and_metrics(
+            backend.clone(),
+            OtherSerializer,
+            NoopMetrics,
+            CacheManagerConfig::default().envelope(),
+        );
+
+        json.set("key", &1i32, CacheOpts::new()).await.unwrap();
+        assert!(other.get::<i32>("key").await.unwrap().is_miss());
+        assert!(!json.exists("key").await.unwrap());
+
+        // Values written without an envelope are read as before
+        let raw = CacheManager::new(backend.clone());
+        raw.set("raw", &2i32, CacheOpts::new()).await.unwrap();
+        assert_eq!(other.get::<i32>("raw").await.unwrap().value(), Some(2));
+    }
+
+    #[cfg(feature = "bincode")]
+    #[tokio::test]
+    async fn test_plain_values_starting_with_envelope_magic() {
+        let backend = MemoryBackend::new(MemoryConfig::default());
+        let plain = CacheManager::with_serializer_and_metrics(
+            backend.clone(),
+            BincodeSerializer,
+            NoopMetrics,
+            CacheManagerConfig::default(),
+        );
+        let enveloped = CacheManager::with_serializer_and_metrics(
+            backend,
+            BincodeSerializer,
+            NoopMetrics,
+            CacheManagerConfig::default().envelope(),
+        );
+        // Bincode writes `u8` fields as is: values starting with the envelope
+        // magic, then an unknown format version or a version 1 header
+        let unknown = (0xFFu8, 0x53u8, "payload".to_string());
+        let header = (0xFFu8, 0x53u8, 1u8, 0u32, "payload".to_string());
+        assert!(BincodeSerializer.serialize(&unknown).unwrap().starts_with(&[0xFF, 0x53, 7]));
+        assert!(BincodeSerializer.serialize(&header).unwrap().starts_with(&[0xFF, 0x53, 1]));
+
+        plain.set("unknown", &unknown, CacheOpts::new()).await.unwrap();
+        plain.set("header", &header, CacheOpts::new()).await.unwrap();
+        assert_eq!(plain.get::<(u8, u8, String)>("unknown").await.unwrap().value(), Some(unknown.clone()));
+        let result = plain
+            .get_or_compute("unknown", || async { Ok((0u8, 0u8, String::new())) }, None)
+            .await
+            .unwrap();
+        assert_eq!(result.value(), Some(unknown));
+
+        // Without a valid checksum the header is not taken for an envelope
+        type Header = (u8, u8, u8, u32, String);
+        assert_eq!(plain.get::<Header>("header").await.unwrap().value(), Some(header.clone()));
+        assert_eq!(enveloped.get::<Header>("header").await.unwrap().value(), Some(header));
+    }
+
+    #[cfg(all(feature = "postcard", feature = "cbor"))]
+    #[tokio::test]
+    async fn test_binary_serializers() {
+        let data = TestData {
+            id: 1,
+            name: "test".to_string(),
+            value: 42,
+        };
+
+        let postcard = CacheManager::with_serializer_and_metrics(
+            MemoryBackend::new(MemoryConfig::default()),
+            PostcardSerializer,
+            NoopMetrics,
+            CacheManagerConfig::default(),
+        );
+        postcard.set("key", &data, CacheOpts::new()).await.unwrap();
+        assert_eq!(postcard.get::<TestData>("key").await.unwrap().value(), Some(data.clone()));
+
+        let cbor = CacheManager::with_serializer_and_metrics(
+            MemoryBackend::new(MemoryConfig::default()),
+            CborSerializer,
+            NoopMetrics,
+            CacheManagerConfig::default(),
+        );
+        cbor.set("key", &data, CacheOpts::new()).await.unwrap();
+        assert_eq!(cbor.get::<TestData>("key").await.unwrap().value(), Some(data));
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[tokio::test]
+    async fn test_archived_values() {
+        use crate::rkyv::{Archive, Deserialize, Serialize};
+
+        #[derive(Debug, PartialEq, Archive, Serialize, Deserialize)]
+        #[rkyv(crate = crate::rkyv)]
+        struct Catalog {
+            version: u32,
+            items: Vec<String>,
+        }
+
+        let cache = CacheManager::with_config(
+            MemoryBackend::new(MemoryConfig::default()),
+            CacheManagerConfig::default().envelope(),
+        );
+        let catalog = Catalog {
+            version: 3,
+            items: vec!["a".to_string(), "b".to_string()],
+        };
+        cache.set_archived("catalog", &catalog, CacheOpts::new()).await.unwrap();
+
+        let archived = cache.get_archived::<Catalog>("catalog").await.unwrap().value().unwrap();
+        assert_eq!(archived.version, 3);
+        assert_eq!(archived.items[1], "b");
+        assert_eq!(archived.deserialize().unwrap(), catalog);
+
+        assert!(cache.get_archived::<Catalog>("missing").await.unwrap().is_miss());
+
+        // serde reads see a different serializer in the envelope
+        assert!(cache.get::<Vec<String>>("catalog").await.unwrap().is_miss());
+        assert!(!cache.exists("catalog").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_clone() {
         let backend = MemoryBackend::new(MemoryConfig::default());